@@ -0,0 +1,55 @@
+//Measures how many instructions per second the CPU gets through.
+//Run with `cargo bench --bench cpu`
+
+use std::time::{Duration, Instant};
+
+use nes_emulator::Nes;
+
+const INSTRUCTIONS: u64 = 5_000_000;
+const RUNS: usize = 5;
+
+//An NROM image that loops over a page of RAM forever, mixing loads, stores, arithmetic and branches
+fn rom() -> Vec<u8> {
+    let program: &[u8] = &[
+        0xA2, 0x00,       //$8000 LDX #$00
+        0xBD, 0x00, 0x02, //$8002 LDA $0200,X
+        0x18,             //$8005 CLC
+        0x69, 0x01,       //$8006 ADC #$01
+        0x9D, 0x00, 0x02, //$8008 STA $0200,X
+        0xE8,             //$800B INX
+        0xD0, 0xF4,       //$800C BNE $8002
+        0x4C, 0x00, 0x80  //$800E JMP $8000
+    ];
+
+    let mut prg = vec![0xEA; 16384];
+    prg[..program.len()].copy_from_slice(program);
+    //Reset vector
+    prg[0x3FFC] = 0x00;
+    prg[0x3FFD] = 0x80;
+
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(prg);
+    rom.extend(vec![0; 8192]);
+    rom
+}
+
+fn run(nes: &mut Nes) -> Duration {
+    let start = Instant::now();
+    for _ in 0..INSTRUCTIONS {
+        nes.step_instruction().unwrap();
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let mut nes = Nes::from_rom_bytes(&rom()).unwrap();
+    //Warm up
+    run(&mut nes);
+
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        best = best.min(run(&mut nes));
+    }
+    let per_second = INSTRUCTIONS as f64 / best.as_secs_f64();
+    println!("{} instructions in {:?}, {:.2}M instructions/s", INSTRUCTIONS, best, per_second / 1_000_000.0);
+}
@@ -0,0 +1,72 @@
+//What the CPU sees of the outside world. Every CPU cycle is one tick followed by one read or write
+pub trait Bus {
+    fn read(&mut self, address: u16) -> u8;
+
+    fn write(&mut self, address: u16, data: u8);
+
+    //Reads without side effects, for debuggers and trace logs
+    fn peek(&self, address: u16) -> u8;
+
+    //Called at the start of every CPU cycle so the rest of the system can keep pace.
+    //Returns true when the run loop should come up for air, like when the NES finishes a frame
+    fn tick(&mut self) -> bool {
+        false
+    }
+
+    //The /NMI and /IRQ lines, true when they're being pulled low
+    fn nmi(&self) -> bool {
+        false
+    }
+
+    fn irq(&self) -> bool {
+        false
+    }
+
+    //Something wants the CPU off the bus, like the NES's sprite DMA. The CPU stops at its next read
+    //and hands its cycles over to dma_cycle until that's done
+    fn dma_pending(&self) -> bool {
+        false
+    }
+
+    //One cycle of DMA. get is true on the cycles it can read on. Returns true on the last cycle
+    fn dma_cycle(&mut self, _get: bool) -> bool {
+        true
+    }
+
+    //The reset line, for anything that's wired to it along with the CPU
+    fn reset(&mut self) {}
+}
+
+//64KiB of RAM and nothing else, for running CPU tests that don't need a whole NES
+pub struct FlatRAM {
+    memory: Vec<u8>
+}
+
+impl FlatRAM {
+    pub fn new() -> FlatRAM {
+        FlatRAM {
+            memory: vec![0; 0x10000]
+        }
+    }
+
+    //Copies data in starting at address, wrapping around at the top of memory
+    pub fn load(&mut self, address: u16, data: &[u8]) {
+        for (i, byte) in data.iter().enumerate() {
+            self.memory[address.wrapping_add(i as u16) as usize] = *byte;
+        }
+    }
+}
+
+impl Bus for FlatRAM {
+    fn read(&mut self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        self.memory[address as usize] = data;
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+}
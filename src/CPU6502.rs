@@ -1,9 +1,9 @@
-mod CPUBus;
-
-extern crate bitflags;
+use crate::Bus::Bus;
+use crate::CPUBus;
+use crate::Error::{NesError, Result};
 
 bitflags! {
-    struct StatusFlags: u8 {
+    pub struct StatusFlags: u8 {
         const CARRY = 0b00000001;
         const ZERO = 0b00000010;
         const IRQ = 0b00000100;
@@ -15,20 +15,77 @@ bitflags! {
     }
 }
 
-pub struct CPU6502 {
+//Which chip the core behaves like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    //The NES's CPU. An NMOS 6502 with the decimal mode circuitry cut out, so the D flag does nothing
+    Ricoh2A03,
+    //A stock NMOS 6502 with working BCD arithmetic
+    Nmos6502,
+    //The CMOS 6502 from WDC. Extra instructions, no undocumented ones, and the JMP indirect bug fixed
+    Wdc65C02
+}
+
+impl Variant {
+    pub(crate) fn instructions(self) -> &'static [Instruction; 256] {
+        match self {
+            Variant::Ricoh2A03 | Variant::Nmos6502 => &INSTRUCTIONS,
+            Variant::Wdc65C02 => &INSTRUCTIONS_65C02
+        }
+    }
+}
+
+//A snapshot of the programmer visible registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    pub program_counter: u16,
+    pub stack_pointer: u8,
+    pub accumulator: u8,
+    pub reg_x: u8,
+    pub reg_y: u8,
+    pub status: u8
+}
+
+//Defaults to the NES's bus, but anything that implements Bus will do
+pub struct CPU6502<B: Bus = CPUBus::CPUBus> {
+    variant: Variant,
     program_counter: u16,
     stack_pointer: u8,
     accumulator: u8,
     reg_x: u8,
     reg_y: u8,
     status: StatusFlags,
-    pub cycles_to_wait: u8,
-    main_bus: CPUBus::CPUBus,
-    total_cycles: u32
+    main_bus: B,
+    total_cycles: u64,
+    //If the bus's tick asked to stop during the current step, which on the NES means the PPU
+    //finished a frame
+    frame_done: bool,
+
+    //NMI is edge triggered so we remember what the line looked like last cycle
+    nmi_line: bool,
+    //An NMI edge has been seen and not acted on yet
+    need_nmi: bool,
+    prev_need_nmi: bool,
+    //IRQ is level triggered and masked by the I flag, as of the end of the last cycle
+    run_irq: bool,
+    prev_run_irq: bool,
+    reset_pending: bool,
+    //A 65C02 sitting in WAI until an interrupt comes along
+    waiting: bool,
+    //The KIL opcode that locked the CPU up. Only a reset gets it going again
+    jammed: Option<u8>
 }
 
-#[derive(Debug)]
-enum AddressingMode {
+//ANE and LXA OR the accumulator with a value that depends on the chip and even its temperature.
+//This is the most common one
+const UNSTABLE_MAGIC: u8 = 0xEE;
+
+const NMI_VECTOR: u16 = 0xFFFA;
+const RESET_VECTOR: u16 = 0xFFFC;
+const IRQ_VECTOR: u16 = 0xFFFE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AddressingMode {
     //Indexed
     ZeroPageIndexedX,
     ZeroPageIndexedY,
@@ -43,315 +100,840 @@ enum AddressingMode {
     ZeroPage,
     Absolute,
     Relative,
-    Indirect
+    Indirect,
+    //65C02 only
+    ZeroPageIndirect,
+    AbsoluteIndexedIndirect, //X, only used by JMP
+    ZeroPageRelative //BBR and BBS test a zero page bit and branch
+}
+
+//The operation an opcode performs, regardless of how it addresses memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mnemonic {
+    ADC, AND, ASL, BCC, BCS, BEQ, BIT, BMI, BNE, BPL,
+    BRK, BVC, BVS, CLC, CLD, CLI, CLV, CMP, CPX, CPY,
+    DEC, DEX, DEY, EOR, INC, INX, INY, JMP, JSR, LDA,
+    LDX, LDY, LSR, NOP, ORA, PHA, PHP, PLA, PLP, ROL,
+    ROR, RTI, RTS, SBC, SEC, SED, SEI, STA, STX, STY,
+    TAX, TAY, TSX, TXA, TXS, TYA,
+    //Undocumented. The unofficial NOPs and SBC share their official mnemonic
+    ALR, ANC, ANE, ARR, AXS, DCP, ISB, LAS, LAX, LXA,
+    RLA, RRA, SAX, SHA, SHX, SHY, SLO, SRE, TAS,
+    //The KIL opcodes
+    JAM,
+    //Added by the 65C02. RMB, SMB, BBR and BBS take the bit number from the opcode
+    BRA, PHX, PHY, PLX, PLY, STZ, TRB, TSB, RMB, SMB,
+    BBR, BBS, WAI, STP
+}
+
+impl Mnemonic {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Mnemonic::ADC => "ADC",
+            Mnemonic::AND => "AND",
+            Mnemonic::ASL => "ASL",
+            Mnemonic::BCC => "BCC",
+            Mnemonic::BCS => "BCS",
+            Mnemonic::BEQ => "BEQ",
+            Mnemonic::BIT => "BIT",
+            Mnemonic::BMI => "BMI",
+            Mnemonic::BNE => "BNE",
+            Mnemonic::BPL => "BPL",
+            Mnemonic::BRK => "BRK",
+            Mnemonic::BVC => "BVC",
+            Mnemonic::BVS => "BVS",
+            Mnemonic::CLC => "CLC",
+            Mnemonic::CLD => "CLD",
+            Mnemonic::CLI => "CLI",
+            Mnemonic::CLV => "CLV",
+            Mnemonic::CMP => "CMP",
+            Mnemonic::CPX => "CPX",
+            Mnemonic::CPY => "CPY",
+            Mnemonic::DEC => "DEC",
+            Mnemonic::DEX => "DEX",
+            Mnemonic::DEY => "DEY",
+            Mnemonic::EOR => "EOR",
+            Mnemonic::INC => "INC",
+            Mnemonic::INX => "INX",
+            Mnemonic::INY => "INY",
+            Mnemonic::JMP => "JMP",
+            Mnemonic::JSR => "JSR",
+            Mnemonic::LDA => "LDA",
+            Mnemonic::LDX => "LDX",
+            Mnemonic::LDY => "LDY",
+            Mnemonic::LSR => "LSR",
+            Mnemonic::NOP => "NOP",
+            Mnemonic::ORA => "ORA",
+            Mnemonic::PHA => "PHA",
+            Mnemonic::PHP => "PHP",
+            Mnemonic::PLA => "PLA",
+            Mnemonic::PLP => "PLP",
+            Mnemonic::ROL => "ROL",
+            Mnemonic::ROR => "ROR",
+            Mnemonic::RTI => "RTI",
+            Mnemonic::RTS => "RTS",
+            Mnemonic::SBC => "SBC",
+            Mnemonic::SEC => "SEC",
+            Mnemonic::SED => "SED",
+            Mnemonic::SEI => "SEI",
+            Mnemonic::STA => "STA",
+            Mnemonic::STX => "STX",
+            Mnemonic::STY => "STY",
+            Mnemonic::TAX => "TAX",
+            Mnemonic::TAY => "TAY",
+            Mnemonic::TSX => "TSX",
+            Mnemonic::TXA => "TXA",
+            Mnemonic::TXS => "TXS",
+            Mnemonic::TYA => "TYA",
+            Mnemonic::ALR => "ALR",
+            Mnemonic::ANC => "ANC",
+            Mnemonic::ANE => "ANE",
+            Mnemonic::ARR => "ARR",
+            Mnemonic::AXS => "AXS",
+            Mnemonic::DCP => "DCP",
+            Mnemonic::ISB => "ISB",
+            Mnemonic::LAS => "LAS",
+            Mnemonic::LAX => "LAX",
+            Mnemonic::LXA => "LXA",
+            Mnemonic::RLA => "RLA",
+            Mnemonic::RRA => "RRA",
+            Mnemonic::SAX => "SAX",
+            Mnemonic::SHA => "SHA",
+            Mnemonic::SHX => "SHX",
+            Mnemonic::SHY => "SHY",
+            Mnemonic::SLO => "SLO",
+            Mnemonic::SRE => "SRE",
+            Mnemonic::TAS => "TAS",
+            Mnemonic::JAM => "JAM",
+            Mnemonic::BRA => "BRA",
+            Mnemonic::PHX => "PHX",
+            Mnemonic::PHY => "PHY",
+            Mnemonic::PLX => "PLX",
+            Mnemonic::PLY => "PLY",
+            Mnemonic::STZ => "STZ",
+            Mnemonic::TRB => "TRB",
+            Mnemonic::TSB => "TSB",
+            Mnemonic::RMB => "RMB",
+            Mnemonic::SMB => "SMB",
+            Mnemonic::BBR => "BBR",
+            Mnemonic::BBS => "BBS",
+            Mnemonic::WAI => "WAI",
+            Mnemonic::STP => "STP"
+        }
+    }
 }
 
 #[derive(Debug)]
-struct Instruction {
-    name: &'static str,
-    addressing: AddressingMode,
-    cycles: u8,
-    extraCycles: u8
+pub(crate) struct Instruction {
+    pub(crate) mnemonic: Mnemonic,
+    pub(crate) addressing: AddressingMode,
+    pub(crate) cycles: u8,
+    //The most that page crossings and taken branches can add. Indexed reads only pay for crossing
+    //a page, everything else with an indexed mode always takes the extra cycle and has 0 here
+    pub(crate) extraCycles: u8
 }
 
-const NAN: Instruction = Instruction { name: "NAN", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 };
+//The KIL opcodes. These lock the CPU up until it is reset
+const JAM: Instruction = Instruction { mnemonic: Mnemonic::JAM, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 };
 
-#[allow(dead_code)]
-const INSTRUCTIONS: [Instruction; 256] = [
+pub(crate) const INSTRUCTIONS: [Instruction; 256] = [
     //----------------------------0x------------------------------------------------- 
-    Instruction { name: "BRK", addressing: AddressingMode::Implicit, cycles: 7, extraCycles: 0 },
-    Instruction { name: "ORA", addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "ORA", addressing: AddressingMode::ZeroPageIndexedX, cycles: 3, extraCycles: 0 },
-    Instruction { name: "ASL", addressing: AddressingMode::ZeroPageIndexedX, cycles: 5, extraCycles: 0 },
-    NAN,
-    Instruction { name: "PHP", addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
-    Instruction { name: "ORA", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "ASL", addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
-    NAN,
-    NAN,
-    Instruction { name: "ORA", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "ASL", addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::BRK, addressing: AddressingMode::Implicit, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::SLO, addressing: AddressingMode::IndexedIndirect, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SLO, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::PHP, addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ANC, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SLO, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
 
     //----------------------------1x-------------------------------------------------
-    Instruction { name: "BPL", addressing: AddressingMode::Relative, cycles: 2, extraCycles: 1 },
-    Instruction { name: "ORA", addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "ORA", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "ASL", addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
-    NAN,
-    Instruction { name: "CLC", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "ORA", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "ORA", addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
-    Instruction { name: "ASL", addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::BPL, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::SLO, addressing: AddressingMode::IndirectIndexed, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SLO, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CLC, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SLO, addressing: AddressingMode::AbsoluteIndexedY, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SLO, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
 
     //----------------------------2x-------------------------------------------------
-    Instruction { name: "JSR", addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
-    Instruction { name: "AND", addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
-    NAN,
-    NAN,
-    Instruction { name: "BIT", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "AND", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "ROL", addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
-    NAN,
-    Instruction { name: "PLP", addressing: AddressingMode::Implicit, cycles: 4, extraCycles: 0 },
-    Instruction { name: "AND", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "ROL", addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "BIT", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "AND", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "ROL", addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::JSR, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::RLA, addressing: AddressingMode::IndexedIndirect, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BIT, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RLA, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::PLP, addressing: AddressingMode::Implicit, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ANC, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BIT, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RLA, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
 
     //----------------------------3x-------------------------------------------------
-    Instruction { name: "BMI", addressing: AddressingMode::Relative, cycles: 2, extraCycles: 1 },
-    Instruction { name: "AND", addressing: AddressingMode::IndexedIndirect, cycles: 5, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "AND", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "ROL", addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
-    NAN,
-    Instruction { name: "SEC", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "AND", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "AND", addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
-    Instruction { name: "ROL", addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::BMI, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::RLA, addressing: AddressingMode::IndirectIndexed, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RLA, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SEC, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RLA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RLA, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
 
     //----------------------------4x-------------------------------------------------
-    Instruction { name: "RTI", addressing: AddressingMode::Implicit, cycles: 6, extraCycles: 0 },
-    Instruction { name: "EOR", addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "EOR", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "LSR", addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
-    NAN,
-    Instruction { name: "PHA", addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
-    Instruction { name: "EOR", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "LSR", addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "JMP", addressing: AddressingMode::Absolute, cycles: 3, extraCycles: 0 },
-    Instruction { name: "EOR", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "LSR", addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::RTI, addressing: AddressingMode::Implicit, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::SRE, addressing: AddressingMode::IndexedIndirect, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SRE, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::PHA, addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ALR, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::JMP, addressing: AddressingMode::Absolute, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SRE, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
 
     //----------------------------5x-------------------------------------------------
-    Instruction { name: "BVC", addressing: AddressingMode::Relative, cycles: 2, extraCycles: 1 },
-    Instruction { name: "EOR", addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "EOR", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "LSR", addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
-    NAN,
-    Instruction { name: "CLI", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "EOR", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "EOR", addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
-    Instruction { name: "LSR", addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::BVC, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::SRE, addressing: AddressingMode::IndirectIndexed, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SRE, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CLI, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SRE, addressing: AddressingMode::AbsoluteIndexedY, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SRE, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
     
     //----------------------------6x----------------------------------------------
-    Instruction { name: "RTS", addressing: AddressingMode::Implicit, cycles: 6, extraCycles: 0 },
-    Instruction { name: "ADC", addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "ADC", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "ROR", addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
-    NAN,
-    Instruction { name: "PLA", addressing: AddressingMode::Implicit, cycles: 4, extraCycles: 0 },
-    Instruction { name: "ADC", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "ROR", addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "JMP", addressing: AddressingMode::Indirect, cycles: 5, extraCycles: 0 },
-    Instruction { name: "ADC", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "ROR", addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::RTS, addressing: AddressingMode::Implicit, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::RRA, addressing: AddressingMode::IndexedIndirect, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RRA, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::PLA, addressing: AddressingMode::Implicit, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ARR, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::JMP, addressing: AddressingMode::Indirect, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RRA, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
 
     //----------------------------7x----------------------------------------------
-    Instruction { name: "BVS", addressing: AddressingMode::Relative, cycles: 2, extraCycles: 1 },
-    Instruction { name: "ADC", addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "ADC", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "ROR", addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
-    NAN,
-    Instruction { name: "SEI", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "ADC", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "ADC", addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
-    Instruction { name: "ROR", addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::BVS, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::RRA, addressing: AddressingMode::IndirectIndexed, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RRA, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SEI, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RRA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RRA, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
 
     //----------------------------8x----------------------------------------------
-    NAN,
-    Instruction { name: "STA", addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
-    NAN,
-    NAN,
-    Instruction { name: "STY", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "STA", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "STX", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    NAN,
-    Instruction { name: "DEY", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "TXA", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "STY", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "STA", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "STX", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SAX, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STY, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STX, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SAX, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEY, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TXA, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ANE, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STY, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STX, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SAX, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
 
     //----------------------------9x----------------------------------------------
-    Instruction { name: "BCC", addressing: AddressingMode::Relative, cycles: 2, extraCycles: 1 },
-    Instruction { name: "STA", addressing: AddressingMode::IndirectIndexed, cycles: 6, extraCycles: 0 },
-    NAN,
-    NAN,
-    Instruction { name: "STY", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "STA", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "STX", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    NAN,
-    Instruction { name: "TYA", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "STA", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 0 },
-    Instruction { name: "TXS", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    NAN,
-    NAN,
-    Instruction { name: "STA", addressing: AddressingMode::AbsoluteIndexedX, cycles: 5, extraCycles: 0 },
-    NAN,
-    NAN,
+    Instruction { mnemonic: Mnemonic::BCC, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::IndirectIndexed, cycles: 6, extraCycles: 0 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::SHA, addressing: AddressingMode::IndirectIndexed, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STY, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STX, addressing: AddressingMode::ZeroPageIndexedY, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SAX, addressing: AddressingMode::ZeroPageIndexedY, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TYA, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TXS, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TAS, addressing: AddressingMode::AbsoluteIndexedY, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SHY, addressing: AddressingMode::AbsoluteIndexedX, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::AbsoluteIndexedX, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SHX, addressing: AddressingMode::AbsoluteIndexedY, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SHA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 5, extraCycles: 0 },
 
     //----------------------------Ax----------------------------------------------
-    Instruction { name: "LDY", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "LDA", addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
-    Instruction { name: "LDX", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "LDY", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "LDA", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "LDX", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    NAN,
-    Instruction { name: "TAY", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "LDA", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "TAX", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "LDY", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "LDA", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "LDX", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LAX, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LAX, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TAY, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TAX, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LXA, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LAX, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
 
     //----------------------------Bx----------------------------------------------
-    Instruction { name: "BCS", addressing: AddressingMode::Relative, cycles: 2, extraCycles: 1 },
-    Instruction { name: "LDA", addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
-    NAN,
-    NAN,
-    Instruction { name: "LDY", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "LDA", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "LDX", addressing: AddressingMode::ZeroPageIndexedY, cycles: 4, extraCycles: 0 },
-    NAN,
-    Instruction { name: "CLV", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "LDA", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
-    Instruction { name: "TSX", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "LDY", addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
-    Instruction { name: "LDA", addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
-    Instruction { name: "LDX", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::BCS, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::LAX, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::ZeroPageIndexedY, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LAX, addressing: AddressingMode::ZeroPageIndexedY, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CLV, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::TSX, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LAS, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LAX, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
 
     //----------------------------Cx----------------------------------------------
-    Instruction { name: "CPY", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "CMP", addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
-    NAN,
-    NAN,
-    Instruction { name: "CPY", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "CMP", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "DEC", addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
-    NAN,
-    Instruction { name: "INY", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "CMP", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "DEX", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "CPY", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "CMP", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "DEC", addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::CPY, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DCP, addressing: AddressingMode::IndexedIndirect, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CPY, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DCP, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::INY, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEX, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AXS, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CPY, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DCP, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
 
     //----------------------------Dx----------------------------------------------
-    Instruction { name: "BNE", addressing: AddressingMode::Relative, cycles: 2, extraCycles: 1 },
-    Instruction { name: "CMP", addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "CMP", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "DEC", addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
-    NAN,
-    Instruction { name: "CLD", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "CMP", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "CMP", addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
-    Instruction { name: "DEC", addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::BNE, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::DCP, addressing: AddressingMode::IndirectIndexed, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DCP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CLD, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DCP, addressing: AddressingMode::AbsoluteIndexedY, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DCP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
 
     //----------------------------Ex----------------------------------------------
-    Instruction { name: "CPX", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "SBC", addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
-    NAN,
-    NAN,
-    Instruction { name: "CPX", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "SBC", addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
-    Instruction { name: "INC", addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
-    NAN,
-    Instruction { name: "INX", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "SBC", addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
-    Instruction { name: "NOP", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    NAN,
-    Instruction { name: "CPX", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "SBC", addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
-    Instruction { name: "INC", addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
-    NAN,
+    Instruction { mnemonic: Mnemonic::CPX, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ISB, addressing: AddressingMode::IndexedIndirect, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CPX, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ISB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::INX, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CPX, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ISB, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
 
     //----------------------------Fx----------------------------------------------
-    Instruction { name: "BEQ", addressing: AddressingMode::Relative, cycles: 2, extraCycles: 1 },
-    Instruction { name: "SBC", addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "SBC", addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
-    Instruction { name: "INC", addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
-    NAN,
-    Instruction { name: "SED", addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
-    Instruction { name: "SBC", addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 0 },
-    NAN,
-    NAN,
-    NAN,
-    Instruction { name: "SBC", addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
-    Instruction { name: "INC", addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
-    NAN
+    Instruction { mnemonic: Mnemonic::BEQ, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    JAM,
+    Instruction { mnemonic: Mnemonic::ISB, addressing: AddressingMode::IndirectIndexed, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ISB, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SED, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ISB, addressing: AddressingMode::AbsoluteIndexedY, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ISB, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 }
 ];
 
+//The WDC 65C02. The KILs and undocumented opcodes are gone, and what's left over are NOPs of various
+//lengths. ADC and SBC take an extra cycle in decimal mode
+pub(crate) const INSTRUCTIONS_65C02: [Instruction; 256] = [
+    //----------------------------0x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BRK, addressing: AddressingMode::Implicit, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TSB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::PHP, addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TSB, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBR, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------1x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BPL, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::ZeroPageIndirect, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TRB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CLC, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TRB, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ORA, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ASL, addressing: AddressingMode::AbsoluteIndexedX, cycles: 6, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::BBR, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------2x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::JSR, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BIT, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::PLP, addressing: AddressingMode::Implicit, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BIT, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBR, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------3x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BMI, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::ZeroPageIndirect, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BIT, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SEC, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BIT, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::AND, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ROL, addressing: AddressingMode::AbsoluteIndexedX, cycles: 6, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::BBR, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------4x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::RTI, addressing: AddressingMode::Implicit, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::PHA, addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::JMP, addressing: AddressingMode::Absolute, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBR, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------5x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BVC, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::ZeroPageIndirect, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CLI, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::PHY, addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Absolute, cycles: 8, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::EOR, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LSR, addressing: AddressingMode::AbsoluteIndexedX, cycles: 6, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::BBR, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------6x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::RTS, addressing: AddressingMode::Implicit, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STZ, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::PLA, addressing: AddressingMode::Implicit, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::Accumulator, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::JMP, addressing: AddressingMode::Indirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBR, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------7x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BVS, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::ZeroPageIndirect, cycles: 5, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STZ, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::RMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SEI, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::PLY, addressing: AddressingMode::Implicit, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::JMP, addressing: AddressingMode::AbsoluteIndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::ADC, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::ROR, addressing: AddressingMode::AbsoluteIndexedX, cycles: 6, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::BBR, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------8x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BRA, addressing: AddressingMode::Relative, cycles: 3, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STY, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STX, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEY, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BIT, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TXA, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STY, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STX, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBS, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------9x-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BCC, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::IndirectIndexed, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::ZeroPageIndirect, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STY, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STX, addressing: AddressingMode::ZeroPageIndexedY, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TYA, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TXS, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STZ, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STA, addressing: AddressingMode::AbsoluteIndexedX, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STZ, addressing: AddressingMode::AbsoluteIndexedX, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBS, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------Ax-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TAY, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::TAX, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBS, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------Bx-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BCS, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::ZeroPageIndirect, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::ZeroPageIndexedY, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CLV, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::TSX, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::LDY, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LDA, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::LDX, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::BBS, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------Cx-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::CPY, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CPY, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::INY, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEX, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::WAI, addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CPY, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBS, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------Dx-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BNE, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::ZeroPageIndirect, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CLD, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::PHX, addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::STP, addressing: AddressingMode::Implicit, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CMP, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::DEC, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBS, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------Ex-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::CPX, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::IndexedIndirect, cycles: 6, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CPX, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::ZeroPage, cycles: 3, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::INX, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::Immediate, cycles: 2, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::CPX, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::Absolute, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBS, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 },
+    //----------------------------Fx-------------------------------------------------
+    Instruction { mnemonic: Mnemonic::BEQ, addressing: AddressingMode::Relative, cycles: 2, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::IndirectIndexed, cycles: 5, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::ZeroPageIndirect, cycles: 5, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::ZeroPageIndexedX, cycles: 4, extraCycles: 1 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::ZeroPageIndexedX, cycles: 6, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SMB, addressing: AddressingMode::ZeroPage, cycles: 5, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SED, addressing: AddressingMode::Implicit, cycles: 2, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::AbsoluteIndexedY, cycles: 4, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::PLX, addressing: AddressingMode::Implicit, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Implicit, cycles: 1, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::NOP, addressing: AddressingMode::Absolute, cycles: 4, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::SBC, addressing: AddressingMode::AbsoluteIndexedX, cycles: 4, extraCycles: 2 },
+    Instruction { mnemonic: Mnemonic::INC, addressing: AddressingMode::AbsoluteIndexedX, cycles: 7, extraCycles: 0 },
+    Instruction { mnemonic: Mnemonic::BBS, addressing: AddressingMode::ZeroPageRelative, cycles: 5, extraCycles: 2 }
+];
+
+//The undocumented opcodes, marked with a * in trace logs
+pub(crate) fn is_unofficial(variant: Variant, opcode: u8) -> bool {
+    if variant == Variant::Wdc65C02 {
+        return INSTRUCTIONS_65C02[opcode as usize].mnemonic == Mnemonic::NOP && opcode != 0xEA;
+    }
+    match INSTRUCTIONS[opcode as usize].mnemonic {
+        //Both have official versions
+        Mnemonic::NOP => opcode != 0xEA,
+        Mnemonic::SBC => opcode == 0xEB,
+        Mnemonic::ALR | Mnemonic::ANC | Mnemonic::ANE | Mnemonic::ARR | Mnemonic::AXS | Mnemonic::DCP
+        | Mnemonic::ISB | Mnemonic::LAS | Mnemonic::LAX | Mnemonic::LXA | Mnemonic::RLA | Mnemonic::RRA
+        | Mnemonic::SAX | Mnemonic::SHA | Mnemonic::SHX | Mnemonic::SHY | Mnemonic::SLO | Mnemonic::SRE
+        | Mnemonic::TAS | Mnemonic::JAM => true,
+        _ => false
+    }
+}
+
+type Handler<B> = fn(&mut CPU6502<B>, &Executable);
+
+const fn handler<B: Bus>(mnemonic: Mnemonic) -> Handler<B> {
+    match mnemonic {
+        Mnemonic::ADC => CPU6502::adc,
+        Mnemonic::AND => CPU6502::and,
+        Mnemonic::ASL => CPU6502::asl,
+        Mnemonic::BCC => CPU6502::bcc,
+        Mnemonic::BCS => CPU6502::bcs,
+        Mnemonic::BEQ => CPU6502::beq,
+        Mnemonic::BIT => CPU6502::bit,
+        Mnemonic::BMI => CPU6502::bmi,
+        Mnemonic::BNE => CPU6502::bne,
+        Mnemonic::BPL => CPU6502::bpl,
+        Mnemonic::BRK => CPU6502::brk,
+        Mnemonic::BVC => CPU6502::bvc,
+        Mnemonic::BVS => CPU6502::bvs,
+        Mnemonic::CLC => CPU6502::clc,
+        Mnemonic::CLD => CPU6502::cld,
+        Mnemonic::CLI => CPU6502::cli,
+        Mnemonic::CLV => CPU6502::clv,
+        Mnemonic::CMP => CPU6502::cmp,
+        Mnemonic::CPX => CPU6502::cpx,
+        Mnemonic::CPY => CPU6502::cpy,
+        Mnemonic::DEC => CPU6502::dec,
+        Mnemonic::DEX => CPU6502::dex,
+        Mnemonic::DEY => CPU6502::dey,
+        Mnemonic::EOR => CPU6502::eor,
+        Mnemonic::INC => CPU6502::inc,
+        Mnemonic::INX => CPU6502::inx,
+        Mnemonic::INY => CPU6502::iny,
+        Mnemonic::JMP => CPU6502::jmp,
+        Mnemonic::JSR => CPU6502::jsr,
+        Mnemonic::LDA => CPU6502::lda,
+        Mnemonic::LDX => CPU6502::ldx,
+        Mnemonic::LDY => CPU6502::ldy,
+        Mnemonic::LSR => CPU6502::lsr,
+        Mnemonic::ORA => CPU6502::ora,
+        Mnemonic::PHA => CPU6502::pha,
+        Mnemonic::PHP => CPU6502::php,
+        Mnemonic::PLA => CPU6502::pla,
+        Mnemonic::PLP => CPU6502::plp,
+        Mnemonic::ROL => CPU6502::rol,
+        Mnemonic::ROR => CPU6502::ror,
+        Mnemonic::RTI => CPU6502::rti,
+        Mnemonic::RTS => CPU6502::rts,
+        Mnemonic::SBC => CPU6502::sbc,
+        Mnemonic::SEC => CPU6502::sec,
+        Mnemonic::SED => CPU6502::sed,
+        Mnemonic::SEI => CPU6502::sei,
+        Mnemonic::STA => CPU6502::sta,
+        Mnemonic::STX => CPU6502::stx,
+        Mnemonic::STY => CPU6502::sty,
+        Mnemonic::TAX => CPU6502::tax,
+        Mnemonic::TAY => CPU6502::tay,
+        Mnemonic::TSX => CPU6502::tsx,
+        Mnemonic::TXA => CPU6502::txa,
+        Mnemonic::TXS => CPU6502::txs,
+        Mnemonic::TYA => CPU6502::tya,
+        Mnemonic::ALR => CPU6502::alr,
+        Mnemonic::ANC => CPU6502::anc,
+        Mnemonic::ANE => CPU6502::ane,
+        Mnemonic::ARR => CPU6502::arr,
+        Mnemonic::AXS => CPU6502::axs,
+        Mnemonic::DCP => CPU6502::dcp,
+        Mnemonic::ISB => CPU6502::isb,
+        Mnemonic::LAS => CPU6502::las,
+        Mnemonic::LAX => CPU6502::lax,
+        Mnemonic::LXA => CPU6502::lxa,
+        Mnemonic::RLA => CPU6502::rla,
+        Mnemonic::RRA => CPU6502::rra,
+        Mnemonic::SAX => CPU6502::sax,
+        Mnemonic::SHA => CPU6502::sha,
+        Mnemonic::SHX => CPU6502::shx,
+        Mnemonic::SHY => CPU6502::shy,
+        Mnemonic::SLO => CPU6502::slo,
+        Mnemonic::SRE => CPU6502::sre,
+        Mnemonic::TAS => CPU6502::tas,
+        Mnemonic::BRA => CPU6502::bra,
+        Mnemonic::PHX => CPU6502::phx,
+        Mnemonic::PHY => CPU6502::phy,
+        Mnemonic::PLX => CPU6502::plx,
+        Mnemonic::PLY => CPU6502::ply,
+        Mnemonic::STZ => CPU6502::stz,
+        Mnemonic::TRB => CPU6502::trb,
+        Mnemonic::TSB => CPU6502::tsb,
+        Mnemonic::RMB => CPU6502::rmb,
+        Mnemonic::SMB => CPU6502::smb,
+        Mnemonic::BBR => CPU6502::bbr,
+        Mnemonic::BBS => CPU6502::bbs,
+        Mnemonic::WAI => CPU6502::wai,
+        //JAM and STP are caught before dispatch
+        Mnemonic::NOP | Mnemonic::JAM | Mnemonic::STP => CPU6502::nop
+    }
+}
+
+const fn build_dispatch<B: Bus>(instructions: &[Instruction; 256]) -> [Handler<B>; 256] {
+    let mut table = [CPU6502::nop as Handler<B>; 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        table[opcode] = handler(instructions[opcode].mnemonic);
+        opcode += 1;
+    }
+    table
+}
+
 struct Executable {
-    name: &'static str,
-    target: u16,
-    data: u8,
-    cycles: u8
+    mnemonic: Mnemonic,
+    opcode: u8,
+    addressing: AddressingMode,
+    //Where the instruction itself starts
+    address: u16,
+    //The effective address. For branches this is where a taken branch goes
+    target: u16
 }
 
 
@@ -359,578 +941,1110 @@ fn is_negative(number: u8) -> bool {
     number & 0b10000000 == 0b10000000
 }
 
-impl CPU6502 {
-    pub fn new() -> CPU6502 {
-        CPU6502 {
-            program_counter: 0xc000,
-            stack_pointer: 0xff,
+impl<B: Bus> CPU6502<B> {
+    //Built from the instruction tables at compile time so the two can't disagree
+    const DISPATCH: [Handler<B>; 256] = build_dispatch(&INSTRUCTIONS);
+    const DISPATCH_65C02: [Handler<B>; 256] = build_dispatch(&INSTRUCTIONS_65C02);
+
+    //A 2A03, like the one in the NES
+    pub fn new(bus: B) -> CPU6502<B> {
+        CPU6502::with_variant(bus, Variant::Ricoh2A03)
+    }
+
+    pub fn with_variant(bus: B, variant: Variant) -> CPU6502<B> {
+        let mut cpu = CPU6502 {
+            variant,
+            program_counter: 0,
+            stack_pointer: 0,
             accumulator: 0,
             reg_x: 0,
             reg_y: 0,
             //this flag is always set
             status: StatusFlags::UNUSED,
-            cycles_to_wait: 0,
-            main_bus: CPUBus::CPUBus::new(),
-            total_cycles: 0
+            main_bus: bus,
+            total_cycles: 0,
+            frame_done: false,
+
+            nmi_line: false,
+            need_nmi: false,
+            prev_need_nmi: false,
+            run_irq: false,
+            prev_run_irq: false,
+            reset_pending: false,
+            waiting: false,
+            jammed: None
+        };
+        cpu.power_on();
+        cpu
+    }
+
+    //Like flipping the power switch. Registers are cleared before going through the reset sequence
+    pub fn power_on(&mut self) {
+        self.accumulator = 0;
+        self.reg_x = 0;
+        self.reg_y = 0;
+        self.stack_pointer = 0;
+        self.status = StatusFlags::UNUSED;
+        self.total_cycles = 0;
+        self.reset();
+    }
+
+    //Like pressing the reset button. The registers keep their values apart from the I flag.
+    //The reset sequence itself runs on the next step
+    pub fn reset(&mut self) {
+        self.main_bus.reset();
+        self.need_nmi = false;
+        self.prev_need_nmi = false;
+        self.run_irq = false;
+        self.prev_run_irq = false;
+        self.waiting = false;
+        self.jammed = None;
+        self.reset_pending = true;
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers {
+            program_counter: self.program_counter,
+            stack_pointer: self.stack_pointer,
+            accumulator: self.accumulator,
+            reg_x: self.reg_x,
+            reg_y: self.reg_y,
+            status: self.status.bits()
         }
     }
 
+    //Puts the CPU in a known state, mostly for tests
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.program_counter = registers.program_counter;
+        self.stack_pointer = registers.stack_pointer;
+        self.accumulator = registers.accumulator;
+        self.reg_x = registers.reg_x;
+        self.reg_y = registers.reg_y;
+        self.status = StatusFlags::from_bits_truncate(registers.status);
+    }
+
+    //For starting somewhere other than the reset vector, like nestest's automation mode at $C000
+    pub fn set_program_counter(&mut self, address: u16) {
+        self.program_counter = address;
+    }
+
+    //If a KIL opcode has stopped the CPU. The rest of the console keeps running
+    pub fn is_halted(&self) -> bool {
+        self.jammed.is_some()
+    }
+
+    //Cycles since power on, including the 7 spent in the reset sequence
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    //Every cycle is exactly one read or write, so the rest of the console is clocked from here.
+    //That way each access sees the PPU and mapper as they are on that cycle
     pub fn read(&mut self, address: u16) -> u8 {
-        self.main_bus.read(address)
+        if self.main_bus.dma_pending() {
+            self.dma(address);
+        }
+        self.start_cycle();
+        let data = self.main_bus.read(address);
+        self.end_cycle();
+        data
     }
 
-    pub fn write (&mut self, address: u16, data: u8) {
+    pub fn write(&mut self, address: u16, data: u8) {
+        self.start_cycle();
         self.main_bus.write(address, data);
+        self.end_cycle();
     }
 
-    fn decode_next_instruction(&mut self) -> Executable {
-        let opcode = self.read(self.program_counter);
-        let instruction: &Instruction = &INSTRUCTIONS[opcode as usize];
-        let mut ret_executable: Executable = Executable {name: instruction.name, target: 0, data: 0, cycles: 0};
+    //DMA can only halt the CPU on a read. The read on the halt cycle happens but gets done again
+    //afterwards. DMA reads on even cycles and writes on odd ones, so it might have to wait one more
+    //cycle to line up
+    fn dma(&mut self, address: u16) {
+        self.start_cycle();
+        self.main_bus.read(address);
+        self.end_cycle();
+        if self.total_cycles & 1 == 1 {
+            self.start_cycle();
+            self.main_bus.read(address);
+            self.end_cycle();
+        }
+        loop {
+            let get = self.total_cycles & 1 == 0;
+            self.start_cycle();
+            let done = self.main_bus.dma_cycle(get);
+            self.end_cycle();
+            if done {
+                break;
+            }
+        }
+    }
+
+    fn start_cycle(&mut self) {
+        self.frame_done |= self.main_bus.tick();
+        self.total_cycles += 1;
+    }
 
-        println!("OP: {}, {:?}", opcode, instruction);
-        println!("Status:");
-        println!("A: {:2x}, X: {:2x}, Y: {:2x}, PC: {:4x}, SP: {:2x}, status: {:8b} \n", self.accumulator, self.reg_x, self.reg_y, self.program_counter, self.stack_pointer, self.status);
+    //The interrupt lines are sampled at the end of every cycle. What gets acted on at the end of an
+    //instruction is what was seen on its second to last cycle, so that's kept around too
+    fn end_cycle(&mut self) {
+        self.prev_need_nmi = self.need_nmi;
+        let nmi = self.main_bus.nmi();
+        if nmi && !self.nmi_line {
+            self.need_nmi = true;
+        }
+        self.nmi_line = nmi;
+
+        self.prev_run_irq = self.run_irq;
+        self.run_irq = self.main_bus.irq() && !self.is_flag_set(StatusFlags::IRQ);
+    }
+
+    fn read_word(&mut self, address: u16) -> u16 {
+        self.read(address) as u16 | ((self.read(address.wrapping_add(1)) as u16) << 8)
+    }
+
+    //An interrupt with the writes turned into reads, so the stack pointer still goes down by 3
+    fn reset_sequence(&mut self) {
+        self.read(self.program_counter);
+        self.read(self.program_counter);
+        for _ in 0..3 {
+            self.read(0x0100 | self.stack_pointer as u16);
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        }
+        self.status.insert(StatusFlags::IRQ);
+        self.program_counter = self.read_word(RESET_VECTOR);
+        self.reset_pending = false;
+    }
+
+    //IRQ and NMI fetch an opcode like normal but throw it away
+    fn interrupt_sequence(&mut self) {
+        self.read(self.program_counter);
+        self.read(self.program_counter);
+        self.interrupt(self.program_counter, false);
+    }
+
+    //Pushes the return address and flags and jumps through a vector. Used by BRK, IRQ and NMI.
+    //An NMI that turns up before the flags are pushed hijacks the sequence, even for BRK
+    fn interrupt(&mut self, return_address: u16, brk: bool) {
+        self.push_stack((return_address >> 8) as u8);
+        self.push_stack(return_address as u8);
+
+        let vector = if self.need_nmi {
+            self.need_nmi = false;
+            NMI_VECTOR
+        } else {
+            IRQ_VECTOR
+        };
+
+        let mut flags = self.status | StatusFlags::UNUSED;
+        flags.set(StatusFlags::BRK, brk);
+        self.push_stack(flags.bits());
+        self.status.insert(StatusFlags::IRQ);
+        //The 65C02 starts handlers in binary mode
+        if self.variant == Variant::Wdc65C02 {
+            self.status.remove(StatusFlags::DECIMAL);
+        }
+        self.program_counter = self.read_word(vector);
+
+        //The handler always gets its first instruction in before another NMI
+        self.prev_need_nmi = false;
+    }
 
-        match instruction.addressing {
+    //Pointers in the zero page wrap around to $00 instead of carrying into page 1
+    fn read_zero_page_word(&mut self, pointer: u8) -> u16 {
+        self.read(pointer as u16) as u16 | ((self.read(pointer.wrapping_add(1) as u16) as u16) << 8)
+    }
+
+    fn fetch(&mut self) -> u8 {
+        let data = self.read(self.program_counter);
+        self.program_counter = self.program_counter.wrapping_add(1);
+        data
+    }
+
+    fn fetch_word(&mut self) -> u16 {
+        self.fetch() as u16 | ((self.fetch() as u16) << 8)
+    }
+
+    //Adds an index to a base address. The low byte is added first and the carry fixed up on the
+    //next cycle, which reads from the half-finished address. Reads skip that cycle when there's
+    //nothing to fix, but writes can't take the chance
+    fn index(&mut self, base: u16, index: u8, page_cross_penalty: bool) -> u16 {
+        let target = base.wrapping_add(index as u16);
+        if target & 0xFF00 != base & 0xFF00 || !page_cross_penalty {
+            //The 65C02 reads the last operand byte again instead, which is safer around I/O
+            let dummy = match self.variant {
+                Variant::Wdc65C02 => self.program_counter.wrapping_sub(1),
+                _ => (base & 0xFF00) | (target & 0x00FF)
+            };
+            self.read(dummy);
+        }
+        target
+    }
+
+    //Works out the effective address, making the same dummy reads the real thing does along the way.
+    //The operand itself is left for the handler so stores and jumps never read their target
+    fn decode_next_instruction(&mut self) -> Executable {
+        let address = self.program_counter;
+        let opcode = self.fetch();
+        let instruction: &Instruction = &self.variant.instructions()[opcode as usize];
+        let page_cross_penalty = instruction.extraCycles != 0;
+
+        let target = match instruction.addressing {
+            //JSR pushes the return address in between fetching the two halves of the target
+            AddressingMode::Absolute if instruction.mnemonic == Mnemonic::JSR => 0,
+            //Single byte instructions still read the next byte, they just don't use it.
+            //The 65C02's single cycle NOPs don't have time to
+            AddressingMode::Implicit | AddressingMode::Accumulator => {
+                if instruction.cycles > 1 {
+                    self.read(self.program_counter);
+                }
+                0
+            },
             AddressingMode::Immediate => {
-                ret_executable.data = self.read(self.program_counter + 1);
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 2;
+                let target = self.program_counter;
+                self.program_counter = self.program_counter.wrapping_add(1);
+                target
             },
-            AddressingMode::Absolute => {
-                //TODO: optimise?
-                ret_executable.target = self.read(self.program_counter + 1) as u16 + self.read(self.program_counter + 2) as u16 * 256;
-                ret_executable.data = self.read(ret_executable.target);
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 3;
+            AddressingMode::ZeroPage => self.fetch() as u16,
+            AddressingMode::ZeroPageIndexedX | AddressingMode::ZeroPageIndexedY => {
+                let base = self.fetch();
+                //The index is added while the unindexed address is read
+                self.read(base as u16);
+                let index = if instruction.addressing == AddressingMode::ZeroPageIndexedX { self.reg_x } else { self.reg_y };
+                base.wrapping_add(index) as u16
             },
+            AddressingMode::Absolute => self.fetch_word(),
             AddressingMode::AbsoluteIndexedX => {
-                let low = self.read(self.program_counter + 1);
-                let high = (self.read(self.program_counter + 2) as u16) << 8;
-
-                //If did_overflow is set, we spill into the next page and need an oops cycle
-                let (low, did_overflow) = low.overflowing_add(self.reg_x);
-                if did_overflow {
-                    //self.status.insert(StatusFlags::CARRY);
-                    ret_executable.cycles = instruction.cycles + 1;
-                    ret_executable.target = low as u16 + (high + 1) as u16;
-                } else {
-                    ret_executable.cycles = instruction.cycles;
-                    ret_executable.target = low as u16 + high as u16;
-                }
-                ret_executable.data = self.read(ret_executable.target);
-                self.program_counter += 3;
+                let base = self.fetch_word();
+                self.index(base, self.reg_x, page_cross_penalty)
             },
             AddressingMode::AbsoluteIndexedY => {
-                let low = self.read(self.program_counter + 1);
-                let high = (self.read(self.program_counter + 2) as u16) << 8;
-
-                //If did_overflow is set, we spill into the next page and need an oops cycle
-                let (low, did_overflow) = low.overflowing_add(self.reg_y);
-                if did_overflow {
-                    //self.status.insert(StatusFlags::CARRY);
-                    ret_executable.cycles = instruction.cycles + 1;
-                    ret_executable.target = low as u16 + (high + 1) as u16;
+                let base = self.fetch_word();
+                self.index(base, self.reg_y, page_cross_penalty)
+            },
+            AddressingMode::Relative => {
+                //Branches are relative to the next instruction
+                let offset = self.fetch();
+                self.program_counter.wrapping_add(offset as i8 as u16)
+            },
+            AddressingMode::Indirect => {
+                //The pointer's high byte is fetched without carrying into the next page,
+                //so JMP ($10FF) reads $10FF and $1000. The 65C02 spends a cycle getting it right
+                let pointer = self.fetch_word();
+                let pointer_high = if self.variant == Variant::Wdc65C02 {
+                    self.read(self.program_counter.wrapping_sub(1));
+                    pointer.wrapping_add(1)
                 } else {
-                    ret_executable.cycles = instruction.cycles;
-                    ret_executable.target = low as u16 + high as u16;
-                }
-                ret_executable.data = self.read(ret_executable.target);
-                self.program_counter += 3;
+                    (pointer & 0xFF00) | (pointer.wrapping_add(1) & 0x00FF)
+                };
+                self.read(pointer) as u16 | ((self.read(pointer_high) as u16) << 8)
             },
-            AddressingMode::ZeroPage => {
-                ret_executable.target = self.read(self.program_counter + 1) as u16;
-                ret_executable.data = self.read(ret_executable.target);
-                
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 2;
+            AddressingMode::AbsoluteIndexedIndirect => {
+                let base = self.fetch_word();
+                self.read(self.program_counter.wrapping_sub(1));
+                self.read_word(base.wrapping_add(self.reg_x as u16))
             },
-            AddressingMode::ZeroPageIndexedX => {
-                //We wrap and ignore carry here
-                ret_executable.target = self.read(self.program_counter + 1).wrapping_add(self.reg_x) as u16;
-                ret_executable.data = self.read(ret_executable.target);
-
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 2;
+            AddressingMode::ZeroPageIndirect => {
+                let pointer = self.fetch();
+                self.read_zero_page_word(pointer)
             },
-            AddressingMode::ZeroPageIndexedY => {
-                //We wrap and ignore carry here
-                ret_executable.target = self.read(self.program_counter + 1).wrapping_add(self.reg_y) as u16;
-                ret_executable.data = self.read(ret_executable.target);
-
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 2;
+            //The handler fetches the offset once it's tested the bit
+            AddressingMode::ZeroPageRelative => self.fetch() as u16,
+            AddressingMode::IndexedIndirect => {
+                let pointer = self.fetch();
+                self.read(pointer as u16);
+                self.read_zero_page_word(pointer.wrapping_add(self.reg_x))
             },
-            AddressingMode::Relative => {
-                //TODO: Implement the oops
-                ret_executable.data = self.read(self.program_counter + 1);
+            AddressingMode::IndirectIndexed => {
+                let pointer = self.fetch();
+                let base = self.read_zero_page_word(pointer);
+                self.index(base, self.reg_y, page_cross_penalty)
+            }
+        };
+
+        Executable {
+            mnemonic: instruction.mnemonic,
+            opcode,
+            addressing: instruction.addressing,
+            address,
+            target
+        }
+    }
 
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 2;
-            },
-            AddressingMode::Indirect => {
-                //The operand here is a pointer to the high byte of the actual target
-                let loc_low = self.read(self.program_counter + 1);
-                let loc_high = self.read(self.program_counter + 2);
+    fn is_flag_set(&self, flag: StatusFlags) -> bool {
+        self.status & flag == flag
+    }
 
-                //Find actual target by reading operand address and the address after
-                let loc = loc_low as u16 + (loc_high as u16) << 8;
+    fn execute(&mut self, executable: Executable) -> Result<()> {
+        if executable.mnemonic == Mnemonic::STP {
+            self.read(self.program_counter);
+        }
+        if executable.mnemonic == Mnemonic::JAM || executable.mnemonic == Mnemonic::STP {
+            //Stay stuck on the instruction so the trace shows where it happened
+            self.program_counter = executable.address;
+            self.jammed = Some(executable.opcode);
+            return Err(NesError::CpuJam { opcode: executable.opcode, address: executable.address });
+        }
+        let handler = match self.variant {
+            Variant::Ricoh2A03 | Variant::Nmos6502 => Self::DISPATCH[executable.opcode as usize],
+            Variant::Wdc65C02 => Self::DISPATCH_65C02[executable.opcode as usize]
+        };
+        handler(self, &executable);
+        Ok(())
+    }
 
-                ret_executable.target = self.read(loc) as u16 + self.read(loc + 1) as u16;
+    //Runs one instruction, or the reset or interrupt sequence if one is due, clocking the rest of
+    //the console as it goes. Returns if a frame was finished along the way, or whatever tick
+    //returning true means on other buses
+    pub fn step(&mut self) -> Result<bool> {
+        self.frame_done = false;
+        if self.reset_pending {
+            self.reset_sequence();
+        } else if let Some(opcode) = self.jammed {
+            //The rest of the console carries on while the CPU is stuck
+            self.read(self.program_counter);
+            return Err(NesError::CpuJam { opcode, address: self.program_counter });
+        } else if self.prev_need_nmi || self.prev_run_irq {
+            self.waiting = false;
+            self.interrupt_sequence();
+        } else if self.waiting {
+            //Any IRQ wakes WAI up, but with I set it carries on from the next instruction instead
+            //of going to the handler
+            self.read(self.program_counter);
+            self.waiting = !self.main_bus.irq();
+        } else {
+            let exec: Executable = self.decode_next_instruction();
+            self.execute(exec)?;
+        }
+        Ok(self.frame_done)
+    }
 
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 3;
-            },
-            AddressingMode::IndexedIndirect => {
-                //The target here has its low byte in $(operand+X) and high byte in $(operand+X+1)
-                let operand = self.read(self.program_counter + 1);
-                
-                ret_executable.target = self.read((operand + self.reg_x) as u16) as u16 + (self.read((operand + self.reg_x + 1) as u16) as u16) << 8;
+    pub fn bus(&self) -> &B {
+        &self.main_bus
+    }
 
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 2;
-            },
-            AddressingMode::IndirectIndexed => {
-                //The target here is the number found in the (operand address and the next one)
-                //incremented by Y
-                let operand = self.read(self.program_counter + 1);
+    pub fn bus_mut(&mut self) -> &mut B {
+        &mut self.main_bus
+    }
 
-                let target = self.read(operand as u16) as u16 + (self.read((operand + 1) as u16) as u16) << 8;
-                let (target, did_overflow) = target.overflowing_add(self.reg_y as u16);
+    fn push_stack(&mut self, data: u8) {
+        self.write(self.stack_pointer as u16 + 256, data);
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+    }
 
-                //TODO: Figure out this oops
-                if did_overflow {
-                    ret_executable.cycles = instruction.cycles + 1;
-                } else {
-                    ret_executable.cycles = instruction.cycles;
-                }
+    fn pop_stack(&mut self) -> u8 {
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        self.read(self.stack_pointer as u16 + 256)
+    }
 
-                ret_executable.target = target;
-                self.program_counter += 2;
-            },
-            AddressingMode::Implicit => {
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 1;
-            },
-            AddressingMode::Accumulator => {
-                ret_executable.cycles = instruction.cycles;
-                self.program_counter += 1;
+    //The cycle where the stack pointer gets incremented before a pull, or JSR stores the low byte
+    //of the target. The 6502 reads the top of the stack while it does that
+    fn stack_dummy_read(&mut self) {
+        self.read(self.stack_pointer as u16 + 256);
+    }
+}
+
+//One handler per mnemonic. These are looked up through DISPATCH rather than called directly
+impl<B: Bus> CPU6502<B> {
+    //Reads the value the instruction works on, which for accumulator mode is A itself
+    fn operand(&mut self, executable: &Executable) -> u8 {
+        if executable.addressing == AddressingMode::Accumulator {
+            self.accumulator
+        } else {
+            self.read(executable.target)
+        }
+    }
+
+    //Read-modify-write instructions write the value back unchanged while they work on it,
+    //then write the result on the next cycle. The 65C02 reads it again instead
+    fn modify(&mut self, executable: &Executable) -> u8 {
+        let data = self.operand(executable);
+        if executable.addressing != AddressingMode::Accumulator {
+            match self.variant {
+                Variant::Wdc65C02 => { self.read(executable.target); },
+                _ => self.write(executable.target, data)
             }
         }
-    ret_executable
+        data
     }
 
-    fn is_flag_set(&self, flag: StatusFlags) -> bool {
-        self.status & flag == flag
+    //Where the shifts and rotates put their result
+    fn write_back(&mut self, executable: &Executable, data: u8) {
+        if executable.addressing == AddressingMode::Accumulator {
+            self.accumulator = data;
+        } else {
+            self.write(executable.target, data);
+        }
     }
 
-    fn execute(&mut self, executable: Executable) {
-        //This function will take up one cycle so we need to artificially wait for the rest
-        self.cycles_to_wait = executable.cycles - 1;
-        self.total_cycles += executable.cycles as u32;
-        match executable.name {
-            "ADC" => {
-                let (result, did_overflow) = self.accumulator.overflowing_add(executable.data);
-                if did_overflow {
-                    self.status.insert(StatusFlags::CARRY);
-                }
+    fn set_zero_negative(&mut self, value: u8) {
+        self.status.set(StatusFlags::ZERO, value == 0);
+        self.status.set(StatusFlags::NEGATIVE, is_negative(value));
+    }
 
-                self.status.set(StatusFlags::ZERO, result == 0);
+    //The 2A03 has the D flag but nothing wired up to it
+    fn decimal_mode(&self) -> bool {
+        self.variant != Variant::Ricoh2A03 && self.is_flag_set(StatusFlags::DECIMAL)
+    }
 
-                //TODO: fix this mess
-                let initial_acc_sign = self.accumulator & 0b10000000;
-                let data_sign = executable.data & 0b10000000;
-                let result_sign = result & 0b10000000;
+    fn add(&mut self, data: u8) {
+        if self.decimal_mode() {
+            self.add_decimal(data);
+        } else {
+            self.add_with_carry(data);
+        }
+    }
 
-                self.status.set(StatusFlags::OVERFLOW,!(initial_acc_sign ^ data_sign) & (initial_acc_sign ^ result_sign) == 0b10000000);
+    fn subtract(&mut self, data: u8) {
+        if self.decimal_mode() {
+            self.subtract_decimal(data);
+        } else {
+            self.add_with_carry(!data);
+        }
+    }
 
-                self.status.set(StatusFlags::NEGATIVE, result_sign == 0b1000000);
-                self.accumulator = result;
-            },
-            "AND" => {
-                let result = self.accumulator & executable.data;
-                if result == 0 { self.status.insert(StatusFlags::ZERO); }
-                self.status.set(StatusFlags::CARRY, is_negative(result));
-                self.accumulator = result;
-            },
-            "ASL" => {
-                self.status.set(StatusFlags::CARRY, is_negative(self.accumulator));
-                self.accumulator <<= 1;
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.accumulator));
-            },
-            "BCC" => {
-                if !self.is_flag_set(StatusFlags::CARRY) {
-                    let new_pc = self.program_counter + executable.data as u16;
-                    if new_pc & 0xff00 != self.program_counter & 0xff00 { self.cycles_to_wait += 1; }
-                    self.program_counter = new_pc;
-                    self.cycles_to_wait += 1;
-                }
-            },
-            "BCS" => {
-                if self.is_flag_set(StatusFlags::CARRY) {
-                    let new_pc = self.program_counter + executable.data as u16;
-                    if new_pc & 0xff00 != self.program_counter & 0xff00 { self.cycles_to_wait += 1; }
-                    self.program_counter = new_pc;
-                    self.cycles_to_wait += 1;
-                }
-            },            
-            "BEQ" => {
-                if self.is_flag_set(StatusFlags::ZERO) {
-                    let new_pc = self.program_counter + executable.data as u16;
-                    if new_pc & 0xff00 != self.program_counter & 0xff00 { self.cycles_to_wait += 1; }
-                    self.program_counter = new_pc;
-                    self.cycles_to_wait += 1;
-                }
-            },
-            "BIT" => {
-                self.status.set(StatusFlags::OVERFLOW, executable.data & 0b01000000 == 0b01000000);
-                self.status.set(StatusFlags::NEGATIVE, executable.data & 0b10000000 == 0b10000000);
+    //BCD addition, following Bruce Clark's "Decimal Mode" tutorial. The NMOS chips take N and V from
+    //the sum before the high digit is adjusted and Z from the binary sum. The 65C02 fixes N and Z
+    fn add_decimal(&mut self, data: u8) {
+        let carry = self.is_flag_set(StatusFlags::CARRY) as i16;
+        let mut low = (self.accumulator & 0x0F) as i16 + (data & 0x0F) as i16 + carry;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+        let mut sum = (self.accumulator & 0xF0) as i16 + (data & 0xF0) as i16 + low;
+        let signed = (self.accumulator & 0xF0) as i8 as i16 + (data & 0xF0) as i8 as i16 + low;
+        let binary = self.accumulator.wrapping_add(data).wrapping_add(carry as u8);
+
+        self.status.set(StatusFlags::OVERFLOW, !(-128..=127).contains(&signed));
+        self.status.set(StatusFlags::NEGATIVE, signed & 0x80 != 0);
+        self.status.set(StatusFlags::ZERO, binary == 0);
+        if sum >= 0xA0 {
+            sum += 0x60;
+        }
+        self.status.set(StatusFlags::CARRY, sum >= 0x100);
+        self.accumulator = sum as u8;
+        if self.variant == Variant::Wdc65C02 {
+            self.set_zero_negative(self.accumulator);
+        }
+    }
 
-                let result = self.accumulator & executable.data;
+    //BCD subtraction. The flags all come from the binary subtraction, apart from N and Z on the 65C02
+    fn subtract_decimal(&mut self, data: u8) {
+        let borrow = 1 - self.is_flag_set(StatusFlags::CARRY) as i16;
+        let low = (self.accumulator & 0x0F) as i16 - (data & 0x0F) as i16 - borrow;
+        let result = if self.variant == Variant::Wdc65C02 {
+            let mut result = self.accumulator as i16 - data as i16 - borrow;
+            if result < 0 {
+                result -= 0x60;
+            }
+            if low < 0 {
+                result -= 0x06;
+            }
+            result
+        } else {
+            let low = if low < 0 { ((low - 0x06) & 0x0F) - 0x10 } else { low };
+            let mut result = (self.accumulator & 0xF0) as i16 - (data & 0xF0) as i16 + low;
+            if result < 0 {
+                result -= 0x60;
+            }
+            result
+        };
 
-                self.status.set(StatusFlags::ZERO, result == 0);
+        self.add_with_carry(!data);
+        self.accumulator = result as u8;
+        if self.variant == Variant::Wdc65C02 {
+            self.set_zero_negative(self.accumulator);
+        }
+    }
 
-                self.accumulator = result;
-            },
-            "BMI" => {
-                if self.is_flag_set(StatusFlags::NEGATIVE) {
-                    let new_pc = self.program_counter + executable.data as u16;
-                    if new_pc & 0xff00 != self.program_counter & 0xff00 { self.cycles_to_wait += 1; }
-                    self.program_counter = new_pc;
-                    self.cycles_to_wait += 1;
-                }
-            },
-            "BNE" => {
-                if !self.is_flag_set(StatusFlags::ZERO) {
-                    let new_pc = (self.program_counter as i32 + (executable.data as i8) as i32) as u16;
-                    println!("Old PC: {:2x}, Data: {:2x}, New PC: {:2x}", self.program_counter, executable.data, new_pc);
-                    if new_pc & 0xff00 != self.program_counter & 0xff00 { self.cycles_to_wait += 1; }
-                    self.program_counter = new_pc;
-                    self.cycles_to_wait += 1;
-                }
-            },
-            "BPL" => {
-                if !self.is_flag_set(StatusFlags::NEGATIVE) {
-                    let new_pc = self.program_counter + executable.data as u16;
-                    if new_pc & 0xff00 != self.program_counter & 0xff00 { self.cycles_to_wait += 1; }
-                    self.program_counter = new_pc;
-                    self.cycles_to_wait += 1;
-                }
-            },
-            "BRK" => {
-                //Low byte
-                self.push_stack((self.program_counter | 0b0000000011111111) as u8);
-                //High byte
-                self.push_stack((self.program_counter >> 8) as u8);
-                self.push_stack(self.status.bits());
-
-                self.status.insert(StatusFlags::BRK);
-            },
-            "BVC" => {
-                if !self.is_flag_set(StatusFlags::OVERFLOW) {
-                    let new_pc = self.program_counter + executable.data as u16;
-                    if new_pc & 0xff00 != self.program_counter & 0xff00 { self.cycles_to_wait += 1; }
-                    self.program_counter = new_pc;
-                    self.cycles_to_wait += 1;
-                }
-            },
-            "BVS" => {
-                if self.is_flag_set(StatusFlags::OVERFLOW) {
-                    let new_pc = self.program_counter + executable.data as u16;
-                    if new_pc & 0xff00 != self.program_counter & 0xff00 { self.cycles_to_wait += 1; }
-                    self.program_counter = new_pc;
-                    self.cycles_to_wait += 1;
-                }
-            },
-            "CLC" => {
-                self.status.remove(StatusFlags::CARRY);
-            },
-            "CLD" => {
-                self.status.remove(StatusFlags::DECIMAL);
-            },
-            "CLI" => {
-                self.status.remove(StatusFlags::IRQ);
-            },
-            "CLV" => {
-                self.status.remove(StatusFlags::OVERFLOW);
-            },
-            "CMP" => {
-                let result = self.accumulator.wrapping_sub(executable.data);
-                if result > 0 {
-                    self.status.insert(StatusFlags::CARRY);
-                } else if result == 0 {
-                    self.status.insert(StatusFlags::ZERO);
-                }
-                
-                self.status.set(StatusFlags::NEGATIVE, is_negative(result));
-            },
-            "CPX" => {
-                let result = self.reg_x.wrapping_sub(executable.data);
-                if result > 0 {
-                    self.status.insert(StatusFlags::CARRY);
-                } else if result == 0 {
-                    self.status.insert(StatusFlags::ZERO);
-                }
-                
-                self.status.set(StatusFlags::NEGATIVE, is_negative(result));
-            },
-            "CPY" => {
-                let result = self.reg_y.wrapping_sub(executable.data);
-                if result > 0 {
-                    self.status.insert(StatusFlags::CARRY);
-                } else if result == 0 {
-                    self.status.insert(StatusFlags::ZERO);
-                }
-                
-                self.status.set(StatusFlags::NEGATIVE, is_negative(result));
-            }, 
-            "DEC" => {
-                let data = self.read(executable.target);
-                let data = data.wrapping_sub(1);
-
-                self.status.set(StatusFlags::ZERO, data == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(data));
-                self.write(executable.target, data);
-            },
-            "DEX" => {
-                self.reg_x = self.reg_x.wrapping_sub(1);
-                self.status.set(StatusFlags::ZERO, self.reg_x == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.reg_x));
-            },
-            "DEY" => {
-                self.reg_y = self.reg_y.wrapping_sub(1);
-                self.status.set(StatusFlags::ZERO, self.reg_y == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.reg_y));
-            }, 
-            "EOR" => {
-                self.accumulator &= executable.data;
-                self.status.set(StatusFlags::ZERO, self.accumulator == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.accumulator));
-            },
-            "INC" => {
-                let data = self.read(executable.target);
-                let data = data.wrapping_add(1);
+    //The 65C02 takes a cycle to fix up the flags after decimal ADC and SBC
+    fn decimal_fixup(&mut self, executable: &Executable) {
+        if self.variant == Variant::Wdc65C02 && self.is_flag_set(StatusFlags::DECIMAL) {
+            self.read(executable.target);
+        }
+    }
 
-                self.status.set(StatusFlags::ZERO, data == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(data));
+    //Binary addition with carry in and out. SBC is the same thing with the operand inverted
+    fn add_with_carry(&mut self, data: u8) {
+        let carry = self.is_flag_set(StatusFlags::CARRY) as u16;
+        let sum = self.accumulator as u16 + data as u16 + carry;
+        let result = sum as u8;
+
+        self.status.set(StatusFlags::CARRY, sum > 0xFF);
+        //Signed overflow is when both inputs have the same sign and the result doesn't
+        self.status.set(StatusFlags::OVERFLOW, (self.accumulator ^ result) & (data ^ result) & 0x80 != 0);
+        self.accumulator = result;
+        self.set_zero_negative(result);
+    }
 
-                self.write(executable.target, data);
-            },
-            "INX" => {
-                self.reg_x = self.reg_x.wrapping_sub(1);
-                self.status.set(StatusFlags::ZERO, self.reg_x == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.reg_x));
-            }, 
-            "INY" => {
-                self.reg_y = self.reg_y.wrapping_sub(1);
-                self.status.set(StatusFlags::ZERO, self.reg_y == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.reg_y));
-            }, 
-            "JMP" => {
-                self.program_counter = executable.target;
-            },
-            "JSR" => {
-                self.program_counter = self.program_counter.wrapping_sub(1);
-                //High byte
-                self.push_stack((self.program_counter >> 8) as u8);
-                //Low byte
-                self.push_stack((self.program_counter & 0b0000000011111111) as u8);
-
-                self.program_counter = executable.target;
-            },
-            "LDA" => {
-                self.status.set(StatusFlags::ZERO, executable.data == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(executable.data));
+    fn compare(&mut self, register: u8, data: u8) {
+        self.status.set(StatusFlags::CARRY, register >= data);
+        self.set_zero_negative(register.wrapping_sub(data));
+    }
 
-                self.accumulator = executable.data;
-            },
-            "LDX" => {
-                self.status.set(StatusFlags::ZERO, executable.data == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(executable.data));
+    //Taken branches cost a cycle, and another if they land on a different page.
+    //An interrupt that shows up on the extra cycle of a taken branch that stays on the page waits
+    //until after the next instruction. The NMI has already been latched, so it's hidden from the poll
+    //for that cycle and put back after
+    fn branch(&mut self, taken: bool, target: u16) {
+        if taken {
+            if self.run_irq && !self.prev_run_irq {
+                self.run_irq = false;
+            }
+            let late_nmi = self.need_nmi && !self.prev_need_nmi;
+            if late_nmi {
+                self.need_nmi = false;
+            }
+            self.read(self.program_counter);
+            self.need_nmi |= late_nmi;
+            if target & 0xFF00 != self.program_counter & 0xFF00 {
+                self.read((self.program_counter & 0xFF00) | (target & 0x00FF));
+            }
+            self.program_counter = target;
+        }
+    }
 
-                self.reg_x = executable.data;
-            },            
-            "LDY" => {
-                self.status.set(StatusFlags::ZERO, executable.data == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(executable.data));
+    fn adc(&mut self, executable: &Executable) {
+        let data = self.operand(executable);
+        self.add(data);
+        self.decimal_fixup(executable);
+    }
 
-                self.reg_y = executable.data;
-            },
-            "LSR" => {
-                let result: u8;
-                if executable.target != 0 {
-                    self.status.set(StatusFlags::CARRY, executable.data & 0b00000001 == 0b00000001);
-                    result = executable.data >> 1;
-                    self.status.set(StatusFlags::ZERO, result == 0);
-                    self.status.set(StatusFlags::NEGATIVE, is_negative(result));
-                    self.write(executable.target, result);
-                } else {
-                    self.status.set(StatusFlags::CARRY, self.accumulator & 0b00000001 == 0b00000001);
-                    result = self.accumulator >> 1;
-                    self.status.set(StatusFlags::ZERO, result == 0);
-                    self.status.set(StatusFlags::NEGATIVE, is_negative(result));
-                    self.accumulator = result;
-                }
-            },
-            "NAN" => {
-                
-            },
-            "ORA" => {
-                self.accumulator |= executable.data;
-                self.status.set(StatusFlags::ZERO, self.accumulator == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.accumulator));
-            },
-            "PHA" => {
-                self.push_stack(self.accumulator);
-            },
-            "PHP" => {
-                self.status.insert(StatusFlags::BRK);
-                self.push_stack(self.status.bits());
-            },
-            "PLA" => {
-                self.accumulator = self.pop_stack();
-            },
-            "PLP" => {
-                self.status = StatusFlags::from_bits_truncate(self.pop_stack());
-            },
-            "ROL" => {
-                let result: u8;
-                if executable.target != 0 {
-                    self.status.set(StatusFlags::CARRY, executable.data & 0b00000001 == 0b00000001);
-                    result = executable.data >> 1;
-                    self.status.set(StatusFlags::ZERO, result == 0);
-                    self.status.set(StatusFlags::NEGATIVE, is_negative(result));
-                    self.write(executable.target, result);
-                } else {
-                    self.status.set(StatusFlags::CARRY, self.accumulator & 0b00000001 == 0b00000001);
-                    result = self.accumulator >> 1;
-                    self.status.set(StatusFlags::ZERO, result == 0);
-                    self.status.set(StatusFlags::NEGATIVE, is_negative(result));
-                    self.accumulator = result;
-                }
-            },
-            "ROR" => {
-                let mut result: u8;
-                if executable.target != 0 {
-                    let new_carry = executable.data & 0b00000001;
-                    result = executable.data >> 1;
-                    result |= (self.status & StatusFlags::CARRY).bits();
-
-                    self.status.set(StatusFlags::CARRY, new_carry == 0b00000001);
-                    self.status.set(StatusFlags::ZERO, result == 0);
-                    self.status.set(StatusFlags::NEGATIVE, is_negative(result));
-
-                    self.write(executable.target, result);
-                } else {
-                    let new_carry = self.accumulator & 0b00000001;
-                    result = self.accumulator >> 1;
-                    result |= (self.status & StatusFlags::CARRY).bits();
+    fn and(&mut self, executable: &Executable) {
+        self.accumulator &= self.operand(executable);
+        self.set_zero_negative(self.accumulator);
+    }
 
-                    self.status.set(StatusFlags::CARRY, new_carry == 0b00000001);
-                    self.status.set(StatusFlags::ZERO, result == 0);
-                    self.status.set(StatusFlags::NEGATIVE, is_negative(result));
+    fn asl(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        let result = data << 1;
+        self.status.set(StatusFlags::CARRY, is_negative(data));
+        self.set_zero_negative(result);
+        self.write_back(executable, result);
+    }
 
-                    self.accumulator = result;
-                }                
-            },
-            "RTI" => {
-                self.status = StatusFlags::from_bits_truncate(self.pop_stack());
-                self.program_counter = self.pop_stack() as u16 * 256 + self.pop_stack() as u16;
-            },
-            "RTS" => {
-                self.program_counter = self.pop_stack() as u16 + (self.pop_stack() as u16) << 8 + 1;
-            },
-            "SBC" => {
-                let amt = if self.status & StatusFlags::CARRY == StatusFlags::CARRY { executable.data } else { executable.data + 1 };
-                let (result, did_overflow) = self.accumulator.overflowing_sub(amt);
+    fn bcc(&mut self, executable: &Executable) {
+        self.branch(!self.is_flag_set(StatusFlags::CARRY), executable.target);
+    }
 
-                self.status.set(StatusFlags::CARRY, !did_overflow);
-                self.status.set(StatusFlags::ZERO, result == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(result));
+    fn bcs(&mut self, executable: &Executable) {
+        self.branch(self.is_flag_set(StatusFlags::CARRY), executable.target);
+    }
 
-                self.accumulator = result;
-            },
-            "SEC" => {
-                self.status.insert(StatusFlags::CARRY);
-            },
-            "SED" => {
-                self.status.insert(StatusFlags::DECIMAL);
-            },
-            "SEI" => {
-                self.status.insert(StatusFlags::IRQ);
-            },
-            "STA" => {
-                self.write(executable.target, self.accumulator);
-            },
-            "STX" => {
-                self.write(executable.target, self.reg_x);
-            },
-            "STY" => {
-                self.write(executable.target, self.reg_y);
-            },
-            "TAX" => {
-                self.reg_x = self.accumulator;
-                self.status.set(StatusFlags::ZERO, self.reg_x == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.reg_x));
-            },
-            "TAY" => {
-                self.reg_y = self.accumulator;
-                self.status.set(StatusFlags::ZERO, self.reg_y == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.reg_y));
-            },
-            "TSX" => {
-                self.reg_x = self.stack_pointer;
-                self.status.set(StatusFlags::ZERO, self.reg_x == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.reg_x));
-            },
-            "TXA" => {
-                self.accumulator = self.reg_x;
-                self.status.set(StatusFlags::ZERO, self.accumulator == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.accumulator));
-            },
-            "TXS" => {
-                self.stack_pointer = self.reg_x;
-            },
-            "TYA" => {
-                self.accumulator = self.reg_y;
-                self.status.set(StatusFlags::ZERO, self.accumulator == 0);
-                self.status.set(StatusFlags::NEGATIVE, is_negative(self.accumulator));
-            },
-            _ => {
+    fn beq(&mut self, executable: &Executable) {
+        self.branch(self.is_flag_set(StatusFlags::ZERO), executable.target);
+    }
 
-            }
+    fn bit(&mut self, executable: &Executable) {
+        //N and V come straight from the operand, only Z looks at A
+        //The 65C02's BIT #imm only sets Z
+        let data = self.operand(executable);
+        if executable.addressing != AddressingMode::Immediate {
+            self.status.set(StatusFlags::OVERFLOW, data & 0b01000000 == 0b01000000);
+            self.status.set(StatusFlags::NEGATIVE, is_negative(data));
         }
+        self.status.set(StatusFlags::ZERO, self.accumulator & data == 0);
+    }
+
+    fn bmi(&mut self, executable: &Executable) {
+        self.branch(self.is_flag_set(StatusFlags::NEGATIVE), executable.target);
+    }
+
+    fn bne(&mut self, executable: &Executable) {
+        self.branch(!self.is_flag_set(StatusFlags::ZERO), executable.target);
+    }
+
+    fn bpl(&mut self, executable: &Executable) {
+        self.branch(!self.is_flag_set(StatusFlags::NEGATIVE), executable.target);
+    }
+
+    fn brk(&mut self, executable: &Executable) {
+        //BRK skips over a padding byte, so the return address is 2 past the opcode
+        self.interrupt(executable.address.wrapping_add(2), true);
+    }
+
+    fn bvc(&mut self, executable: &Executable) {
+        self.branch(!self.is_flag_set(StatusFlags::OVERFLOW), executable.target);
+    }
+
+    fn bvs(&mut self, executable: &Executable) {
+        self.branch(self.is_flag_set(StatusFlags::OVERFLOW), executable.target);
+    }
+
+    fn clc(&mut self, _executable: &Executable) {
+        self.status.remove(StatusFlags::CARRY);
+    }
+
+    fn cld(&mut self, _executable: &Executable) {
+        self.status.remove(StatusFlags::DECIMAL);
+    }
+
+    fn cli(&mut self, _executable: &Executable) {
+        self.status.remove(StatusFlags::IRQ);
+    }
+
+    fn clv(&mut self, _executable: &Executable) {
+        self.status.remove(StatusFlags::OVERFLOW);
+    }
+
+    fn cmp(&mut self, executable: &Executable) {
+        let data = self.operand(executable);
+        self.compare(self.accumulator, data);
+    }
+
+    fn cpx(&mut self, executable: &Executable) {
+        let data = self.operand(executable);
+        self.compare(self.reg_x, data);
+    }
+
+    fn cpy(&mut self, executable: &Executable) {
+        let data = self.operand(executable);
+        self.compare(self.reg_y, data);
+    }
+
+    fn dec(&mut self, executable: &Executable) {
+        let data = self.modify(executable).wrapping_sub(1);
+        self.set_zero_negative(data);
+        self.write_back(executable, data);
+    }
+
+    fn dex(&mut self, _executable: &Executable) {
+        self.reg_x = self.reg_x.wrapping_sub(1);
+        self.set_zero_negative(self.reg_x);
+    }
+
+    fn dey(&mut self, _executable: &Executable) {
+        self.reg_y = self.reg_y.wrapping_sub(1);
+        self.set_zero_negative(self.reg_y);
+    }
+
+    fn eor(&mut self, executable: &Executable) {
+        self.accumulator ^= self.operand(executable);
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn inc(&mut self, executable: &Executable) {
+        let data = self.modify(executable).wrapping_add(1);
+        self.set_zero_negative(data);
+        self.write_back(executable, data);
+    }
+
+    fn inx(&mut self, _executable: &Executable) {
+        self.reg_x = self.reg_x.wrapping_add(1);
+        self.set_zero_negative(self.reg_x);
+    }
+
+    fn iny(&mut self, _executable: &Executable) {
+        self.reg_y = self.reg_y.wrapping_add(1);
+        self.set_zero_negative(self.reg_y);
+    }
+
+    fn jmp(&mut self, executable: &Executable) {
+        self.program_counter = executable.target;
+    }
+
+    fn jsr(&mut self, _executable: &Executable) {
+        //The high byte of the target is fetched last, after the return address is pushed.
+        //The address pushed is the last byte of the JSR, RTS adds the 1 back on
+        let low = self.fetch() as u16;
+        self.stack_dummy_read();
+        self.push_stack((self.program_counter >> 8) as u8);
+        self.push_stack(self.program_counter as u8);
+        let high = (self.read(self.program_counter) as u16) << 8;
+        self.program_counter = low | high;
+    }
+
+    fn lda(&mut self, executable: &Executable) {
+        self.accumulator = self.operand(executable);
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn ldx(&mut self, executable: &Executable) {
+        self.reg_x = self.operand(executable);
+        self.set_zero_negative(self.reg_x);
+    }
+
+    fn ldy(&mut self, executable: &Executable) {
+        self.reg_y = self.operand(executable);
+        self.set_zero_negative(self.reg_y);
+    }
+
+    fn lsr(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        let result = data >> 1;
+        self.status.set(StatusFlags::CARRY, data & 0b00000001 == 0b00000001);
+        self.set_zero_negative(result);
+        self.write_back(executable, result);
+    }
+
+    fn ora(&mut self, executable: &Executable) {
+        self.accumulator |= self.operand(executable);
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn pha(&mut self, _executable: &Executable) {
+        self.push_stack(self.accumulator);
+    }
+
+    fn php(&mut self, _executable: &Executable) {
+        //B only exists on the stack, and it's always set when pushed by PHP
+        self.push_stack((self.status | StatusFlags::BRK | StatusFlags::UNUSED).bits());
+    }
+
+    fn pla(&mut self, _executable: &Executable) {
+        self.stack_dummy_read();
+        self.accumulator = self.pop_stack();
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn plp(&mut self, _executable: &Executable) {
+        self.stack_dummy_read();
+        self.status = (StatusFlags::from_bits_truncate(self.pop_stack()) - StatusFlags::BRK) | StatusFlags::UNUSED;
+    }
+
+    fn rol(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        let result = (data << 1) | self.is_flag_set(StatusFlags::CARRY) as u8;
+        self.status.set(StatusFlags::CARRY, is_negative(data));
+        self.set_zero_negative(result);
+        self.write_back(executable, result);
+    }
+
+    fn ror(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        let result = (data >> 1) | ((self.is_flag_set(StatusFlags::CARRY) as u8) << 7);
+        self.status.set(StatusFlags::CARRY, data & 0b00000001 == 0b00000001);
+        self.set_zero_negative(result);
+        self.write_back(executable, result);
+    }
+
+    fn rti(&mut self, _executable: &Executable) {
+        //B doesn't exist in the real register and U is always set
+        self.stack_dummy_read();
+        self.status = (StatusFlags::from_bits_truncate(self.pop_stack()) - StatusFlags::BRK) | StatusFlags::UNUSED;
+        let low = self.pop_stack() as u16;
+        let high = (self.pop_stack() as u16) << 8;
+        self.program_counter = low | high;
+    }
+
+    fn rts(&mut self, _executable: &Executable) {
+        self.stack_dummy_read();
+        let low = self.pop_stack() as u16;
+        let high = (self.pop_stack() as u16) << 8;
+        self.program_counter = low | high;
+        //The increment takes a cycle of its own
+        self.read(self.program_counter);
+        self.program_counter = self.program_counter.wrapping_add(1);
+    }
+
+    fn sbc(&mut self, executable: &Executable) {
+        let data = self.operand(executable);
+        self.subtract(data);
+        self.decimal_fixup(executable);
+    }
+
+    fn sec(&mut self, _executable: &Executable) {
+        self.status.insert(StatusFlags::CARRY);
+    }
+
+    fn sed(&mut self, _executable: &Executable) {
+        self.status.insert(StatusFlags::DECIMAL);
+    }
+
+    fn sei(&mut self, _executable: &Executable) {
+        self.status.insert(StatusFlags::IRQ);
+    }
+
+    fn sta(&mut self, executable: &Executable) {
+        self.write(executable.target, self.accumulator);
+    }
+
+    fn stx(&mut self, executable: &Executable) {
+        self.write(executable.target, self.reg_x);
+    }
+
+    fn sty(&mut self, executable: &Executable) {
+        self.write(executable.target, self.reg_y);
+    }
+
+    fn tax(&mut self, _executable: &Executable) {
+        self.reg_x = self.accumulator;
+        self.set_zero_negative(self.reg_x);
+    }
+
+    fn tay(&mut self, _executable: &Executable) {
+        self.reg_y = self.accumulator;
+        self.set_zero_negative(self.reg_y);
+    }
+
+    fn tsx(&mut self, _executable: &Executable) {
+        self.reg_x = self.stack_pointer;
+        self.set_zero_negative(self.reg_x);
+    }
+
+    fn txa(&mut self, _executable: &Executable) {
+        self.accumulator = self.reg_x;
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn txs(&mut self, _executable: &Executable) {
+        //The only transfer that doesn't touch the flags
+        self.stack_pointer = self.reg_x;
+    }
+
+    fn tya(&mut self, _executable: &Executable) {
+        self.accumulator = self.reg_y;
+        self.set_zero_negative(self.accumulator);
     }
 
-    //Returns if the frame is finished
-    pub fn clock(&mut self) -> bool {
-        let mut frame_done = false;
-        //The PPU clocks 3 times per CPU clock
-        for _i in 0..3 {
-            frame_done = frame_done || self.main_bus.clock_ppu();
+    //Memory operand NOPs still do the read, which matters for registers with read side effects
+    fn nop(&mut self, executable: &Executable) {
+        if executable.addressing != AddressingMode::Implicit {
+            self.operand(executable);
         }
-        if self.cycles_to_wait == 0 {
-            let exec: Executable = self.decode_next_instruction();
-            self.cycles_to_wait = exec.cycles;
-            self.execute(exec);
+        //The 65C02's $5C takes 8 cycles to do nothing
+        if executable.opcode == 0x5C && self.variant == Variant::Wdc65C02 {
+            for _ in 0..4 {
+                self.read(0xFF00 | (executable.target & 0x00FF));
+            }
+        }
+    }
+
+    //The undocumented opcodes. Most are two official instructions glued together
+
+    fn alr(&mut self, executable: &Executable) {
+        let data = self.accumulator & self.operand(executable);
+        self.accumulator = data >> 1;
+        self.status.set(StatusFlags::CARRY, data & 0b00000001 == 0b00000001);
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn anc(&mut self, executable: &Executable) {
+        self.accumulator &= self.operand(executable);
+        self.set_zero_negative(self.accumulator);
+        self.status.set(StatusFlags::CARRY, is_negative(self.accumulator));
+    }
+
+    fn ane(&mut self, executable: &Executable) {
+        let data = self.operand(executable);
+        self.accumulator = (self.accumulator | UNSTABLE_MAGIC) & self.reg_x & data;
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn arr(&mut self, executable: &Executable) {
+        //AND then ROR, but with C and V coming from bits 6 and 5 of the result
+        let data = self.accumulator & self.operand(executable);
+        self.accumulator = (data >> 1) | ((self.is_flag_set(StatusFlags::CARRY) as u8) << 7);
+        self.set_zero_negative(self.accumulator);
+        self.status.set(StatusFlags::OVERFLOW, ((self.accumulator >> 6) ^ (self.accumulator >> 5)) & 1 != 0);
+        if self.decimal_mode() {
+            self.arr_decimal(data);
         } else {
-            self.cycles_to_wait -= 1;
+            self.status.set(StatusFlags::CARRY, self.accumulator & 0b01000000 != 0);
+        }
+    }
+
+    //In decimal mode the NMOS chips then fix up each digit of the rotated value, deciding from the
+    //digits of the AND result. N, Z and V stay as they were and C is set if the high digit was fixed up
+    fn arr_decimal(&mut self, data: u8) {
+        if (data & 0x0F) + (data & 0x01) > 0x05 {
+            self.accumulator = (self.accumulator & 0xF0) | (self.accumulator.wrapping_add(0x06) & 0x0F);
         }
-        frame_done
+        let high = (data & 0xF0) as u16 + (data & 0x10) as u16 > 0x50;
+        if high {
+            self.accumulator = self.accumulator.wrapping_add(0x60);
+        }
+        self.status.set(StatusFlags::CARRY, high);
     }
 
-    pub fn get_frame_buffer(&mut self) -> &Box<[u8; 61440 * 3]> {
-        self.main_bus.get_frame_buffer()
+    fn axs(&mut self, executable: &Executable) {
+        //CMP's flags but the result goes into X
+        let data = self.operand(executable);
+        let register = self.accumulator & self.reg_x;
+        self.compare(register, data);
+        self.reg_x = register.wrapping_sub(data);
     }
 
-    fn push_stack(&mut self, data: u8) {
-        self.write(self.stack_pointer as u16 + 256, data);
-        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+    fn dcp(&mut self, executable: &Executable) {
+        let data = self.modify(executable).wrapping_sub(1);
+        self.write_back(executable, data);
+        self.compare(self.accumulator, data);
     }
 
-    fn pop_stack(&mut self) -> u8 {
-        self.stack_pointer = self.stack_pointer.wrapping_add(1);
-        self.read(self.stack_pointer as u16 + 256)
+    fn isb(&mut self, executable: &Executable) {
+        let data = self.modify(executable).wrapping_add(1);
+        self.write_back(executable, data);
+        self.subtract(data);
+    }
+
+    fn las(&mut self, executable: &Executable) {
+        let data = self.operand(executable) & self.stack_pointer;
+        self.accumulator = data;
+        self.reg_x = data;
+        self.stack_pointer = data;
+        self.set_zero_negative(data);
+    }
+
+    fn lax(&mut self, executable: &Executable) {
+        self.accumulator = self.operand(executable);
+        self.reg_x = self.accumulator;
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn lxa(&mut self, executable: &Executable) {
+        let data = self.operand(executable);
+        self.accumulator = (self.accumulator | UNSTABLE_MAGIC) & data;
+        self.reg_x = self.accumulator;
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn rla(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        let result = (data << 1) | self.is_flag_set(StatusFlags::CARRY) as u8;
+        self.status.set(StatusFlags::CARRY, is_negative(data));
+        self.write_back(executable, result);
+        self.accumulator &= result;
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn rra(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        let result = (data >> 1) | ((self.is_flag_set(StatusFlags::CARRY) as u8) << 7);
+        self.status.set(StatusFlags::CARRY, data & 0b00000001 == 0b00000001);
+        self.write_back(executable, result);
+        self.add(result);
+    }
+
+    fn sax(&mut self, executable: &Executable) {
+        self.write(executable.target, self.accumulator & self.reg_x);
+    }
+
+    //SHA, SHX, SHY and TAS store the value ANDed with the high byte of the base address plus one.
+    //If indexing carried into the high byte, that value replaces the high byte of the address too
+    fn unstable_store(&mut self, executable: &Executable, data: u8) {
+        let index = if executable.addressing == AddressingMode::AbsoluteIndexedX { self.reg_x } else { self.reg_y };
+        let base = executable.target.wrapping_sub(index as u16);
+        let data = data & ((base >> 8) as u8).wrapping_add(1);
+
+        let target = if base & 0xFF00 != executable.target & 0xFF00 {
+            ((data as u16) << 8) | (executable.target & 0x00FF)
+        } else {
+            executable.target
+        };
+        self.write(target, data);
+    }
+
+    fn sha(&mut self, executable: &Executable) {
+        self.unstable_store(executable, self.accumulator & self.reg_x);
+    }
+
+    fn shx(&mut self, executable: &Executable) {
+        self.unstable_store(executable, self.reg_x);
+    }
+
+    fn shy(&mut self, executable: &Executable) {
+        self.unstable_store(executable, self.reg_y);
+    }
+
+    fn slo(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        let result = data << 1;
+        self.status.set(StatusFlags::CARRY, is_negative(data));
+        self.write_back(executable, result);
+        self.accumulator |= result;
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn sre(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        let result = data >> 1;
+        self.status.set(StatusFlags::CARRY, data & 0b00000001 == 0b00000001);
+        self.write_back(executable, result);
+        self.accumulator ^= result;
+        self.set_zero_negative(self.accumulator);
+    }
+
+    fn tas(&mut self, executable: &Executable) {
+        self.stack_pointer = self.accumulator & self.reg_x;
+        self.unstable_store(executable, self.stack_pointer);
+    }
+
+    //The 65C02 additions
+
+    fn bra(&mut self, executable: &Executable) {
+        self.branch(true, executable.target);
+    }
+
+    fn phx(&mut self, _executable: &Executable) {
+        self.push_stack(self.reg_x);
+    }
+
+    fn phy(&mut self, _executable: &Executable) {
+        self.push_stack(self.reg_y);
+    }
+
+    fn plx(&mut self, _executable: &Executable) {
+        self.stack_dummy_read();
+        self.reg_x = self.pop_stack();
+        self.set_zero_negative(self.reg_x);
+    }
+
+    fn ply(&mut self, _executable: &Executable) {
+        self.stack_dummy_read();
+        self.reg_y = self.pop_stack();
+        self.set_zero_negative(self.reg_y);
+    }
+
+    fn stz(&mut self, executable: &Executable) {
+        self.write(executable.target, 0);
+    }
+
+    //TRB and TSB clear or set the bits of A in memory. Z says if any of them were set beforehand
+    fn trb(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        self.status.set(StatusFlags::ZERO, self.accumulator & data == 0);
+        self.write_back(executable, data & !self.accumulator);
+    }
+
+    fn tsb(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        self.status.set(StatusFlags::ZERO, self.accumulator & data == 0);
+        self.write_back(executable, data | self.accumulator);
+    }
+
+    fn rmb(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        self.write_back(executable, data & !(1 << ((executable.opcode >> 4) & 7)));
+    }
+
+    fn smb(&mut self, executable: &Executable) {
+        let data = self.modify(executable);
+        self.write_back(executable, data | (1 << ((executable.opcode >> 4) & 7)));
+    }
+
+    //Tests a zero page bit then branches like any other branch. The offset comes last
+    fn branch_on_bit(&mut self, executable: &Executable, set: bool) {
+        let data = self.read(executable.target);
+        self.read(executable.target);
+        let offset = self.fetch();
+        let target = self.program_counter.wrapping_add(offset as i8 as u16);
+        let bit = data & (1 << ((executable.opcode >> 4) & 7)) != 0;
+        self.branch(bit == set, target);
+    }
+
+    fn bbr(&mut self, executable: &Executable) {
+        self.branch_on_bit(executable, false);
+    }
+
+    fn bbs(&mut self, executable: &Executable) {
+        self.branch_on_bit(executable, true);
+    }
+
+    fn wai(&mut self, _executable: &Executable) {
+        self.read(self.program_counter);
+        self.waiting = true;
     }
 }
@@ -1,67 +1,145 @@
-#[path = "RAM.rs"] mod RAM;
-#[path = "Cartridge.rs"] mod Cartridge;
-#[path = "PPU.rs"] mod PPU;
+use crate::Bus::Bus;
+use crate::RAM;
+use crate::Cartridge;
+use crate::PPU;
+use crate::Error::Result;
 
 pub struct CPUBus {
     ram: RAM::RAM,
     ppu: PPU::PPU,
-    cart: Cartridge::Cartridge
+    cart: Cartridge::Cartridge,
+    //The last value on the data bus. Nothing drives it for unconnected addresses, so reads there
+    //see whatever was left on it
+    data_bus: u8,
+    //Sprite DMA copying a page to OAM, as the page and how far through its 512 cycles it is. Each
+    //byte is read on one cycle and written to $2004 on the next
+    oam_dma: Option<(u8, u16)>,
+    oam_dma_data: u8
 }
 
 impl CPUBus {
-    pub fn new() -> CPUBus {
-        let mut ret: CPUBus = CPUBus {
+    pub fn new(cart: Cartridge::Cartridge) -> CPUBus {
+        CPUBus {
             ram: RAM::RAM::new(),
             ppu: PPU::PPU::new(),
-            cart: Cartridge::Cartridge::new()
-        };
-        ret.cart.load_from_file("res/nestest.nes".to_string());
-        ret
+            cart,
+            data_bus: 0,
+            oam_dma: None,
+            oam_dma_data: 0
+        }
+    }
+
+    pub fn from_rom_file(path: &str) -> Result<CPUBus> {
+        Ok(CPUBus::new(Cartridge::Cartridge::from_file(path)?))
     }
 
-    pub fn clock_ppu(&mut self) -> bool {
-        self.ppu.clock()
+    pub fn from_rom_bytes(bytes: &[u8]) -> Result<CPUBus> {
+        Ok(CPUBus::new(Cartridge::Cartridge::from_bytes(bytes)?))
     }
 
-    pub fn get_frame_buffer(&mut self) -> &Box<[u8; 61440 * 3]> {
-        &self.ppu.frame_buffer
+    //Swaps out the cartridge, like pulling it out and putting a new one in
+    pub fn insert_cartridge(&mut self, cart: Cartridge::Cartridge) {
+        self.cart = cart;
     }
 
-    pub fn read(&mut self, address: u16) -> u8 {
-        if address <= 0x17FF {
+    pub fn frame_buffer(&self) -> &[u8] {
+        &self.ppu.frame_buffer[..]
+    }
+
+    pub fn ppu(&self) -> &PPU::PPU {
+        &self.ppu
+    }
+}
+
+impl Bus for CPUBus {
+    //Reads without side effects, for debuggers and trace logs. Gives what a read would return
+    fn peek(&self, address: u16) -> u8 {
+        if address <= 0x1FFF {
             self.ram.read(address)
-        } else if address >= 0x2000 && address <= 0x3FFF {
-            self.ppu.cpu_read(address & 0x2000)
-        } else if address == 0x4016 {
-            //TODO: Joypad inputs
-            0
-        } else if address == 0x4017 {
-            0
-        } else if address >= 0x8000 {
-            self.cart.read(address - 0x8000)
+        } else if (0x2000..=0x3FFF).contains(&address) {
+            self.ppu.peek_register(address)
+        } else if address == 0x4016 || address == 0x4017 {
+            self.data_bus & 0b11100000
+        } else if address >= 0x4020 {
+            self.cart.peek(address).unwrap_or(self.data_bus)
         } else {
-            0
+            self.data_bus
         }
     }
 
-    pub fn write(&mut self, address: u16, data: u8) {
-        if address < 0x17FF {
+    fn read(&mut self, address: u16) -> u8 {
+        let data = if address <= 0x1FFF {
+            self.ram.read(address)
+        } else if (0x2000..=0x3FFF).contains(&address) {
+            self.ppu.cpu_read(address, &mut self.cart)
+        } else if address == 0x4016 || address == 0x4017 {
+            //TODO: Joypad inputs. The ports only drive the bottom 5 bits
+            self.data_bus & 0b11100000
+        } else if address >= 0x4020 {
+            self.cart.read(address).unwrap_or(self.data_bus)
+        } else {
+            //Open bus
+            self.data_bus
+        };
+        self.data_bus = data;
+        data
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        self.data_bus = data;
+        if address <= 0x1FFF {
             self.ram.write(address, data);
-        } else if address >= 0x2000 && address <= 0x3FFF {
-            self.ppu.cpu_write(address & 0x2000, data);
-        } else if address >= 0x4000 && address <= 0x4017 {
-            match address {
-                0x4014 => {
-                    self.ppu.OAMDMA(self.ram.get_page(data));
-                },
-                _ => {
-
-                }
-            }
-        } else if address >= 0x8000 {
-            
+        } else if (0x2000..=0x3FFF).contains(&address) {
+            self.ppu.cpu_write(address, data, &mut self.cart);
+        } else if address == 0x4014 {
+            //Starts on the CPU's next read
+            self.oam_dma = Some((data, 0));
+        } else if address >= 0x4020 {
+            self.cart.write(address, data);
+        }
+    }
+
+    //One CPU cycle. The PPU runs 3 times as fast. Returns if a frame was finished
+    fn tick(&mut self) -> bool {
+        let mut frame_done = false;
+        for _i in 0..3 {
+            frame_done |= self.ppu.clock(&mut self.cart);
+        }
+        self.cart.clock();
+        frame_done
+    }
+
+    fn dma_pending(&self) -> bool {
+        self.oam_dma.is_some()
+    }
+
+    //The DMA unit reads the page through the bus, so it can come from anywhere
+    fn dma_cycle(&mut self, get: bool) -> bool {
+        let (page, cycle) = match self.oam_dma {
+            Some(dma) => dma,
+            None => return true
+        };
+        if get {
+            self.oam_dma_data = self.read((page as u16) << 8 | cycle >> 1);
         } else {
-            
+            self.write(0x2004, self.oam_dma_data);
         }
+        self.oam_dma = if cycle < 511 {Some((page, cycle + 1))} else {None};
+        self.oam_dma.is_none()
+    }
+
+    //The PPU's /NMI output
+    fn nmi(&self) -> bool {
+        self.ppu.nmi_line()
+    }
+
+    //Everything that can pull /IRQ low. The APU will join the cartridge here once there is one
+    fn irq(&self) -> bool {
+        self.cart.irq()
+    }
+
+    //The reset button only reaches the CPU and PPU. RAM and the cartridge keep their contents
+    fn reset(&mut self) {
+        self.ppu.reset();
     }
 }
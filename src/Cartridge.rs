@@ -2,47 +2,193 @@ use std::io::prelude::*;
 use std::io::BufReader;
 use std::fs::File;
 
-#[path = "Mappers/Mapper.rs"] mod Mapper;
+use crate::Mapper;
+use crate::CartridgeHeader;
+use crate::Error::{NesError, Result};
 
 pub struct Cartridge {
-    data: Box<[u8; 32768]>,
+    header: CartridgeHeader::CartridgeHeader,
+    prg_rom: Vec<u8>,
+    //Either CHR ROM or CHR RAM, depending on the header
+    chr: Vec<u8>,
+    //Both the volatile and battery backed parts
+    prg_ram: Vec<u8>,
+    //For spotting rising edges on PPU A12
+    last_a12: bool,
     mapper: Box<dyn Mapper::Mapper>
 }
 
 impl Cartridge {
+    //An empty slot. Reads are open bus
     pub fn new() -> Cartridge {
-        let amapper = Box::new(Mapper::Mapper0 {
-            nprg_banks: 1,
-            nchr_banks: 1
-        });
+        let header = CartridgeHeader::CartridgeHeader {
+            format: CartridgeHeader::HeaderFormat::INes,
+            prg_rom_size: 0,
+            chr_rom_size: 0,
+            prg_ram_size: 0,
+            prg_nvram_size: 0,
+            chr_ram_size: CartridgeHeader::CHR_BANK_SIZE,
+            chr_nvram_size: 0,
+            mapper: 0,
+            submapper: 0,
+            mirroring: CartridgeHeader::Mirroring::Horizontal,
+            battery: false,
+            trainer: false,
+            console: CartridgeHeader::ConsoleType::Nes,
+            timing: CartridgeHeader::TimingMode::Ntsc
+        };
         Cartridge {
-            data: Box::new([0; 32768]),
-            mapper: amapper
+            mapper: Box::new(Mapper::Mapper0::new(&header)),
+            prg_rom: Vec::new(),
+            chr: vec![0; header.chr_ram_size],
+            prg_ram: Vec::new(),
+            last_a12: false,
+            header
         }
     }
 
-    pub fn read(&mut self, address: u16) -> u8 {
-        self.data[self.mapper.map_cpu_address(address) as usize]
-    }
-
-    pub fn write(&mut self, address: u16) {
-        //This is ROM but used for bank switching
-    }
-
-    pub fn load_from_file(&mut self, path: String) -> std::io::Result<()> {
+    pub fn from_file(path: &str) -> Result<Cartridge> {
         let file = File::open(path)?;
         let mut reader = BufReader::new(file);
 
         let mut content = Vec::new();
         reader.read_to_end(&mut content)?;
 
-        let mut current = 0;
+        Cartridge::from_bytes(&content)
+    }
+
+    pub fn from_bytes(content: &[u8]) -> Result<Cartridge> {
+        let header = CartridgeHeader::CartridgeHeader::parse(content)?;
+
+        //The trainer sits between the header and PRG ROM
+        let mut offset = CartridgeHeader::HEADER_SIZE;
+        let mut trainer = None;
+        if header.trainer {
+            trainer = content.get(offset..offset + CartridgeHeader::TRAINER_SIZE);
+            offset += CartridgeHeader::TRAINER_SIZE;
+        }
+
+        //Done first so a silly size gets reported as too big for the mapper rather than truncated
+        let mapper = Mapper::create_mapper(&header)?;
+
+        //NES 2.0 exponent sizes can be absurd, so these saturate rather than overflow
+        let prg_end = offset.saturating_add(header.prg_rom_size);
+        let chr_end = prg_end.saturating_add(header.chr_rom_size);
+        if content.len() < chr_end {
+            return Err(NesError::TruncatedFile { expected: chr_end, actual: content.len() });
+        }
+
+        let prg_rom = content[offset..prg_end].to_vec();
+        let chr = if header.has_chr_ram() {
+            vec![0; header.chr_ram_size + header.chr_nvram_size]
+        } else {
+            content[prg_end..chr_end].to_vec()
+        };
+
+        //Trainers get loaded into PRG RAM at $7000
+        let mut prg_ram = vec![0; mapper.prg_ram_size()];
+        if let (Some(trainer), Some(dest)) = (trainer, prg_ram.get_mut(0x1000..0x1000 + CartridgeHeader::TRAINER_SIZE)) {
+            dest.copy_from_slice(trainer);
+        }
+
+        Ok(Cartridge {
+            mapper,
+            prg_rom,
+            chr,
+            prg_ram,
+            last_a12: false,
+            header
+        })
+    }
+
+    pub fn header(&self) -> &CartridgeHeader::CartridgeHeader {
+        &self.header
+    }
+
+    pub fn mirroring(&self) -> CartridgeHeader::Mirroring {
+        self.mapper.mirroring()
+    }
+
+    pub fn irq(&self) -> bool {
+        self.mapper.irq()
+    }
+
+    //What a frontend should save for battery backed carts
+    pub fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    pub fn has_battery(&self) -> bool {
+        self.mapper.has_battery()
+    }
+
+    pub fn clock(&mut self) {
+        self.mapper.cpu_clock();
+    }
+
+    //CPU reads from $4020-$FFFF. None if nothing on the cartridge drives the bus, in which case
+    //the CPU sees open bus
+    pub fn read(&mut self, address: u16) -> Option<u8> {
+        self.peek(address)
+    }
+
+    //Mappers only change state on writes so reading never has side effects
+    pub fn peek(&self, address: u16) -> Option<u8> {
+        match self.mapper.map_cpu_address(address) {
+            Mapper::MappedAddress::PrgRom(offset) => self.prg_rom.get(offset).copied(),
+            Mapper::MappedAddress::PrgRam(offset) => self.prg_ram.get(offset).copied(),
+            Mapper::MappedAddress::None => None
+        }
+    }
+
+    //CPU writes to $4020-$FFFF. This is ROM but used for bank switching
+    pub fn write(&mut self, address: u16, data: u8) {
+        let data = if self.mapper.has_bus_conflicts() {
+            match self.mapper.map_cpu_address(address) {
+                Mapper::MappedAddress::PrgRom(offset) => data & self.prg_rom.get(offset).copied().unwrap_or(0xFF),
+                _ => data
+            }
+        } else {
+            data
+        };
+
+        if let Mapper::MappedAddress::PrgRam(offset) = self.mapper.cpu_write(address, data) {
+            if let Some(byte) = self.prg_ram.get_mut(offset) {
+                *byte = data;
+            }
+        }
+    }
+
+    fn watch_a12(&mut self, address: u16) {
+        let a12 = address & 0x1000 != 0;
+        if a12 && !self.last_a12 {
+            self.mapper.ppu_a12_rise();
+        } else if !a12 && self.last_a12 {
+            self.mapper.ppu_a12_fall();
+        }
+        self.last_a12 = a12;
+    }
+
+    //Every PPU access goes past the cartridge, even nametable ones, so mappers can watch A12
+    pub fn ppu_address(&mut self, address: u16) {
+        self.watch_a12(address);
+    }
+
+    pub fn ppu_read(&mut self, address: u16) -> u8 {
+        self.watch_a12(address);
+        let mapped = self.mapper.map_ppu_address(address);
+        self.chr.get(mapped).copied().unwrap_or(0)
+    }
 
-        for token in &content[16..] {
-            print!("{:02x} ", token);
-            self.data[current] = *token;
-            current += 1;
+    pub fn ppu_write(&mut self, address: u16, data: u8) {
+        self.watch_a12(address);
+        self.mapper.ppu_write(address, data);
+        //CHR ROM can't be written to
+        if self.header.has_chr_ram() {
+            let mapped = self.mapper.map_ppu_address(address);
+            if let Some(byte) = self.chr.get_mut(mapped) {
+                *byte = data;
+            }
         }
-        Ok(())
     }
 }
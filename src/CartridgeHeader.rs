@@ -0,0 +1,177 @@
+use crate::Error::{NesError, Result};
+
+pub const HEADER_SIZE: usize = 16;
+pub const TRAINER_SIZE: usize = 512;
+pub const PRG_BANK_SIZE: usize = 16384;
+pub const CHR_BANK_SIZE: usize = 8192;
+pub const PRG_RAM_BANK_SIZE: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFormat {
+    INes,
+    Nes2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    FourScreen,
+    //Every nametable shows the same 1KiB. Only mappers can pick these
+    SingleScreenLower,
+    SingleScreenUpper
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleType {
+    Nes,
+    VsSystem,
+    Playchoice10,
+    //NES 2.0 only. The actual type is in byte 13
+    Extended(u8)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingMode {
+    Ntsc,
+    Pal,
+    MultiRegion,
+    Dendy
+}
+
+//The 16 byte header at the start of every .nes file. All sizes are in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartridgeHeader {
+    pub format: HeaderFormat,
+    pub prg_rom_size: usize,
+    //0 means the board has CHR RAM instead
+    pub chr_rom_size: usize,
+    //Volatile and battery backed RAM at $6000
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mirroring: Mirroring,
+    pub battery: bool,
+    pub trainer: bool,
+    pub console: ConsoleType,
+    pub timing: TimingMode
+}
+
+impl CartridgeHeader {
+    pub fn parse(bytes: &[u8]) -> Result<CartridgeHeader> {
+        if bytes.len() < 4 || bytes[0..4] != *b"NES\x1a" {
+            return Err(NesError::BadMagic);
+        }
+        if bytes.len() < HEADER_SIZE {
+            return Err(NesError::TruncatedFile { expected: HEADER_SIZE, actual: bytes.len() });
+        }
+
+        let flags6 = bytes[6];
+        let flags7 = bytes[7];
+
+        //Bit 3 overrides bit 0 and means the cart has its own nametable RAM
+        let mirroring = if flags6 & 0b00001000 != 0 {
+            Mirroring::FourScreen
+        } else if flags6 & 0b00000001 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        };
+        let battery = flags6 & 0b00000010 != 0;
+        let trainer = flags6 & 0b00000100 != 0;
+
+        //NES 2.0 is marked by bits 2-3 of flags 7 being 10
+        if flags7 & 0b00001100 == 0b00001000 {
+            return Ok(CartridgeHeader {
+                format: HeaderFormat::Nes2,
+                prg_rom_size: nes2_rom_size(bytes[4], bytes[9] & 0x0F, PRG_BANK_SIZE),
+                chr_rom_size: nes2_rom_size(bytes[5], bytes[9] >> 4, CHR_BANK_SIZE),
+                prg_ram_size: nes2_ram_size(bytes[10] & 0x0F),
+                prg_nvram_size: nes2_ram_size(bytes[10] >> 4),
+                chr_ram_size: nes2_ram_size(bytes[11] & 0x0F),
+                chr_nvram_size: nes2_ram_size(bytes[11] >> 4),
+                mapper: ((bytes[8] as u16 & 0x0F) << 8) | (flags7 & 0xF0) as u16 | (flags6 >> 4) as u16,
+                submapper: bytes[8] >> 4,
+                mirroring,
+                battery,
+                trainer,
+                console: match flags7 & 0b11 {
+                    0 => ConsoleType::Nes,
+                    1 => ConsoleType::VsSystem,
+                    2 => ConsoleType::Playchoice10,
+                    _ => ConsoleType::Extended(bytes[13] & 0x0F)
+                },
+                timing: match bytes[12] & 0b11 {
+                    0 => TimingMode::Ntsc,
+                    1 => TimingMode::Pal,
+                    2 => TimingMode::MultiRegion,
+                    _ => TimingMode::Dendy
+                }
+            });
+        }
+
+        //Some old dumpers wrote their name into bytes 7-15, which ruins the high mapper nibble.
+        //If the end of the header isn't blank, only trust the low nibble
+        let high_nibble = if bytes[12..16].iter().all(|b| *b == 0) { flags7 & 0xF0 } else { 0 };
+
+        let chr_rom_size = bytes[5] as usize * CHR_BANK_SIZE;
+        //Old dumps leave the PRG RAM size at 0, which means 8KiB. There's no way for an iNES header
+        //to say a cart has none, so everything gets it
+        let prg_ram_size = bytes[8].max(1) as usize * PRG_RAM_BANK_SIZE;
+
+        Ok(CartridgeHeader {
+            format: HeaderFormat::INes,
+            prg_rom_size: bytes[4] as usize * PRG_BANK_SIZE,
+            chr_rom_size,
+            prg_ram_size: if battery { 0 } else { prg_ram_size },
+            prg_nvram_size: if battery { prg_ram_size } else { 0 },
+            chr_ram_size: if chr_rom_size == 0 { CHR_BANK_SIZE } else { 0 },
+            chr_nvram_size: 0,
+            mapper: (high_nibble | (flags6 >> 4)) as u16,
+            submapper: 0,
+            mirroring,
+            battery,
+            trainer,
+            console: match flags7 & 0b11 {
+                1 => ConsoleType::VsSystem,
+                2 => ConsoleType::Playchoice10,
+                _ => ConsoleType::Nes
+            },
+            timing: TimingMode::Ntsc
+        })
+    }
+
+    //In 16KiB units, rounded up
+    pub fn prg_rom_banks(&self) -> usize {
+        self.prg_rom_size.div_ceil(PRG_BANK_SIZE)
+    }
+
+    //In 8KiB units, rounded up
+    pub fn chr_rom_banks(&self) -> usize {
+        self.chr_rom_size.div_ceil(CHR_BANK_SIZE)
+    }
+
+    pub fn has_chr_ram(&self) -> bool {
+        self.chr_rom_size == 0
+    }
+}
+
+//The MSB nibble normally extends the bank count, but 0xF switches to exponent-multiplier
+//notation where the LSB byte is EEEEEEMM and the size is 2^E * (MM * 2 + 1)
+fn nes2_rom_size(lsb: u8, msb: u8, bank_size: usize) -> usize {
+    if msb == 0x0F {
+        let exponent = (lsb >> 2) as u32;
+        let multiplier = (lsb & 0b11) as usize * 2 + 1;
+        2usize.saturating_pow(exponent).saturating_mul(multiplier)
+    } else {
+        (((msb as usize) << 8) | lsb as usize) * bank_size
+    }
+}
+
+//RAM sizes are stored as a shift count: 64 << n bytes, with 0 meaning none
+fn nes2_ram_size(shift: u8) -> usize {
+    if shift == 0 { 0 } else { 64 << shift }
+}
@@ -1,4 +1,127 @@
+use crate::CPU6502::{CPU6502, AddressingMode, Mnemonic, Variant, is_unofficial};
+use crate::Bus::Bus;
 
-pub struct Debugger {
-    
+//How many bytes an instruction takes up, opcode included
+fn instruction_length(addressing: AddressingMode) -> u16 {
+    match addressing {
+        AddressingMode::Implicit | AddressingMode::Accumulator => 1,
+        AddressingMode::Absolute | AddressingMode::AbsoluteIndexedX | AddressingMode::AbsoluteIndexedY
+        | AddressingMode::Indirect | AddressingMode::AbsoluteIndexedIndirect | AddressingMode::ZeroPageRelative => 3,
+        _ => 2
+    }
+}
+
+fn peek_word_zero_page<B: Bus>(bus: &B, pointer: u8) -> u16 {
+    bus.peek(pointer as u16) as u16 | ((bus.peek(pointer.wrapping_add(1) as u16) as u16) << 8)
+}
+
+//Disassembles the instruction at address in the style of Nintendulator, showing the effective
+//address and the value there as of right now
+pub fn disassemble<B: Bus>(cpu: &CPU6502<B>, address: u16) -> String {
+    let bus = cpu.bus();
+    let registers = cpu.registers();
+    let opcode = bus.peek(address);
+    let instruction = &cpu.variant().instructions()[opcode as usize];
+    //The 65C02's bit instructions have the bit number in the name, like RMB3
+    let name = match instruction.mnemonic {
+        Mnemonic::RMB | Mnemonic::SMB | Mnemonic::BBR | Mnemonic::BBS => {
+            format!("{}{}", instruction.mnemonic.name(), (opcode >> 4) & 7)
+        },
+        _ => instruction.mnemonic.name().to_string()
+    };
+
+    let low = bus.peek(address.wrapping_add(1));
+    let high = bus.peek(address.wrapping_add(2));
+    let absolute = low as u16 | ((high as u16) << 8);
+
+    match instruction.addressing {
+        AddressingMode::Implicit => name,
+        AddressingMode::Accumulator => format!("{} A", name),
+        AddressingMode::Immediate => format!("{} #${:02X}", name, low),
+        AddressingMode::ZeroPage => format!("{} ${:02X} = {:02X}", name, low, bus.peek(low as u16)),
+        AddressingMode::ZeroPageIndexedX | AddressingMode::ZeroPageIndexedY => {
+            let (index, register) = if instruction.addressing == AddressingMode::ZeroPageIndexedX {
+                ("X", registers.reg_x)
+            } else {
+                ("Y", registers.reg_y)
+            };
+            let target = low.wrapping_add(register);
+            format!("{} ${:02X},{} @ {:02X} = {:02X}", name, low, index, target, bus.peek(target as u16))
+        },
+        AddressingMode::Absolute => {
+            //Jumps don't touch memory at the target so there's no value to show
+            if instruction.mnemonic == Mnemonic::JMP || instruction.mnemonic == Mnemonic::JSR {
+                format!("{} ${:04X}", name, absolute)
+            } else {
+                format!("{} ${:04X} = {:02X}", name, absolute, bus.peek(absolute))
+            }
+        },
+        AddressingMode::AbsoluteIndexedX | AddressingMode::AbsoluteIndexedY => {
+            let (index, register) = if instruction.addressing == AddressingMode::AbsoluteIndexedX {
+                ("X", registers.reg_x)
+            } else {
+                ("Y", registers.reg_y)
+            };
+            let target = absolute.wrapping_add(register as u16);
+            format!("{} ${:04X},{} @ {:04X} = {:02X}", name, absolute, index, target, bus.peek(target))
+        },
+        AddressingMode::Relative => {
+            let target = address.wrapping_add(2).wrapping_add(low as i8 as u16);
+            format!("{} ${:04X}", name, target)
+        },
+        AddressingMode::Indirect => {
+            //The pointer's high byte comes from the start of the same page, like the real JMP.
+            //Apart from on the 65C02
+            let pointer_high = match cpu.variant() {
+                Variant::Wdc65C02 => absolute.wrapping_add(1),
+                _ => (absolute & 0xFF00) | (absolute.wrapping_add(1) & 0x00FF)
+            };
+            let target = bus.peek(absolute) as u16 | ((bus.peek(pointer_high) as u16) << 8);
+            format!("{} (${:04X}) = {:04X}", name, absolute, target)
+        },
+        AddressingMode::IndexedIndirect => {
+            let pointer = low.wrapping_add(registers.reg_x);
+            let target = peek_word_zero_page(bus, pointer);
+            format!("{} (${:02X},X) @ {:02X} = {:04X} = {:02X}", name, low, pointer, target, bus.peek(target))
+        },
+        AddressingMode::IndirectIndexed => {
+            let base = peek_word_zero_page(bus, low);
+            let target = base.wrapping_add(registers.reg_y as u16);
+            format!("{} (${:02X}),Y = {:04X} @ {:04X} = {:02X}", name, low, base, target, bus.peek(target))
+        },
+        AddressingMode::ZeroPageIndirect => {
+            let target = peek_word_zero_page(bus, low);
+            format!("{} (${:02X}) = {:04X} = {:02X}", name, low, target, bus.peek(target))
+        },
+        AddressingMode::AbsoluteIndexedIndirect => {
+            let pointer = absolute.wrapping_add(registers.reg_x as u16);
+            let target = bus.peek(pointer) as u16 | ((bus.peek(pointer.wrapping_add(1)) as u16) << 8);
+            format!("{} (${:04X},X) = {:04X}", name, absolute, target)
+        },
+        AddressingMode::ZeroPageRelative => {
+            let target = address.wrapping_add(3).wrapping_add(high as i8 as u16);
+            format!("{} ${:02X} = {:02X},${:04X}", name, low, bus.peek(low as u16), target)
+        }
+    }
+}
+
+//One line of a Nintendulator style trace log for the instruction about to run, e.g.
+//C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+//Unofficial opcodes get a * in front of the mnemonic
+pub fn trace_line(cpu: &CPU6502) -> String {
+    let bus = cpu.bus();
+    let registers = cpu.registers();
+    let address = registers.program_counter;
+    let instruction = &cpu.variant().instructions()[bus.peek(address) as usize];
+
+    let bytes: Vec<String> = (0..instruction_length(instruction.addressing))
+        .map(|i| format!("{:02X}", bus.peek(address.wrapping_add(i))))
+        .collect();
+    let (scanline, dot) = bus.ppu().position();
+    let marker = if is_unofficial(cpu.variant(), bus.peek(address)) { '*' } else { ' ' };
+
+    format!("{:04X}  {:<8} {}{:<31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+        address, bytes.join(" "), marker, disassemble(cpu, address),
+        registers.accumulator, registers.reg_x, registers.reg_y, registers.status, registers.stack_pointer,
+        scanline, dot, cpu.total_cycles())
 }
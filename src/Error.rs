@@ -0,0 +1,50 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum NesError {
+    //The file doesn't start with "NES" followed by an MS-DOS EOF
+    BadMagic,
+    //The header promises more PRG/CHR data than the file has
+    TruncatedFile { expected: usize, actual: usize },
+    UnsupportedMapper(u16),
+    //The header asks for more ROM than the mapper (or the address space) can hold
+    OversizedImage { size: usize },
+    Io(io::Error),
+    //The CPU hit one of the KIL opcodes, or a 65C02 STP, and locked up
+    CpuJam { opcode: u8, address: u16 }
+}
+
+pub type Result<T> = std::result::Result<T, NesError>;
+
+impl fmt::Display for NesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NesError::BadMagic => write!(f, "not an iNES image"),
+            NesError::TruncatedFile { expected, actual } => {
+                write!(f, "ROM image is truncated: expected {} bytes but got {}", expected, actual)
+            },
+            NesError::UnsupportedMapper(mapper) => write!(f, "mapper {} isn't supported", mapper),
+            NesError::OversizedImage { size } => write!(f, "ROM image is too large ({} bytes)", size),
+            NesError::Io(e) => write!(f, "{}", e),
+            NesError::CpuJam { opcode, address } => {
+                write!(f, "CPU jammed on opcode {:02X} at {:04X}", opcode, address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NesError::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for NesError {
+    fn from(e: io::Error) -> NesError {
+        NesError::Io(e)
+    }
+}
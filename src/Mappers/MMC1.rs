@@ -0,0 +1,206 @@
+use crate::CartridgeHeader::{CartridgeHeader, Mirroring};
+use crate::Mapper::{Mapper, MappedAddress};
+
+const PRG_BANK_SIZE: usize = 16384;
+const CHR_BANK_SIZE: usize = 4096;
+const PRG_RAM_BANK_SIZE: usize = 8192;
+
+//The shift register is full when this bit makes it to bit 0
+const SHIFT_RESET: u8 = 0b10000;
+
+//Mapper 1. The registers are 5 bits wide and written one bit at a time through a serial port
+pub struct MMC1 {
+    shift: u8,
+    control: u8,
+    chr_bank0: u8,
+    chr_bank1: u8,
+    prg_bank: u8,
+    //The SxROM boards use the CHR registers for PRG and RAM banking, and in 4KiB mode
+    //it's the last one written that counts
+    last_chr_bank1: bool,
+
+    prg_rom_size: usize,
+    chr_size: usize,
+    prg_ram_size: usize,
+    battery: bool,
+    chr_ram: bool,
+
+    //The serial port ignores writes on back to back cycles, which is what a read-modify-write does
+    cycle: u64,
+    last_write_cycle: Option<u64>
+}
+
+impl MMC1 {
+    pub fn new(header: &CartridgeHeader) -> MMC1 {
+        MMC1 {
+            shift: SHIFT_RESET,
+            //Powers on with the last bank fixed at $C000
+            control: 0x0C,
+            chr_bank0: 0,
+            chr_bank1: 0,
+            prg_bank: 0,
+            last_chr_bank1: false,
+
+            prg_rom_size: header.prg_rom_size,
+            chr_size: if header.has_chr_ram() { header.chr_ram_size + header.chr_nvram_size } else { header.chr_rom_size },
+            prg_ram_size: header.prg_ram_size + header.prg_nvram_size,
+            battery: header.prg_nvram_size != 0,
+            chr_ram: header.has_chr_ram(),
+
+            cycle: 0,
+            last_write_cycle: None
+        }
+    }
+
+    //The CHR register that the SxROM boards steal lines from
+    fn board_bank(&self) -> u8 {
+        if self.control & 0b10000 != 0 && self.last_chr_bank1 {
+            self.chr_bank1
+        } else {
+            self.chr_bank0
+        }
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        //SNROM wires CHR bit 4 to the RAM's chip enable. SUROM/SXROM use that bit for PRG instead
+        if self.chr_ram && self.prg_rom_size <= 256 * 1024 && self.board_bank() & 0b10000 != 0 {
+            return false;
+        }
+        self.prg_bank & 0b10000 == 0
+    }
+
+    fn map_prg_ram(&self, address: u16) -> MappedAddress {
+        if self.prg_ram_size == 0 || !self.prg_ram_enabled() {
+            return MappedAddress::None;
+        }
+
+        //SOROM has 16KiB of RAM banked by CHR bit 3, SXROM has 32KiB banked by bits 2-3
+        let bank = match self.prg_ram_size {
+            16384 => (self.board_bank() >> 3) & 0b1,
+            32768 => (self.board_bank() >> 2) & 0b11,
+            _ => 0
+        } as usize;
+
+        let offset = bank * PRG_RAM_BANK_SIZE + (address - 0x6000) as usize;
+        MappedAddress::PrgRam(offset % self.prg_ram_size)
+    }
+
+    fn map_prg_rom(&self, address: u16) -> MappedAddress {
+        //SUROM/SXROM have 512KiB split into two 256KiB halves, picked by CHR bit 4
+        let outer = if self.prg_rom_size > 256 * 1024 {
+            (self.board_bank() & 0b10000) as usize
+        } else {
+            0
+        };
+
+        let bank = (self.prg_bank & 0b1111) as usize;
+        let last = (self.prg_rom_size / PRG_BANK_SIZE).saturating_sub(1).min(15);
+        let upper = address >= 0xC000;
+
+        let bank = match (self.control >> 2) & 0b11 {
+            //32KiB mode ignores the low bit
+            0 | 1 => (bank & !1) | upper as usize,
+            //First bank fixed at $8000
+            2 => if upper { bank } else { 0 },
+            //Last bank fixed at $C000
+            _ => if upper { last } else { bank }
+        };
+
+        let offset = (outer | bank) * PRG_BANK_SIZE + (address & 0x3FFF) as usize;
+        if self.prg_rom_size == 0 {
+            MappedAddress::None
+        } else {
+            MappedAddress::PrgRom(offset % self.prg_rom_size)
+        }
+    }
+
+    fn write_register(&mut self, address: u16, value: u8) {
+        match address {
+            0x8000..=0x9FFF => self.control = value,
+            0xA000..=0xBFFF => {
+                self.chr_bank0 = value;
+                self.last_chr_bank1 = false;
+            },
+            0xC000..=0xDFFF => {
+                self.chr_bank1 = value;
+                self.last_chr_bank1 = true;
+            },
+            _ => self.prg_bank = value
+        }
+    }
+}
+
+impl Mapper for MMC1 {
+    fn map_cpu_address(&self, address: u16) -> MappedAddress {
+        match address {
+            0x6000..=0x7FFF => self.map_prg_ram(address),
+            0x8000..=0xFFFF => self.map_prg_rom(address),
+            _ => MappedAddress::None
+        }
+    }
+
+    fn map_ppu_address(&self, address: u16) -> usize {
+        let address = (address & 0x1FFF) as usize;
+        let offset = if self.control & 0b10000 == 0 {
+            //8KiB mode ignores the low bit
+            (self.chr_bank0 & 0b11110) as usize * CHR_BANK_SIZE + address
+        } else if address < 0x1000 {
+            self.chr_bank0 as usize * CHR_BANK_SIZE + address
+        } else {
+            self.chr_bank1 as usize * CHR_BANK_SIZE + (address & 0x0FFF)
+        };
+
+        if self.chr_size == 0 { 0 } else { offset % self.chr_size }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) -> MappedAddress {
+        if address < 0x8000 {
+            return match address {
+                0x6000..=0x7FFF => self.map_prg_ram(address),
+                _ => MappedAddress::None
+            };
+        }
+
+        let back_to_back = self.last_write_cycle.is_some_and(|last| last + 1 >= self.cycle);
+        self.last_write_cycle = Some(self.cycle);
+        if back_to_back {
+            return MappedAddress::None;
+        }
+
+        if data & 0x80 != 0 {
+            //Reset the shift register and go back to fixing the last bank
+            self.shift = SHIFT_RESET;
+            self.control |= 0x0C;
+        } else {
+            let full = self.shift & 1 != 0;
+            self.shift = (self.shift >> 1) | ((data & 1) << 4);
+            if full {
+                //Which register gets written only depends on the address of the 5th write
+                self.write_register(address, self.shift);
+                self.shift = SHIFT_RESET;
+            }
+        }
+        MappedAddress::None
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0b11 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal
+        }
+    }
+
+    fn prg_ram_size(&self) -> usize {
+        self.prg_ram_size
+    }
+
+    fn has_battery(&self) -> bool {
+        self.battery
+    }
+
+    fn cpu_clock(&mut self) {
+        self.cycle += 1;
+    }
+}
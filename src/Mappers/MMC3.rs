@@ -0,0 +1,224 @@
+use crate::CartridgeHeader::{CartridgeHeader, Mirroring};
+use crate::Mapper::{Mapper, MappedAddress};
+
+const PRG_BANK_SIZE: usize = 8192;
+const CHR_BANK_SIZE: usize = 1024;
+
+//A12 has to sit low for this many CPU cycles before a rise clocks the IRQ counter. This filters out
+//the quick toggling during sprite fetches so the counter only sees one rise per scanline
+const A12_FILTER_CYCLES: u64 = 3;
+
+//The two revisions of the chip disagree on when a counter of 0 fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrqVariant {
+    //MMC3B/C. Fires every time the counter is 0 after being clocked, even if it was reloaded to 0
+    Sharp,
+    //MMC3A. Only fires when the counter is decremented to 0 or a reload was asked for
+    Nec
+}
+
+//Mapper 4
+pub struct MMC3 {
+    bank_select: u8,
+    //R0-R7. R0 and R1 are 2KiB CHR banks, R2-R5 are 1KiB CHR banks and R6-R7 are 8KiB PRG banks
+    banks: [u8; 8],
+    horizontal: bool,
+    prg_ram_enabled: bool,
+    prg_ram_protected: bool,
+
+    irq_variant: IrqVariant,
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+
+    cycle: u64,
+    a12_low_since: u64,
+
+    prg_rom_size: usize,
+    chr_size: usize,
+    prg_ram_size: usize,
+    battery: bool,
+    four_screen: bool
+}
+
+impl MMC3 {
+    pub fn new(header: &CartridgeHeader, irq_variant: IrqVariant) -> MMC3 {
+        MMC3 {
+            bank_select: 0,
+            banks: [0, 2, 4, 5, 6, 7, 0, 1],
+            horizontal: false,
+            prg_ram_enabled: true,
+            prg_ram_protected: false,
+
+            irq_variant,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+
+            cycle: 0,
+            a12_low_since: 0,
+
+            prg_rom_size: header.prg_rom_size,
+            chr_size: if header.has_chr_ram() { header.chr_ram_size + header.chr_nvram_size } else { header.chr_rom_size },
+            prg_ram_size: header.prg_ram_size + header.prg_nvram_size,
+            battery: header.prg_nvram_size != 0,
+            four_screen: header.mirroring == Mirroring::FourScreen
+        }
+    }
+
+    fn map_prg_ram(&self, address: u16) -> MappedAddress {
+        if self.prg_ram_size == 0 || !self.prg_ram_enabled {
+            MappedAddress::None
+        } else {
+            MappedAddress::PrgRam((address - 0x6000) as usize % self.prg_ram_size)
+        }
+    }
+
+    fn clock_irq_counter(&mut self) {
+        let old_counter = self.irq_counter;
+        let reloading = self.irq_reload;
+
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+        } else {
+            self.irq_counter -= 1;
+        }
+        self.irq_reload = false;
+
+        let fire = match self.irq_variant {
+            IrqVariant::Sharp => self.irq_counter == 0,
+            IrqVariant::Nec => self.irq_counter == 0 && (old_counter != 0 || reloading)
+        };
+        if fire && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+}
+
+impl Mapper for MMC3 {
+    fn map_cpu_address(&self, address: u16) -> MappedAddress {
+        if address < 0x8000 {
+            return match address {
+                0x6000..=0x7FFF => self.map_prg_ram(address),
+                _ => MappedAddress::None
+            };
+        }
+        if self.prg_rom_size == 0 {
+            return MappedAddress::None;
+        }
+
+        let last = self.prg_rom_size / PRG_BANK_SIZE;
+        let second_last = last.saturating_sub(2);
+        let prg_mode = self.bank_select & 0b01000000 != 0;
+
+        let bank = match (address - 0x8000) / 0x2000 {
+            0 => if prg_mode { second_last } else { self.banks[6] as usize },
+            1 => self.banks[7] as usize,
+            2 => if prg_mode { self.banks[6] as usize } else { second_last },
+            _ => last.saturating_sub(1)
+        };
+
+        MappedAddress::PrgRom((bank * PRG_BANK_SIZE + (address & 0x1FFF) as usize) % self.prg_rom_size)
+    }
+
+    fn map_ppu_address(&self, address: u16) -> usize {
+        //CHR inversion swaps which half of pattern memory gets the 2KiB banks
+        let mut address = address & 0x1FFF;
+        if self.bank_select & 0b10000000 != 0 {
+            address ^= 0x1000;
+        }
+
+        let bank = match address / 0x400 {
+            0 | 1 => (self.banks[0] & !1) as usize + (address / 0x400) as usize,
+            2 | 3 => (self.banks[1] & !1) as usize + (address / 0x400 - 2) as usize,
+            n => self.banks[n as usize - 2] as usize
+        };
+
+        let offset = bank * CHR_BANK_SIZE + (address & 0x3FF) as usize;
+        if self.chr_size == 0 { 0 } else { offset % self.chr_size }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) -> MappedAddress {
+        let even = address & 1 == 0;
+        match address {
+            0x6000..=0x7FFF => {
+                return if self.prg_ram_protected { MappedAddress::None } else { self.map_prg_ram(address) };
+            },
+            0x8000..=0x9FFF => {
+                if even {
+                    self.bank_select = data;
+                } else {
+                    self.banks[(self.bank_select & 0b111) as usize] = data;
+                }
+            },
+            0xA000..=0xBFFF => {
+                if even {
+                    self.horizontal = data & 1 != 0;
+                } else {
+                    self.prg_ram_enabled = data & 0b10000000 != 0;
+                    self.prg_ram_protected = data & 0b01000000 != 0;
+                }
+            },
+            0xC000..=0xDFFF => {
+                if even {
+                    self.irq_latch = data;
+                } else {
+                    //The counter gets reloaded on the next clock
+                    self.irq_counter = 0;
+                    self.irq_reload = true;
+                }
+            },
+            0xE000..=0xFFFF => {
+                if even {
+                    //Disabling also acknowledges
+                    self.irq_enabled = false;
+                    self.irq_pending = false;
+                } else {
+                    self.irq_enabled = true;
+                }
+            },
+            _ => {}
+        }
+        MappedAddress::None
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.four_screen {
+            Mirroring::FourScreen
+        } else if self.horizontal {
+            Mirroring::Horizontal
+        } else {
+            Mirroring::Vertical
+        }
+    }
+
+    fn prg_ram_size(&self) -> usize {
+        self.prg_ram_size
+    }
+
+    fn has_battery(&self) -> bool {
+        self.battery
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn ppu_a12_rise(&mut self) {
+        if self.cycle - self.a12_low_since >= A12_FILTER_CYCLES {
+            self.clock_irq_counter();
+        }
+    }
+
+    fn ppu_a12_fall(&mut self) {
+        self.a12_low_since = self.cycle;
+    }
+
+    fn cpu_clock(&mut self) {
+        self.cycle += 1;
+    }
+}
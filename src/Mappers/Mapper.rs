@@ -1,24 +1,346 @@
+use crate::CartridgeHeader::{CartridgeHeader, Mirroring, PRG_BANK_SIZE, CHR_BANK_SIZE};
+use crate::Error::{NesError, Result};
+use crate::MMC1::MMC1;
+use crate::MMC3::{MMC3, IrqVariant};
+
+//Where a CPU access to cartridge space ends up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappedAddress {
+    //Offset into PRG ROM
+    PrgRom(usize),
+    //Offset into PRG RAM
+    PrgRam(usize),
+    //Nothing drives the bus
+    None
+}
+
 pub trait Mapper {
-    fn map_cpu_address(&self, cpu_address: u16) -> u16;
+    //Called for CPU reads from $4020-$FFFF
+    fn map_cpu_address(&self, address: u16) -> MappedAddress;
+
+    //Called for PPU reads and writes to $0000-$1FFF. Returns an offset into CHR ROM/RAM
+    fn map_ppu_address(&self, address: u16) -> usize;
+
+    //Called for CPU writes to $4020-$FFFF. This is where bank switching registers live.
+    //Returns where the write should land, if it lands in memory at all
+    fn cpu_write(&mut self, address: u16, data: u8) -> MappedAddress;
+
+    //Called for PPU writes to $0000-$1FFF, for mappers that snoop on them
+    fn ppu_write(&mut self, _address: u16, _data: u8) {}
+
+    //The nametable layout the board is currently wired for
+    fn mirroring(&self) -> Mirroring;
+
+    //How much PRG RAM the board has for $6000-$7FFF, battery backed or not.
+    //The cartridge allocates this much and map_cpu_address/cpu_write index into it
+    fn prg_ram_size(&self) -> usize {
+        0
+    }
+
+    //If the PRG RAM keeps its contents with the power off, so it's worth saving
+    fn has_battery(&self) -> bool {
+        false
+    }
+
+    //The mapper's IRQ line. This is level triggered so stays high until the mapper is acknowledged
+    fn irq(&self) -> bool {
+        false
+    }
 
-    fn map_ppu_address(&self, ppu_address: u16) -> u16;
+    //Called when PPU address line A12 goes from low to high
+    fn ppu_a12_rise(&mut self) {}
+
+    //Called when PPU address line A12 goes from high to low
+    fn ppu_a12_fall(&mut self) {}
+
+    //Called once per CPU cycle
+    fn cpu_clock(&mut self) {}
+
+    //Discrete logic boards where the ROM and the written value fight over the bus.
+    //The value that gets through is the AND of the two
+    fn has_bus_conflicts(&self) -> bool {
+        false
+    }
 }
 
 pub struct Mapper0 {
     pub nprg_banks: u8,
-    pub nchr_banks: u8
+    pub nchr_banks: u8,
+    pub prg_ram_size: usize,
+    pub battery: bool,
+    pub mirroring: Mirroring
+}
+
+impl Mapper0 {
+    pub fn new(header: &CartridgeHeader) -> Mapper0 {
+        Mapper0 {
+            nprg_banks: header.prg_rom_banks() as u8,
+            nchr_banks: header.chr_rom_banks() as u8,
+            prg_ram_size: header.prg_ram_size + header.prg_nvram_size,
+            battery: header.prg_nvram_size != 0,
+            mirroring: header.mirroring
+        }
+    }
+
+    fn map_prg_ram(&self, address: u16) -> MappedAddress {
+        //iNES headers always get at least 8KiB here since old dumps don't say. Only a NES 2.0 header
+        //can ask for none, and then nothing answers
+        if self.prg_ram_size == 0 {
+            MappedAddress::None
+        } else {
+            MappedAddress::PrgRam((address - 0x6000) as usize % self.prg_ram_size)
+        }
+    }
 }
 
 impl Mapper for Mapper0 {
-    fn map_cpu_address(&self, address: u16) -> u16 {
-        if self.nprg_banks > 1 {
-            address & 0x7FFF
+    fn map_cpu_address(&self, address: u16) -> MappedAddress {
+        match address {
+            0x6000..=0x7FFF => self.map_prg_ram(address),
+            0x8000..=0xFFFF => {
+                //A single 16KiB bank is mirrored into both halves
+                if self.nprg_banks > 1 {
+                    MappedAddress::PrgRom((address & 0x7FFF) as usize)
+                } else {
+                    MappedAddress::PrgRom((address & 0x3FFF) as usize)
+                }
+            },
+            _ => MappedAddress::None
+        }
+    }
+
+    fn map_ppu_address(&self, address: u16) -> usize {
+        (address & 0x1FFF) as usize
+    }
+
+    fn cpu_write(&mut self, address: u16, _data: u8) -> MappedAddress {
+        //No registers to write to
+        match address {
+            0x6000..=0x7FFF => self.map_prg_ram(address),
+            _ => MappedAddress::None
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_ram_size(&self) -> usize {
+        self.prg_ram_size
+    }
+
+    fn has_battery(&self) -> bool {
+        self.battery
+    }
+}
+
+//For the discrete boards, NES 2.0 submapper 1 means no bus conflicts and 2 means bus conflicts.
+//Anything else falls back to what the original board did
+fn bus_conflicts(header: &CartridgeHeader, default: bool) -> bool {
+    match header.submapper {
+        1 => false,
+        2 => true,
+        _ => default
+    }
+}
+
+//Mapper 2. 16KiB switchable at $8000 with the last bank fixed at $C000
+pub struct UxROM {
+    bank: u8,
+    prg_rom_size: usize,
+    mirroring: Mirroring,
+    bus_conflicts: bool
+}
+
+impl UxROM {
+    pub fn new(header: &CartridgeHeader) -> UxROM {
+        UxROM {
+            bank: 0,
+            prg_rom_size: header.prg_rom_size,
+            mirroring: header.mirroring,
+            bus_conflicts: bus_conflicts(header, true)
+        }
+    }
+}
+
+impl Mapper for UxROM {
+    fn map_cpu_address(&self, address: u16) -> MappedAddress {
+        if address < 0x8000 || self.prg_rom_size == 0 {
+            return MappedAddress::None;
+        }
+
+        let bank = if address >= 0xC000 {
+            (self.prg_rom_size / PRG_BANK_SIZE).saturating_sub(1)
+        } else {
+            self.bank as usize
+        };
+        MappedAddress::PrgRom((bank * PRG_BANK_SIZE + (address & 0x3FFF) as usize) % self.prg_rom_size)
+    }
+
+    fn map_ppu_address(&self, address: u16) -> usize {
+        (address & 0x1FFF) as usize
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) -> MappedAddress {
+        if address >= 0x8000 {
+            self.bank = data;
+        }
+        MappedAddress::None
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        self.bus_conflicts
+    }
+}
+
+//Mapper 3. Fixed PRG like NROM with 8KiB CHR banks
+pub struct CNROM {
+    bank: u8,
+    nprg_banks: u8,
+    chr_size: usize,
+    mirroring: Mirroring,
+    bus_conflicts: bool
+}
+
+impl CNROM {
+    pub fn new(header: &CartridgeHeader) -> CNROM {
+        CNROM {
+            bank: 0,
+            nprg_banks: header.prg_rom_banks() as u8,
+            chr_size: if header.has_chr_ram() { header.chr_ram_size } else { header.chr_rom_size },
+            mirroring: header.mirroring,
+            bus_conflicts: bus_conflicts(header, true)
+        }
+    }
+}
+
+impl Mapper for CNROM {
+    fn map_cpu_address(&self, address: u16) -> MappedAddress {
+        match address {
+            0x8000..=0xFFFF if self.nprg_banks > 1 => MappedAddress::PrgRom((address & 0x7FFF) as usize),
+            0x8000..=0xFFFF => MappedAddress::PrgRom((address & 0x3FFF) as usize),
+            _ => MappedAddress::None
+        }
+    }
+
+    fn map_ppu_address(&self, address: u16) -> usize {
+        let offset = self.bank as usize * CHR_BANK_SIZE + (address & 0x1FFF) as usize;
+        if self.chr_size == 0 { 0 } else { offset % self.chr_size }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) -> MappedAddress {
+        if address >= 0x8000 {
+            self.bank = data;
+        }
+        MappedAddress::None
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        self.bus_conflicts
+    }
+}
+
+//Mapper 7. 32KiB PRG banks and a single screen picked by bit 4
+pub struct AxROM {
+    bank: u8,
+    prg_rom_size: usize,
+    bus_conflicts: bool
+}
+
+impl AxROM {
+    pub fn new(header: &CartridgeHeader) -> AxROM {
+        AxROM {
+            bank: 0,
+            prg_rom_size: header.prg_rom_size,
+            //AMROM and AOROM have conflicts but ANROM, the most common one, doesn't
+            bus_conflicts: bus_conflicts(header, false)
+        }
+    }
+}
+
+impl Mapper for AxROM {
+    fn map_cpu_address(&self, address: u16) -> MappedAddress {
+        if address < 0x8000 || self.prg_rom_size == 0 {
+            return MappedAddress::None;
+        }
+
+        let bank = (self.bank & 0b1111) as usize;
+        MappedAddress::PrgRom((bank * 2 * PRG_BANK_SIZE + (address & 0x7FFF) as usize) % self.prg_rom_size)
+    }
+
+    fn map_ppu_address(&self, address: u16) -> usize {
+        (address & 0x1FFF) as usize
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) -> MappedAddress {
+        if address >= 0x8000 {
+            self.bank = data;
+        }
+        MappedAddress::None
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.bank & 0b10000 == 0 {
+            Mirroring::SingleScreenLower
         } else {
-            address & 0x3FFF
+            Mirroring::SingleScreenUpper
         }
     }
 
-    fn map_ppu_address(&self, address: u16) -> u16 {
-        address
+    fn has_bus_conflicts(&self) -> bool {
+        self.bus_conflicts
+    }
+}
+
+//Refuses images with more ROM than the board can address
+fn check_size(header: &CartridgeHeader, max_prg: usize, max_chr: usize) -> Result<()> {
+    if header.prg_rom_size > max_prg || header.chr_rom_size > max_chr {
+        Err(NesError::OversizedImage { size: header.prg_rom_size.saturating_add(header.chr_rom_size) })
+    } else {
+        Ok(())
+    }
+}
+
+pub fn create_mapper(header: &CartridgeHeader) -> Result<Box<dyn Mapper>> {
+    match header.mapper {
+        0 => {
+            //NROM has no bank switching so it can only see 32KiB of PRG and 8KiB of CHR
+            check_size(header, 2 * PRG_BANK_SIZE, CHR_BANK_SIZE)?;
+            Ok(Box::new(Mapper0::new(header)))
+        },
+        1 => {
+            //SUROM/SXROM top out at 512KiB of PRG, and the 5 bit CHR registers reach 128KiB
+            check_size(header, 32 * PRG_BANK_SIZE, 16 * CHR_BANK_SIZE)?;
+            Ok(Box::new(MMC1::new(header)))
+        },
+        2 => {
+            //The bank register is 8 bits on the biggest boards
+            check_size(header, 256 * PRG_BANK_SIZE, CHR_BANK_SIZE)?;
+            Ok(Box::new(UxROM::new(header)))
+        },
+        3 => {
+            check_size(header, 2 * PRG_BANK_SIZE, 256 * CHR_BANK_SIZE)?;
+            Ok(Box::new(CNROM::new(header)))
+        },
+        4 => {
+            //8 bit registers of 8KiB PRG and 1KiB CHR banks
+            check_size(header, 128 * PRG_BANK_SIZE, 32 * CHR_BANK_SIZE)?;
+            //NES 2.0 submapper 4 is the MMC3A with the older IRQ behaviour
+            let variant = if header.submapper == 4 { IrqVariant::Nec } else { IrqVariant::Sharp };
+            Ok(Box::new(MMC3::new(header, variant)))
+        },
+        7 => {
+            check_size(header, 32 * PRG_BANK_SIZE, CHR_BANK_SIZE)?;
+            Ok(Box::new(AxROM::new(header)))
+        },
+        n => Err(NesError::UnsupportedMapper(n))
     }
 }
@@ -1,20 +1,94 @@
+use crate::Cartridge::Cartridge;
+use crate::CartridgeHeader::Mirroring;
+
+//The 2C03's colours, 3 bits per channel as octal RGB. Same as res/2C03.pal
+const COLOURS: [u16; 64] = [
+    0o333, 0o014, 0o006, 0o326, 0o403, 0o503, 0o510, 0o420, 0o320, 0o120, 0o031, 0o040, 0o022, 0o000, 0o000, 0o000,
+    0o555, 0o036, 0o027, 0o407, 0o507, 0o704, 0o700, 0o630, 0o430, 0o140, 0o040, 0o053, 0o044, 0o000, 0o000, 0o000,
+    0o777, 0o357, 0o447, 0o637, 0o707, 0o737, 0o740, 0o750, 0o660, 0o360, 0o070, 0o276, 0o077, 0o000, 0o000, 0o000,
+    0o777, 0o567, 0o657, 0o757, 0o747, 0o755, 0o764, 0o772, 0o773, 0o572, 0o473, 0o276, 0o467, 0o000, 0o000, 0o000
+];
+
+pub const DOTS_PER_SCANLINE: u16 = 341;
+pub const SCANLINES_PER_FRAME: u16 = 262;
+//The line before the first visible one, where the first two tiles get fetched
+pub const PRE_RENDER_SCANLINE: u16 = 261;
+//VBlank starts on the second dot of this line
+pub const VBLANK_SCANLINE: u16 = 241;
+const VISIBLE_SCANLINES: u16 = 240;
+const SPRITES_PER_SCANLINE: usize = 8;
+
+//PPUSTATUS bits
+const SPRITE_OVERFLOW: u8 = 0b00100000;
+const SPRITE_ZERO_HIT: u8 = 0b01000000;
+const VBLANK: u8 = 0b10000000;
+
+//The I/O latch takes around 600ms to fade
+const IO_LATCH_DECAY_FRAMES: u64 = 36;
+
+//A colour from the palette as RGB24
+pub fn rgb(colour: u8) -> [u8; 3] {
+    let colour = COLOURS[(colour & 0x3F) as usize];
+    let channel = |shift: u16| (((colour >> shift) & 0x07) * 255 / 7) as u8;
+    [channel(6), channel(3), channel(0)]
+}
+
 pub struct PPU {
     PPUCTRL: u8,
     PPUMASK: u8,
     PPUSTATUS: u8,
     OAMADDR: u8,
-    PPUSCROLL: u16,
-    PPUADDR: u16,
 
-    VRAM: Box<[u8; 16384]>,
+    //The 2KiB of nametable RAM in the console, plus another 2KiB for four screen carts
+    VRAM: Box<[u8; 4096]>,
+    palette: [u8; 32],
     SPR_RAM: Box<[u8; 256]>,
 
-    //This is for loading in the PPUADDR in 2 writes
+    //Loopy's scroll registers. v is the VRAM address, which is also where rendering is up to. t is
+    //where v gets reloaded from, and is what $2000/$2005/$2006 write to. Both are laid out as
+    //yyy NN YYYYY XXXXX: fine Y, nametable, coarse Y and coarse X
+    vram_address: u16,
     temp_address: u16,
+    fine_x: u8,
+    //$2005 and $2006 share a latch for which write is next
     first_write: bool,
+    //$2007 reads give what the last read fetched
+    read_buffer: u8,
+    //The capacitance of the data bus to the CPU holds the last value on it for a while. Each bit
+    //remembers the frame it was last driven on to fade out on its own
+    io_latch: u8,
+    io_latch_refreshed: [u64; 8],
+
+    //What the background fetches have got for the next tile
+    next_tile: u8,
+    next_attribute: u8,
+    next_pattern_low: u8,
+    next_pattern_high: u8,
+    //The current tile in the high byte and the next one in the low byte. The attribute shifters
+    //hold a copy of the palette bit for every pixel so they shift along with the pattern
+    pattern_low: u16,
+    pattern_high: u16,
+    attribute_low: u16,
+    attribute_high: u16,
+
+    //Sprite evaluation copies the sprites on the next line here, up to 8 of them
+    secondary_oam: [u8; 32],
+    sprite_count: usize,
+    //If sprite 0 is the first of them, for sprite 0 hits
+    sprite_zero_on_line: bool,
+    //What got fetched for each of them. The patterns are already flipped horizontally
+    sprite_pattern_low: [u8; SPRITES_PER_SCANLINE],
+    sprite_pattern_high: [u8; SPRITES_PER_SCANLINE],
+    sprite_attributes: [u8; SPRITES_PER_SCANLINE],
+    sprite_x: [u8; SPRITES_PER_SCANLINE],
 
     cycle: u16,
     scanline: u16,
+    //With rendering on, every other frame is a dot short
+    odd_frame: bool,
+    //Set by reading PPUSTATUS on the dot before VBlank starts, which stops it starting
+    suppress_vblank: bool,
+    frame_count: u64,
     pub frame_done: bool,
 
     pub frame_buffer: Box<[u8; 61440 * 3]>
@@ -27,119 +101,228 @@ impl PPU {
             PPUMASK: 0,
             PPUSTATUS: 0,
             OAMADDR: 0,
-            PPUSCROLL: 0,
-            PPUADDR: 0,
 
-            VRAM: Box::new([0; 16384]),
+            VRAM: Box::new([0; 4096]),
+            palette: [0; 32],
             SPR_RAM: Box::new([0; 256]),
 
+            vram_address: 0,
             temp_address: 0,
+            fine_x: 0,
             first_write: true,
+            read_buffer: 0,
+            io_latch: 0,
+            io_latch_refreshed: [0; 8],
+
+            next_tile: 0,
+            next_attribute: 0,
+            next_pattern_low: 0,
+            next_pattern_high: 0,
+            pattern_low: 0,
+            pattern_high: 0,
+            attribute_low: 0,
+            attribute_high: 0,
+
+            secondary_oam: [0xFF; 32],
+            sprite_count: 0,
+            sprite_zero_on_line: false,
+            sprite_pattern_low: [0; SPRITES_PER_SCANLINE],
+            sprite_pattern_high: [0; SPRITES_PER_SCANLINE],
+            sprite_attributes: [0; SPRITES_PER_SCANLINE],
+            sprite_x: [0; SPRITES_PER_SCANLINE],
 
             cycle: 0,
             scanline: 0,
+            odd_frame: false,
+            suppress_vblank: false,
+            frame_count: 0,
             frame_done: false,
 
             frame_buffer: Box::new([0; 61440 * 3])
         }
     }
 
-    pub fn read(&mut self, address: u16) -> u8 {
-        0
+    pub fn reset(&mut self) {
+        self.PPUCTRL = 0;
+        self.PPUMASK = 0;
+        self.temp_address = 0;
+        self.fine_x = 0;
+        self.first_write = true;
+        self.read_buffer = 0;
     }
 
-    pub fn write(&mut self, address: u16, data: u8) {
+    //High when in VBlank with NMIs turned on in PPUCTRL
+    pub fn nmi_line(&self) -> bool {
+        self.PPUCTRL & 0b10000000 != 0 && self.PPUSTATUS & VBLANK != 0
+    }
 
-    } 
+    //(scanline, dot)
+    pub fn position(&self) -> (u16, u16) {
+        (self.scanline, self.cycle)
+    }
 
-    pub fn cpu_read(&mut self, address: u16) -> u8 {
-        match address {
-            0x0000 => {
-                //Meant to be write only but no harm
-                self.PPUCTRL
-            },
-            0x0001 => {
-                //So is this
-                self.PPUMASK
+    //What reading a register would return, without the side effects of actually reading it
+    pub fn peek_register(&self, address: u16) -> u8 {
+        match address & 0x0007 {
+            0x0002 => (self.PPUSTATUS & 0b11100000) | (self.io_latch & 0b00011111),
+            0x0004 => self.SPR_RAM[self.OAMADDR as usize],
+            0x0007 => {
+                let address = self.vram_address & 0x3FFF;
+                if address < 0x3F00 {
+                    self.read_buffer
+                } else {
+                    (self.palette[PPU::palette_index(address)] & 0b00111111) | (self.io_latch & 0b11000000)
+                }
             },
+            _ => self.io_latch
+        }
+    }
+
+    //Where a nametable address ends up in VRAM
+    fn nametable_index(address: u16, mirroring: Mirroring) -> usize {
+        let nametable = (address >> 10) & 0x03;
+        let nametable = match mirroring {
+            Mirroring::Horizontal => nametable >> 1,
+            Mirroring::Vertical => nametable & 0x01,
+            Mirroring::FourScreen => nametable,
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1
+        };
+        ((nametable << 10) | (address & 0x03FF)) as usize
+    }
+
+    //The background colours at $3F10/$3F14/$3F18/$3F1C are the same bytes as $3F00/$3F04/$3F08/$3F0C
+    fn palette_index(address: u16) -> usize {
+        let index = address & 0x001F;
+        if index & 0x0013 == 0x0010 {
+            (index & 0x000F) as usize
+        } else {
+            index as usize
+        }
+    }
+
+    //The PPU's own address space. Pattern tables are on the cartridge, nametables are in VRAM
+    //arranged the way the cartridge wires them, and the palette is inside the PPU
+    pub fn read(&mut self, address: u16, cart: &mut Cartridge) -> u8 {
+        let address = address & 0x3FFF;
+        if address < 0x2000 {
+            cart.ppu_read(address)
+        } else if address < 0x3F00 {
+            cart.ppu_address(address);
+            self.VRAM[PPU::nametable_index(address, cart.mirroring())]
+        } else {
+            self.palette[PPU::palette_index(address)]
+        }
+    }
+
+    pub fn write(&mut self, address: u16, data: u8, cart: &mut Cartridge) {
+        let address = address & 0x3FFF;
+        if address < 0x2000 {
+            cart.ppu_write(address, data);
+        } else if address < 0x3F00 {
+            cart.ppu_address(address);
+            self.VRAM[PPU::nametable_index(address, cart.mirroring())] = data;
+        } else {
+            self.palette[PPU::palette_index(address)] = data & 0x3F;
+        }
+    }
+
+    //The registers repeat every 8 bytes from $2000 to $3FFF. Anything the PPU doesn't drive reads back
+    //the I/O latch, which holds whatever was last on the bus between the CPU and PPU
+    pub fn cpu_read(&mut self, address: u16, cart: &mut Cartridge) -> u8 {
+        match address & 0x0007 {
             0x0002 => {
-                //End VBlank
-                self.PPUSTATUS |= 0b10000000;
+                //Reading on the dot before VBlank starts sees it clear and stops it starting at all,
+                //so there's no NMI this frame. A read in the same CPU cycle as it starts sees it set
+                //but clears it before the CPU notices the NMI
+                if self.scanline == VBLANK_SCANLINE && self.cycle == 1 {
+                    self.suppress_vblank = true;
+                }
+                let status = self.PPUSTATUS;
+                //End VBlank, which takes the NMI line back up too
+                self.PPUSTATUS &= !VBLANK;
                 //Clear the latch
                 self.first_write = true;
-                self.PPUSTATUS
-            },
-            0x0003 => {
-                //And this
-                self.OAMADDR
+                //Only the top 3 bits are real
+                self.refresh_io_latch(status, 0b11100000)
             },
             0x0004 => {
-                self.SPR_RAM[self.OAMADDR as usize]
-            },
-            0x0005 => {
-                0
-            },
-            0x0006 => {
-                0
+                self.refresh_io_latch(self.SPR_RAM[self.OAMADDR as usize], 0xFF)
             },
             0x0007 => {
-                //TODO: Something about the first read being invalid
-                self.VRAM[(self.PPUADDR & 0x4000) as usize]
+                let address = self.vram_address & 0x3FFF;
+                let data = if address < 0x3F00 {
+                    //Reads are a step behind
+                    let data = self.read_buffer;
+                    self.read_buffer = self.read(address, cart);
+                    self.refresh_io_latch(data, 0xFF)
+                } else {
+                    //Except for the palette, which comes straight back. The buffer gets the
+                    //nametable underneath it instead. Palette entries are only 6 bits
+                    self.read_buffer = self.read(address & 0x2FFF, cart);
+                    let data = self.read(address, cart);
+                    self.refresh_io_latch(data, 0b00111111)
+                };
+                self.increment_address();
+                data
             },
             _ => {
-                0
+                //The rest are write only
+                self.io_latch
             }
         }
     }
 
-    pub fn cpu_write(&mut self, address: u16, data: u8) {
-        match address {
-            0x2000 => {
+    pub fn cpu_write(&mut self, address: u16, data: u8, cart: &mut Cartridge) {
+        //Writes to any register fill the I/O latch, even $2002 which ignores them otherwise
+        self.refresh_io_latch(data, 0xFF);
+        match address & 0x0007 {
+            0x0000 => {
                 self.PPUCTRL = data;
+                //The nametable select bits go straight into t
+                self.temp_address = (self.temp_address & !0x0C00) | ((data as u16 & 0x03) << 10);
             },
-            0x2001 => {
+            0x0001 => {
                 self.PPUMASK = data;
             },
-            0x2002 => {
-                self.PPUSTATUS = data;
-            },
-            0x2003 => {
+            0x0003 => {
                 //Set the sprite RAM address. This is only an 8 bit pointer
                 self.OAMADDR = data;
             },
-            0x2004 => {
+            0x0004 => {
+                //Bits 2-4 of the attribute bytes don't exist
+                let data = if self.OAMADDR & 0x03 == 0x02 {data & 0b11100011} else {data};
                 self.SPR_RAM[self.OAMADDR as usize] = data;
                 //TODO: check if this actually wraps (probably does)
                 self.OAMADDR = self.OAMADDR.wrapping_add(1);
             },
-            0x2005 => {
-                //Set the ppu scroll. This takes to writes as it is a 16 bit value
+            0x0005 => {
+                //Set the scroll, X then Y. The bottom 3 bits are the fine scroll and the rest pick the tile
+                let data = data as u16;
                 if self.first_write {
-                    self.temp_address = (data as u16) << 8;
-                    self.first_write = false;
+                    self.temp_address = (self.temp_address & !0x001F) | (data >> 3);
+                    self.fine_x = data as u8 & 0x07;
                 } else {
-                    self.temp_address = self.temp_address + data as u16;
-                    self.PPUSCROLL = self.temp_address;
-                    self.first_write = true;
+                    self.temp_address = (self.temp_address & !0x73E0) | ((data & 0x07) << 12) | ((data & 0xF8) << 2);
                 }
+                self.first_write = !self.first_write;
             },
-            0x2006 => {
-                //Set the PPU RAM address. This takes to writes as it is a 16 bit value
+            0x0006 => {
+                //Set the PPU RAM address, high byte first. This goes through t too, so it moves the
+                //scroll. The top bit of the high byte is lost and v only changes on the second write
+                let data = data as u16;
                 if self.first_write {
-                    self.temp_address = (data as u16) << 8;
-                    self.first_write = false;
+                    self.temp_address = (self.temp_address & 0x00FF) | ((data & 0x3F) << 8);
                 } else {
-                    self.temp_address = self.temp_address + data as u16;
-                    self.PPUADDR = self.temp_address;
-                    self.first_write = true;
+                    self.temp_address = (self.temp_address & 0xFF00) | data;
+                    self.vram_address = self.temp_address;
                 }
+                self.first_write = !self.first_write;
             },
-            0x2007 => {
+            0x0007 => {
                 //TODO: Make sure screen is off first
-                self.VRAM[(self.PPUADDR & 0x4000) as usize] = data;
-                //If this bit is set to 0 we're going across so add 1. Else we're going down a line
-                //so add 32
-                self.PPUADDR = self.PPUADDR.wrapping_add(if self.PPUCTRL & 0b00000100 == 0 {1} else {32});
+                self.write(self.vram_address & 0x3FFF, data, cart);
+                self.increment_address();
             },
             _ => {
                 
@@ -147,25 +330,333 @@ impl PPU {
         }        
     }
 
-    //This copies a page from cpu RAM to SPR_RAM
-    ////TODO: optimise this
-    pub fn OAMDMA(&mut self, data: [u8; 256]) {
-        self.SPR_RAM = Box::new(data);
+    //Puts the bits of data the PPU is driving on the bus into the I/O latch and returns what the CPU
+    //would read, the rest coming from the latch
+    fn refresh_io_latch(&mut self, data: u8, driven: u8) -> u8 {
+        self.io_latch = (self.io_latch & !driven) | (data & driven);
+        for bit in 0..8 {
+            if driven & (1 << bit) != 0 {
+                self.io_latch_refreshed[bit] = self.frame_count;
+            }
+        }
+        self.io_latch
+    }
+
+    //Bits that haven't been refreshed in a while fade to 0
+    fn decay_io_latch(&mut self) {
+        for bit in 0..8 {
+            if self.frame_count - self.io_latch_refreshed[bit] >= IO_LATCH_DECAY_FRAMES {
+                self.io_latch &= !(1 << bit);
+            }
+        }
+    }
+
+    //If this bit is set to 0 we're going across so add 1. Else we're going down a line so add 32.
+    //While rendering v is busy being the scroll position, and the PPU bumps both coarse X and Y
+    //instead
+    fn increment_address(&mut self) {
+        if self.rendering_enabled() && (self.scanline < VISIBLE_SCANLINES || self.scanline == PRE_RENDER_SCANLINE) {
+            self.increment_x();
+            self.increment_y();
+        } else {
+            let step = if self.PPUCTRL & 0b00000100 == 0 {1} else {32};
+            self.vram_address = self.vram_address.wrapping_add(step) & 0x7FFF;
+        }
+    }
+
+    fn rendering_enabled(&self) -> bool {
+        self.PPUMASK & 0b00011000 != 0
     }
 
-    pub fn clock(&mut self) -> bool {
-        if self.cycle < 256 && self.scanline < 240 {
-            self.frame_buffer[(self.cycle + self.scanline * 256) as usize] = self.cycle as u8;
-            self.frame_buffer[(self.cycle + self.scanline * 256 + 1) as usize] = self.cycle as u8;
-            self.frame_buffer[(self.cycle + self.scanline * 256 + 2) as usize] = self.cycle as u8;
+    //Next tile across, into the next nametable over after the 32nd
+    fn increment_x(&mut self) {
+        if self.vram_address & 0x001F == 31 {
+            self.vram_address = (self.vram_address & !0x001F) ^ 0x0400;
+        } else {
+            self.vram_address += 1;
+        }
+    }
+
+    //Next row of pixels down. After the 30th row of tiles that's the nametable below. Rows 30 and 31
+    //are the attribute table, which games can scroll into, and those wrap without switching
+    fn increment_y(&mut self) {
+        if self.vram_address & 0x7000 != 0x7000 {
+            self.vram_address += 0x1000;
+            return;
+        }
+        self.vram_address &= !0x7000;
+        let coarse_y = (self.vram_address & 0x03E0) >> 5;
+        let coarse_y = match coarse_y {
+            29 => {
+                self.vram_address ^= 0x0800;
+                0
+            },
+            31 => 0,
+            _ => coarse_y + 1
+        };
+        self.vram_address = (self.vram_address & !0x03E0) | (coarse_y << 5);
+    }
+
+    fn tile_address(&self) -> u16 {
+        0x2000 | (self.vram_address & 0x0FFF)
+    }
+
+    //Each attribute byte covers 4x4 tiles, 2 bits for each 2x2 of them
+    fn fetch_attribute(&mut self, cart: &mut Cartridge) {
+        let v = self.vram_address;
+        let attribute = self.read(0x23C0 | (v & 0x0C00) | ((v >> 4) & 0x38) | ((v >> 2) & 0x07), cart);
+        let shift = ((v >> 4) & 0x04) | (v & 0x02);
+        self.next_attribute = (attribute >> shift) & 0x03;
+    }
+
+    //Plane is 0 for the low bits of each pixel and 8 for the high bits
+    fn pattern_address(&self, plane: u16) -> u16 {
+        let fine_y = (self.vram_address >> 12) & 0x07;
+        let table = if self.PPUCTRL & 0b00010000 != 0 {0x1000} else {0x0000};
+        table | ((self.next_tile as u16) << 4) | plane | fine_y
+    }
+
+    fn load_background_shifters(&mut self) {
+        let expand = |bit: u8| if bit != 0 {0x00FF} else {0x0000};
+        self.pattern_low = (self.pattern_low & 0xFF00) | self.next_pattern_low as u16;
+        self.pattern_high = (self.pattern_high & 0xFF00) | self.next_pattern_high as u16;
+        self.attribute_low = (self.attribute_low & 0xFF00) | expand(self.next_attribute & 0x01);
+        self.attribute_high = (self.attribute_high & 0xFF00) | expand(self.next_attribute & 0x02);
+    }
+
+    //The background half of a dot on a rendering line. Every 8 dots fetch the nametable byte,
+    //attribute byte and two pattern bytes for a tile, 2 dots each, and the shifters move along one
+    //pixel per dot taking the next tile in every time they've emptied a byte
+    fn clock_background(&mut self, cart: &mut Cartridge) {
+        let dot = self.cycle;
+        if (2..=257).contains(&dot) || (322..=337).contains(&dot) {
+            self.pattern_low <<= 1;
+            self.pattern_high <<= 1;
+            self.attribute_low <<= 1;
+            self.attribute_high <<= 1;
+            if dot % 8 == 1 {
+                self.load_background_shifters();
+            }
+        }
+
+        if (1..=256).contains(&dot) || (321..=336).contains(&dot) {
+            match (dot - 1) % 8 {
+                0 => self.next_tile = self.read(self.tile_address(), cart),
+                2 => self.fetch_attribute(cart),
+                4 => self.next_pattern_low = self.read(self.pattern_address(0), cart),
+                6 => self.next_pattern_high = self.read(self.pattern_address(8), cart),
+                7 => self.increment_x(),
+                _ => {}
+            }
+        } else if dot == 337 || dot == 339 {
+            //Two more nametable fetches that nothing uses
+            self.read(self.tile_address(), cart);
+        }
+
+        if dot == 256 {
+            self.increment_y();
+        } else if dot == 257 {
+            //Back to the left edge for the next line
+            self.vram_address = (self.vram_address & !0x041F) | (self.temp_address & 0x041F);
+        } else if self.scanline == PRE_RENDER_SCANLINE && (280..=304).contains(&dot) {
+            //And back to the top for the next frame. Writes to t in VBlank take effect here
+            self.vram_address = (self.vram_address & !0x7BE0) | (self.temp_address & 0x7BE0);
+        }
+    }
+
+    fn sprite_height(&self) -> u16 {
+        if self.PPUCTRL & 0b00100000 != 0 {16} else {8}
+    }
+
+    //Finds the sprites on the next line. The real thing does this over dots 65-256, here it's all
+    //done at once at the end
+    fn evaluate_sprites(&mut self) {
+        self.secondary_oam = [0xFF; 32];
+        self.sprite_count = 0;
+        self.sprite_zero_on_line = false;
+        //Nothing is drawn on the line after the pre-render line, so its sprites never get found
+        if self.scanline == PRE_RENDER_SCANLINE {
+            return;
+        }
+
+        let scanline = self.scanline;
+        let height = self.sprite_height();
+        let in_range = |y: u8| scanline.wrapping_sub(y as u16) < height;
+        let mut n = 0;
+        while n < 64 && self.sprite_count < SPRITES_PER_SCANLINE {
+            if in_range(self.SPR_RAM[n * 4]) {
+                let slot = self.sprite_count * 4;
+                self.secondary_oam[slot..slot + 4].copy_from_slice(&self.SPR_RAM[n * 4..n * 4 + 4]);
+                self.sprite_zero_on_line |= n == 0;
+                self.sprite_count += 1;
+            }
+            n += 1;
+        }
+
+        //Once it has 8 the PPU keeps looking for a 9th to set the overflow flag, but it goes wrong.
+        //It moves on to the next byte of each sprite as well as the next sprite, so it ends up
+        //checking tile numbers, attributes and X positions as if they were Y
+        let mut m = 0;
+        while n < 64 {
+            if in_range(self.SPR_RAM[n * 4 + m]) {
+                self.PPUSTATUS |= SPRITE_OVERFLOW;
+                break;
+            }
+            n += 1;
+            m = (m + 1) & 0x03;
+        }
+    }
+
+    //Plane is 0 for the low bits of each pixel and 8 for the high bits. Empty slots fetch tile $FF
+    fn sprite_pattern_address(&self, slot: usize, plane: u16) -> u16 {
+        let y = self.secondary_oam[slot * 4] as u16;
+        let tile = self.secondary_oam[slot * 4 + 1];
+        let attributes = self.secondary_oam[slot * 4 + 2];
+        let height = self.sprite_height();
+
+        let mut row = self.scanline.wrapping_sub(y) & (height - 1);
+        if attributes & 0b10000000 != 0 {
+            row = height - 1 - row;
+        }
+        //8x16 sprites take the pattern table from the bottom bit of the tile number and are made
+        //of that tile and the one after
+        let (table, tile) = if height == 16 {
+            ((tile as u16 & 0x01) << 12, (tile & 0xFE) as u16 + (row >> 3))
+        } else if self.PPUCTRL & 0b00001000 != 0 {
+            (0x1000, tile as u16)
+        } else {
+            (0x0000, tile as u16)
+        };
+        table | (tile << 4) | plane | (row & 0x07)
+    }
+
+    //The sprite half of a dot on a rendering line. Dots 257-320 fetch the patterns for the sprites
+    //found for the next line, 8 dots per sprite with 2 nametable fetches that nothing uses first
+    fn clock_sprites(&mut self, cart: &mut Cartridge) {
+        let dot = self.cycle;
+        if dot == 257 {
+            self.evaluate_sprites();
+        }
+        if !(257..=320).contains(&dot) {
+            return;
+        }
+
+        let slot = ((dot - 257) / 8) as usize;
+        match (dot - 257) % 8 {
+            0 | 2 => {
+                self.read(self.tile_address(), cart);
+            },
+            4 => {
+                self.sprite_pattern_low[slot] = self.read(self.sprite_pattern_address(slot, 0), cart);
+            },
+            6 => {
+                let high = self.read(self.sprite_pattern_address(slot, 8), cart);
+                let attributes = self.secondary_oam[slot * 4 + 2];
+                let (low, high) = if slot >= self.sprite_count {
+                    (0, 0)
+                } else if attributes & 0b01000000 != 0 {
+                    (self.sprite_pattern_low[slot].reverse_bits(), high.reverse_bits())
+                } else {
+                    (self.sprite_pattern_low[slot], high)
+                };
+                self.sprite_pattern_low[slot] = low;
+                self.sprite_pattern_high[slot] = high;
+                self.sprite_attributes[slot] = attributes;
+                self.sprite_x[slot] = self.secondary_oam[slot * 4 + 3];
+            },
+            _ => {}
+        }
+    }
+
+    //The first sprite with a pixel at x, as (slot, pixel)
+    fn sprite_pixel(&self, x: u16) -> Option<(usize, u16)> {
+        (0..self.sprite_count).find_map(|slot| {
+            let column = x.wrapping_sub(self.sprite_x[slot] as u16);
+            if column >= 8 {
+                return None;
+            }
+            let bit = 7 - column;
+            let pixel = ((self.sprite_pattern_high[slot] as u16 >> bit) & 0x01) << 1
+                | ((self.sprite_pattern_low[slot] as u16 >> bit) & 0x01);
+            if pixel == 0 {None} else {Some((slot, pixel))}
+        })
+    }
+
+    //Puts the pixel for this dot in the frame buffer
+    fn output_pixel(&mut self) {
+        let x = self.cycle - 1;
+        let show_background = self.PPUMASK & 0b00001000 != 0 && (x >= 8 || self.PPUMASK & 0b00000010 != 0);
+        let show_sprites = self.PPUMASK & 0b00010000 != 0 && (x >= 8 || self.PPUMASK & 0b00000100 != 0);
+
+        //Colour 0 of every palette is the backdrop
+        let (background, background_entry) = if show_background {
+            let bit = 15 - self.fine_x as u16;
+            let pixel = ((self.pattern_high >> bit) & 0x01) << 1 | ((self.pattern_low >> bit) & 0x01);
+            let palette = ((self.attribute_high >> bit) & 0x01) << 1 | ((self.attribute_low >> bit) & 0x01);
+            (pixel, if pixel == 0 {0} else {(palette << 2 | pixel) as usize})
+        } else {
+            (0, 0)
+        };
+
+        let sprite = if show_sprites {self.sprite_pixel(x)} else {None};
+        let entry = match sprite {
+            Some((slot, pixel)) => {
+                //Sprite 0 hits whenever it's drawn over the background, whatever its priority.
+                //Except at the very right edge
+                if slot == 0 && self.sprite_zero_on_line && background != 0 && x != 255 {
+                    self.PPUSTATUS |= SPRITE_ZERO_HIT;
+                }
+                let attributes = self.sprite_attributes[slot];
+                if background == 0 || attributes & 0b00100000 == 0 {
+                    0x10 | ((attributes as usize & 0x03) << 2) | pixel as usize
+                } else {
+                    background_entry
+                }
+            },
+            None => background_entry
+        };
+
+        let mut colour = self.palette[entry];
+        if self.PPUMASK & 0b00000001 != 0 {
+            //Greyscale
+            colour &= 0x30;
+        }
+        let index = (self.scanline as usize * 256 + x as usize) * 3;
+        self.frame_buffer[index..index + 3].copy_from_slice(&rgb(colour));
+    }
+
+    //Returns true on the dot that finishes a frame
+    pub fn clock(&mut self, cart: &mut Cartridge) -> bool {
+        self.frame_done = false;
+        let visible = self.scanline < VISIBLE_SCANLINES;
+        if self.scanline == VBLANK_SCANLINE && self.cycle == 1 {
+            if !self.suppress_vblank {
+                self.PPUSTATUS |= VBLANK;
+            }
+            self.suppress_vblank = false;
+        } else if self.scanline == PRE_RENDER_SCANLINE && self.cycle == 1 {
+            self.PPUSTATUS &= !(VBLANK | SPRITE_ZERO_HIT | SPRITE_OVERFLOW);
+        }
+        if self.rendering_enabled() && (visible || self.scanline == PRE_RENDER_SCANLINE) {
+            self.clock_background(cart);
+            self.clock_sprites(cart);
+        }
+        if visible && (1..=256).contains(&self.cycle) {
+            self.output_pixel();
         }
 
         self.cycle += 1;
-        if self.cycle >= 341 {
+        //Odd frames skip the last dot of the pre-render line, if rendering's on
+        let skip_dot = self.scanline == PRE_RENDER_SCANLINE && self.cycle == DOTS_PER_SCANLINE - 1
+            && self.odd_frame && self.rendering_enabled();
+        if self.cycle >= DOTS_PER_SCANLINE || skip_dot {
             self.cycle = 0;
             self.scanline += 1;
-            if self.scanline >= 261 {
+            if self.scanline >= SCANLINES_PER_FRAME {
                 self.scanline = 0;
+                self.odd_frame = !self.odd_frame;
+                self.frame_count += 1;
+                self.decay_io_latch();
                 self.frame_done = true;
             }
         }
@@ -1,5 +1,3 @@
-use std::convert::TryInto;
-
 pub struct RAM {
     data: Box<[u8; 2048]>
 }
@@ -11,15 +9,12 @@ impl RAM {
         }
     }
 
-    pub fn read(&mut self, address: u16) -> u8 {
-        self.data[(address % 2047) as usize]
+    //The 2KiB is mirrored 4 times over $0000-$1FFF
+    pub fn read(&self, address: u16) -> u8 {
+        self.data[(address & 0x07FF) as usize]
     }
 
     pub fn write(&mut self, address: u16, data: u8) {
-        self.data[(address % 2047) as usize] = data;
-    }
-
-    pub fn get_page(&mut self, page: u8) -> [u8; 256] {
-        self.data[((page as u16) << 8) as usize.. (((page as u16) << 8) + 256) as usize].try_into().expect("Error reading page")
+        self.data[(address & 0x07FF) as usize] = data;
     }
 }
@@ -0,0 +1,101 @@
+//The module and register names follow the hardware docs rather than rust naming
+#![allow(non_snake_case)]
+#![allow(clippy::upper_case_acronyms)]
+#![allow(clippy::new_without_default)]
+
+#[macro_use]
+extern crate bitflags;
+
+#[path = "CPU6502.rs"] pub mod CPU6502;
+#[path = "Bus.rs"] pub mod Bus;
+#[path = "CPUBus.rs"] pub mod CPUBus;
+#[path = "PPU.rs"] pub mod PPU;
+#[path = "RAM.rs"] pub mod RAM;
+#[path = "Cartridge.rs"] pub mod Cartridge;
+#[path = "CartridgeHeader.rs"] pub mod CartridgeHeader;
+#[path = "Mappers/Mapper.rs"] pub mod Mapper;
+#[path = "Mappers/MMC1.rs"] pub mod MMC1;
+#[path = "Mappers/MMC3.rs"] pub mod MMC3;
+#[path = "Error.rs"] pub mod Error;
+#[path = "Debugger.rs"] pub mod Debugger;
+
+pub use crate::Error::{NesError, Result};
+
+pub const SCREEN_WIDTH: usize = 256;
+pub const SCREEN_HEIGHT: usize = 240;
+
+//The whole console. The CPU owns the bus, which in turn owns the PPU, RAM and cartridge
+pub struct Nes {
+    cpu: CPU6502::CPU6502
+}
+
+impl Nes {
+    //A console with nothing plugged in
+    pub fn new() -> Nes {
+        Nes::with_cartridge(Cartridge::Cartridge::new())
+    }
+
+    pub fn with_cartridge(cart: Cartridge::Cartridge) -> Nes {
+        Nes {
+            cpu: CPU6502::CPU6502::new(CPUBus::CPUBus::new(cart))
+        }
+    }
+
+    pub fn from_rom_file(path: &str) -> Result<Nes> {
+        Ok(Nes::with_cartridge(Cartridge::Cartridge::from_file(path)?))
+    }
+
+    pub fn from_rom_bytes(bytes: &[u8]) -> Result<Nes> {
+        Ok(Nes::with_cartridge(Cartridge::Cartridge::from_bytes(bytes)?))
+    }
+
+    //Swapping cartridges means turning the console off and on again
+    pub fn load_rom(&mut self, path: &str) -> Result<()> {
+        let cart = Cartridge::Cartridge::from_file(path)?;
+        self.cpu.bus_mut().insert_cartridge(cart);
+        self.cpu.power_on();
+        Ok(())
+    }
+
+    pub fn load_rom_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        let cart = Cartridge::Cartridge::from_bytes(bytes)?;
+        self.cpu.bus_mut().insert_cartridge(cart);
+        self.cpu.power_on();
+        Ok(())
+    }
+
+    //The reset button on the front of the console
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    //A KIL opcode stops the CPU until the console is reset. Stepping a halted console returns
+    //NesError::CpuJam
+    pub fn is_halted(&self) -> bool {
+        self.cpu.is_halted()
+    }
+
+    //Runs one whole instruction, or interrupt sequence if one was due.
+    //Returns if a frame was finished along the way
+    pub fn step_instruction(&mut self) -> Result<bool> {
+        self.cpu.step()
+    }
+
+    pub fn run_frame(&mut self) -> Result<()> {
+        while !self.cpu.step()? {}
+        Ok(())
+    }
+
+    //RGB24, SCREEN_WIDTH * SCREEN_HEIGHT pixels
+    pub fn frame_buffer(&self) -> &[u8] {
+        self.cpu.bus().frame_buffer()
+    }
+
+    pub fn cpu(&self) -> &CPU6502::CPU6502 {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut CPU6502::CPU6502 {
+        &mut self.cpu
+    }
+}
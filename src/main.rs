@@ -1,5 +1,3 @@
-#[macro_use]
-extern crate bitflags;
 extern crate sdl2;
 
 use sdl2::pixels::PixelFormatEnum;
@@ -8,16 +6,79 @@ use sdl2::keyboard::Keycode;
 
 use std::time::{Duration, Instant};
 
+use nes_emulator::{Nes, SCREEN_WIDTH, SCREEN_HEIGHT};
 
-#[path = "CPU6502.rs"] mod CPU6502;
+const USAGE: &str = "usage: nes-emulator <rom.nes> [--scale N] [--paused]";
+
+struct Options {
+    rom_path: String,
+    scale: u32,
+    paused: bool
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut rom_path = None;
+    let mut scale = 3;
+    let mut paused = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scale" => {
+                let value = args.next().ok_or("--scale needs a value")?;
+                scale = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid scale: {}", value))
+                };
+            },
+            "--paused" => {
+                paused = true;
+            },
+            _ if arg.starts_with("--") => {
+                return Err(format!("unknown option: {}", arg));
+            },
+            _ if rom_path.is_none() => {
+                rom_path = Some(arg);
+            },
+            _ => {
+                return Err(format!("unexpected argument: {}", arg));
+            }
+        }
+    }
+
+    Ok(Options {
+        rom_path: rom_path.ok_or("no ROM given")?,
+        scale,
+        paused
+    })
+}
+
+//The CPU jammed. Leave the last frame up and say why
+fn halted(window: &mut sdl2::video::Window, error: &nes_emulator::NesError) -> Result<(), String> {
+    eprintln!("{}", error);
+    window.set_title(&format!("nes-emulator - {}", error)).map_err(|e| e.to_string())
+}
 
 fn main() -> Result<(), String> {
-    let mut cpu = CPU6502::CPU6502::new();
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let mut nes = match Nes::from_rom_file(&options.rom_path) {
+        Ok(nes) => nes,
+        Err(e) => {
+            eprintln!("couldn't load {}: {}", options.rom_path, e);
+            std::process::exit(1);
+        }
+    };
 
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
 
-    let window = video_subsystem.window("rust-sdl2 demo: Video", 800, 600)
+    let window = video_subsystem.window("nes-emulator", SCREEN_WIDTH as u32 * options.scale, SCREEN_HEIGHT as u32 * options.scale)
         .position_centered()
         .opengl()
         .build()
@@ -26,15 +87,15 @@ fn main() -> Result<(), String> {
     let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
     let texture_creator = canvas.texture_creator();
 
-    let mut texture = texture_creator.create_texture_streaming(PixelFormatEnum::RGB24, 256, 240)
+    let mut texture = texture_creator.create_texture_streaming(PixelFormatEnum::RGB24, SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32)
         .map_err(|e| e.to_string())?;
 
     let mut event_pump = sdl_context.event_pump()?;
 
-    let mut step_mode = true;
-    let mut time_left: i64 = 0;
-    let mut start_time = Instant::now();
-    let mut new_time = Instant::now();
+    let frame_time = Duration::from_nanos(1_000_000_000 / 60);
+    let mut step_mode = options.paused;
+    let mut redraw = true;
+    let mut next_frame = Instant::now();
 
     'running: loop {
         for event in event_pump.poll_iter() {
@@ -43,42 +104,42 @@ fn main() -> Result<(), String> {
                 | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
                     break 'running
                 },
-                Event::KeyDown { keycode: Some(Keycode::N), ..} => {
-                    if step_mode {
-                        cpu.clock();
-                        while cpu.cycles_to_wait != 0 {
-                            cpu.clock();
-                        }
+                Event::KeyDown { keycode: Some(Keycode::N), ..} if step_mode && !nes.is_halted() => {
+                    match nes.step_instruction() {
+                        Ok(frame_done) => redraw |= frame_done,
+                        Err(e) => halted(canvas.window_mut(), &e)?
                     }
                 },
                 Event::KeyDown { keycode: Some(Keycode::Space), .. } => {
                     step_mode = !step_mode;
+                    next_frame = Instant::now();
                 },
                 _ => {}
             }
         }
 
         if !step_mode {
-            new_time = Instant::now();
-            let time_taken = new_time.duration_since(start_time);
-
-            if time_left >= 0 {
-                time_left -= time_taken.subsec_nanos() as i64;
-            } else {
-                time_left = Duration::from_nanos(((1.0 / 60.0) * 1_000_000.0) as u64).subsec_nanos() as i64 - time_taken.subsec_nanos() as i64;
-                
-                let frame_done: bool = cpu.clock();
-                if frame_done {
-                    canvas.clear();
-                    texture.update(None, cpu.get_frame_buffer().as_ref(), 256);
-                    canvas.copy(&texture, None, None)?;
-                    canvas.present();
+            let now = Instant::now();
+            if now >= next_frame {
+                if let Err(e) = nes.run_frame() {
+                    halted(canvas.window_mut(), &e)?;
+                    step_mode = true;
                 }
+                redraw = true;
+                next_frame += frame_time;
+            } else {
+                std::thread::sleep(next_frame - now);
             }
+        }
 
-            start_time = new_time;
+        if redraw {
+            texture.update(None, nes.frame_buffer(), SCREEN_WIDTH * 3).map_err(|e| e.to_string())?;
+            canvas.clear();
+            canvas.copy(&texture, None, None)?;
+            canvas.present();
+            redraw = false;
         }
     }
 
-    Ok(()) 
+    Ok(())
 }
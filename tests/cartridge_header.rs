@@ -0,0 +1,195 @@
+//Parses hand built headers. Only the 16 header bytes are looked at, so there's no ROM after them
+
+use nes_emulator::CartridgeHeader::{CartridgeHeader, ConsoleType, HeaderFormat, Mirroring, TimingMode};
+use nes_emulator::CartridgeHeader::{PRG_BANK_SIZE, CHR_BANK_SIZE, PRG_RAM_BANK_SIZE};
+use nes_emulator::Cartridge::Cartridge;
+
+//An iNES header with bytes 8-15 left blank
+fn ines(prg_banks: u8, chr_banks: u8, flags6: u8, flags7: u8) -> [u8; 16] {
+    [b'N', b'E', b'S', 0x1A, prg_banks, chr_banks, flags6, flags7, 0, 0, 0, 0, 0, 0, 0, 0]
+}
+
+//A NES 2.0 header. Bytes 8-15 are passed in as they are
+fn nes2(prg_banks: u8, chr_banks: u8, flags6: u8, flags7: u8, rest: [u8; 8]) -> [u8; 16] {
+    let mut bytes = ines(prg_banks, chr_banks, flags6, flags7 | 0b1000);
+    bytes[8..].copy_from_slice(&rest);
+    bytes
+}
+
+fn parse(bytes: &[u8]) -> CartridgeHeader {
+    CartridgeHeader::parse(bytes).unwrap()
+}
+
+#[test]
+fn ines_sizes_are_in_16k_prg_and_8k_chr_banks() {
+    let header = parse(&ines(2, 1, 0, 0));
+    assert_eq!(header.format, HeaderFormat::INes);
+    assert_eq!((header.prg_rom_size, header.chr_rom_size), (2 * PRG_BANK_SIZE, CHR_BANK_SIZE));
+    assert_eq!((header.chr_ram_size, header.has_chr_ram()), (0, false));
+
+    //No CHR ROM means 8KiB of CHR RAM
+    let header = parse(&ines(1, 0, 0, 0));
+    assert_eq!((header.chr_rom_size, header.chr_ram_size, header.has_chr_ram()), (0, CHR_BANK_SIZE, true));
+}
+
+#[test]
+fn ines_flags_6_give_the_mirroring_battery_and_trainer() {
+    //(flags 6, mirroring, battery, trainer)
+    let cases = [
+        (0b0000, Mirroring::Horizontal, false, false),
+        (0b0001, Mirroring::Vertical, false, false),
+        (0b0010, Mirroring::Horizontal, true, false),
+        (0b0100, Mirroring::Horizontal, false, true),
+        //Four screen wins over the mirroring bit
+        (0b1000, Mirroring::FourScreen, false, false),
+        (0b1001, Mirroring::FourScreen, false, false)
+    ];
+    for &(flags6, mirroring, battery, trainer) in cases.iter() {
+        let header = parse(&ines(1, 1, flags6, 0));
+        assert_eq!((header.mirroring, header.battery, header.trainer), (mirroring, battery, trainer), "flags 6 {:08b}", flags6);
+    }
+}
+
+#[test]
+fn ines_prg_ram_is_8k_unless_it_says_otherwise_and_battery_backed_if_there_is_a_battery() {
+    //(byte 8, flags 6, volatile, battery backed)
+    let cases = [
+        (0, 0b00, PRG_RAM_BANK_SIZE, 0),
+        (2, 0b00, 2 * PRG_RAM_BANK_SIZE, 0),
+        (0, 0b10, 0, PRG_RAM_BANK_SIZE),
+        (4, 0b10, 0, 4 * PRG_RAM_BANK_SIZE)
+    ];
+    for &(size, flags6, volatile, battery) in cases.iter() {
+        let mut bytes = ines(1, 1, flags6, 0);
+        bytes[8] = size;
+        let header = parse(&bytes);
+        assert_eq!((header.prg_ram_size, header.prg_nvram_size), (volatile, battery), "byte 8 {} flags 6 {:08b}", size, flags6);
+    }
+}
+
+#[test]
+fn nrom_gets_prg_ram_from_an_ines_header_but_not_a_nes2_one_that_asks_for_none() {
+    for &(bytes, size) in [(ines(1, 1, 0, 0), PRG_RAM_BANK_SIZE), (nes2(1, 1, 0, 0, [0; 8]), 0)].iter() {
+        let mut rom = bytes.to_vec();
+        rom.extend(vec![0; PRG_BANK_SIZE + CHR_BANK_SIZE]);
+        let mut cart = Cartridge::from_bytes(&rom).unwrap();
+        assert_eq!(cart.prg_ram().len(), size);
+
+        cart.write(0x6000, 0x55);
+        cart.write(0x7FFF, 0xAA);
+        let stored = if size == 0 { (None, None) } else { (Some(0x55), Some(0xAA)) };
+        assert_eq!((cart.peek(0x6000), cart.peek(0x7FFF)), stored, "{:?}", cart.header().format);
+    }
+}
+
+#[test]
+fn ines_mapper_number_is_split_across_flags_6_and_7() {
+    //(flags 6, flags 7, mapper)
+    let cases = [
+        (0x00, 0x00, 0),
+        (0x10, 0x00, 1),
+        (0x40, 0x00, 4),
+        (0x00, 0x40, 64),
+        (0x71, 0x40, 71),
+        (0xF0, 0xF0, 255)
+    ];
+    for &(flags6, flags7, mapper) in cases.iter() {
+        let header = parse(&ines(1, 1, flags6, flags7));
+        assert_eq!((header.mapper, header.submapper), (mapper, 0), "flags {:02X} {:02X}", flags6, flags7);
+    }
+}
+
+#[test]
+fn ines_console_type_is_in_flags_7() {
+    let cases = [(0b00, ConsoleType::Nes), (0b01, ConsoleType::VsSystem), (0b10, ConsoleType::Playchoice10)];
+    for &(flags7, console) in cases.iter() {
+        assert_eq!(parse(&ines(1, 1, 0, flags7)).console, console);
+    }
+}
+
+#[test]
+fn junk_in_the_end_of_the_header_drops_the_high_mapper_nibble() {
+    //DiskDude! starts at byte 7, so flags 7 is a 'D'
+    let mut bytes = ines(1, 1, 0x41, 0);
+    bytes[7..16].copy_from_slice(b"DiskDude!");
+    let header = parse(&bytes);
+    assert_eq!(header.format, HeaderFormat::INes);
+    assert_eq!((header.mapper, header.mirroring), (4, Mirroring::Vertical));
+
+    //Anything in bytes 12-15 counts
+    let mut bytes = ines(1, 1, 0x10, 0x20);
+    bytes[15] = 1;
+    assert_eq!(parse(&bytes).mapper, 1);
+}
+
+#[test]
+fn nes2_is_marked_by_bits_2_and_3_of_flags_7_being_10() {
+    //(flags 7, format)
+    let cases = [(0b0000, HeaderFormat::INes), (0b0100, HeaderFormat::INes), (0b1000, HeaderFormat::Nes2), (0b1100, HeaderFormat::INes)];
+    for &(flags7, format) in cases.iter() {
+        assert_eq!(parse(&ines(1, 1, 0, flags7)).format, format, "flags 7 {:08b}", flags7);
+    }
+}
+
+#[test]
+fn nes2_mapper_has_a_third_nibble_and_a_submapper() {
+    //(flags 6, flags 7, byte 8, mapper, submapper)
+    let cases = [
+        (0x40, 0x00, 0x40, 4, 4),
+        (0x10, 0x20, 0x00, 0x21, 0),
+        (0x40, 0x50, 0x31, 0x154, 3),
+        (0xF0, 0xF0, 0xFF, 0xFFF, 15)
+    ];
+    for &(flags6, flags7, byte8, mapper, submapper) in cases.iter() {
+        let header = parse(&nes2(1, 1, flags6, flags7, [byte8, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!((header.mapper, header.submapper), (mapper, submapper), "{:02X} {:02X} {:02X}", flags6, flags7, byte8);
+    }
+}
+
+#[test]
+fn nes2_ram_sizes_are_64_shifted_left() {
+    //(shift, size)
+    let cases = [(0, 0), (1, 128), (6, 4096), (7, 8192), (9, 32768), (14, 1024 * 1024)];
+    for &(shift, size) in cases.iter() {
+        //Volatile in the low nibble and battery backed in the high one, PRG then CHR
+        let header = parse(&nes2(1, 0, 0, 0, [0, 0, shift, shift << 4, 0, 0, 0, 0]));
+        assert_eq!((header.prg_ram_size, header.prg_nvram_size), (size, 0), "shift {}", shift);
+        assert_eq!((header.chr_ram_size, header.chr_nvram_size), (0, size), "shift {}", shift);
+
+        let header = parse(&nes2(1, 0, 0, 0, [0, 0, shift << 4, shift, 0, 0, 0, 0]));
+        assert_eq!((header.prg_ram_size, header.prg_nvram_size), (0, size), "shift {}", shift);
+        assert_eq!((header.chr_ram_size, header.chr_nvram_size), (size, 0), "shift {}", shift);
+    }
+}
+
+#[test]
+fn nes2_rom_sizes_have_a_high_nibble_in_byte_9() {
+    let header = parse(&nes2(0x02, 0x03, 0, 0, [0, 0x21, 0, 0, 0, 0, 0, 0]));
+    assert_eq!(header.prg_rom_size, 0x102 * PRG_BANK_SIZE);
+    assert_eq!(header.chr_rom_size, 0x203 * CHR_BANK_SIZE);
+}
+
+#[test]
+fn nes2_rom_sizes_can_be_an_exponent_and_multiplier() {
+    //A high nibble of $F makes the low byte EEEEEEMM, for 2^E * (MM * 2 + 1) bytes
+    //(E, MM, size)
+    let cases = [(0, 0, 1), (7, 1, 128 * 3), (14, 0, 16384), (20, 3, 1024 * 1024 * 7)];
+    for &(exponent, multiplier, size) in cases.iter() {
+        let lsb = exponent << 2 | multiplier;
+        let header = parse(&nes2(lsb, lsb, 0, 0, [0, 0xFF, 0, 0, 0, 0, 0, 0]));
+        assert_eq!((header.prg_rom_size, header.chr_rom_size), (size, size), "{:08b}", lsb);
+    }
+    //Too big for anything, but it shouldn't overflow
+    let header = parse(&nes2(0xFF, 0xFF, 0, 0, [0, 0xFF, 0, 0, 0, 0, 0, 0]));
+    assert_eq!(header.prg_rom_size, usize::MAX);
+}
+
+#[test]
+fn nes2_timing_and_extended_console_types() {
+    let cases = [(0, TimingMode::Ntsc), (1, TimingMode::Pal), (2, TimingMode::MultiRegion), (3, TimingMode::Dendy)];
+    for &(byte12, timing) in cases.iter() {
+        assert_eq!(parse(&nes2(1, 1, 0, 0, [0, 0, 0, 0, byte12, 0, 0, 0])).timing, timing);
+    }
+    let header = parse(&nes2(1, 1, 0, 0b11, [0, 0, 0, 0, 0, 0x03, 0, 0]));
+    assert_eq!(header.console, ConsoleType::Extended(3));
+}
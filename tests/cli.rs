@@ -0,0 +1,56 @@
+//Runs the binary with bad command lines. These all stop before SDL starts up, so they don't need a display
+
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_nes-emulator")).args(args).output().unwrap()
+}
+
+//Fails with exit code and the first line of stderr starting with message
+fn assert_fails(args: &[&str], code: i32, message: &str) {
+    let output = run(args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(code), "{:?}: {}", args, stderr);
+    assert!(stderr.starts_with(message), "{:?}: {}", args, stderr);
+    //Usage only comes with bad arguments
+    assert_eq!(stderr.contains("usage: nes-emulator <rom.nes> [--scale N] [--paused]"), code == 2, "{:?}: {}", args, stderr);
+}
+
+#[test]
+fn a_rom_is_required() {
+    assert_fails(&[], 2, "no ROM given");
+    assert_fails(&["--paused"], 2, "no ROM given");
+    assert_fails(&["--scale", "2"], 2, "no ROM given");
+}
+
+#[test]
+fn scale_needs_a_positive_number() {
+    assert_fails(&["game.nes", "--scale"], 2, "--scale needs a value");
+    assert_fails(&["game.nes", "--scale", "0"], 2, "invalid scale: 0");
+    assert_fails(&["game.nes", "--scale", "-1"], 2, "invalid scale: -1");
+    assert_fails(&["game.nes", "--scale", "big"], 2, "invalid scale: big");
+}
+
+#[test]
+fn unknown_and_extra_arguments_are_refused() {
+    assert_fails(&["game.nes", "--fullscreen"], 2, "unknown option: --fullscreen");
+    assert_fails(&["game.nes", "other.nes"], 2, "unexpected argument: other.nes");
+}
+
+#[test]
+fn good_arguments_get_as_far_as_loading_the_rom() {
+    //Options go before or after the path
+    assert_fails(&["res/no_such_rom.nes"], 1, "couldn't load res/no_such_rom.nes: ");
+    assert_fails(&["--scale", "4", "--paused", "res/no_such_rom.nes"], 1, "couldn't load res/no_such_rom.nes: ");
+    assert_fails(&["res/no_such_rom.nes", "--paused", "--scale", "1"], 1, "couldn't load res/no_such_rom.nes: ");
+}
+
+#[test]
+fn bad_images_are_reported() {
+    let path = std::env::temp_dir().join(format!("nes_emulator_cli_{}.nes", std::process::id()));
+    std::fs::write(&path, b"not a rom").unwrap();
+    let output = run(&[path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).trim_end().ends_with(": not an iNES image"));
+}
@@ -0,0 +1,151 @@
+//Fixtures shared between the integration tests. Each test crate only uses some of them
+#![allow(dead_code)]
+
+use nes_emulator::Nes;
+use nes_emulator::Bus::Bus;
+use nes_emulator::CPU6502::{Registers, StatusFlags};
+
+//The CPU tests poke programs into RAM here and run them one instruction at a time
+pub const ORIGIN: u16 = 0x0600;
+pub const IRQ_HANDLER: u16 = 0xA000;
+
+pub const CARRY: u8 = StatusFlags::CARRY.bits();
+pub const ZERO: u8 = StatusFlags::ZERO.bits();
+pub const IRQ: u8 = StatusFlags::IRQ.bits();
+pub const DECIMAL: u8 = StatusFlags::DECIMAL.bits();
+pub const BRK: u8 = StatusFlags::BRK.bits();
+pub const UNUSED: u8 = StatusFlags::UNUSED.bits();
+pub const OVERFLOW: u8 = StatusFlags::OVERFLOW.bits();
+pub const NEGATIVE: u8 = StatusFlags::NEGATIVE.bits();
+
+//An NROM image that's just there for the vectors
+pub fn nrom() -> Vec<u8> {
+    let mut prg = vec![0xEA; 16384];
+    prg[0x3FFC] = 0x00;
+    prg[0x3FFD] = 0x80;
+    prg[0x3FFE] = IRQ_HANDLER as u8;
+    prg[0x3FFF] = (IRQ_HANDLER >> 8) as u8;
+
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(prg);
+    rom.extend(vec![0; 8192]);
+    rom
+}
+
+pub fn registers() -> Registers {
+    Registers { program_counter: ORIGIN, stack_pointer: 0xFD, accumulator: 0, reg_x: 0, reg_y: 0, status: UNUSED | IRQ }
+}
+
+//A NES running rom, with the program at the PC and anything else in memory poked in
+pub fn setup(rom: &[u8], registers: Registers, memory: &[(u16, u8)], program: &[u8]) -> Nes {
+    let mut nes = Nes::from_rom_bytes(rom).unwrap();
+    //Get through the reset sequence first
+    nes.step_instruction().unwrap();
+
+    for (i, byte) in program.iter().enumerate() {
+        nes.cpu_mut().bus_mut().write(registers.program_counter.wrapping_add(i as u16), *byte);
+    }
+    for (address, data) in memory {
+        nes.cpu_mut().bus_mut().write(*address, *data);
+    }
+    nes.cpu_mut().set_registers(registers);
+    nes
+}
+
+//Runs one instruction and returns how many cycles it took
+pub fn step(nes: &mut Nes) -> u64 {
+    let before = nes.cpu().total_cycles();
+    nes.step_instruction().unwrap();
+    nes.cpu().total_cycles() - before
+}
+
+//Runs a single instruction and returns the registers afterwards
+pub fn run(rom: &[u8], registers: Registers, memory: &[(u16, u8)], program: &[u8]) -> (Nes, Registers) {
+    let mut nes = setup(rom, registers, memory, program);
+    step(&mut nes);
+    let registers = nes.cpu().registers();
+    (nes, registers)
+}
+
+pub fn peek(nes: &Nes, address: u16) -> u8 {
+    nes.cpu().bus().peek(address)
+}
+
+//The same for a bare CPU on flat RAM, for the variants that never went into a NES
+pub mod flat {
+    use nes_emulator::Bus::{Bus, FlatRAM};
+    use nes_emulator::CPU6502::{Registers, Variant};
+
+    use super::{ORIGIN, IRQ_HANDLER};
+
+    pub type CPU6502 = nes_emulator::CPU6502::CPU6502<FlatRAM>;
+
+    //RAM with the reset vector pointing at ORIGIN
+    pub fn ram() -> FlatRAM {
+        let mut ram = FlatRAM::new();
+        ram.load(0xFFFC, &[ORIGIN as u8, (ORIGIN >> 8) as u8, IRQ_HANDLER as u8, (IRQ_HANDLER >> 8) as u8]);
+        ram
+    }
+
+    pub fn setup(variant: Variant, registers: Registers, memory: &[(u16, u8)], program: &[u8]) -> CPU6502 {
+        let mut cpu = CPU6502::with_variant(ram(), variant);
+        //Get through the reset sequence first
+        cpu.step().unwrap();
+
+        for (i, byte) in program.iter().enumerate() {
+            cpu.bus_mut().write(registers.program_counter.wrapping_add(i as u16), *byte);
+        }
+        for (address, data) in memory {
+            cpu.bus_mut().write(*address, *data);
+        }
+        cpu.set_registers(registers);
+        cpu
+    }
+
+    pub fn step(cpu: &mut CPU6502) -> u64 {
+        let before = cpu.total_cycles();
+        cpu.step().unwrap();
+        cpu.total_cycles() - before
+    }
+
+    pub fn run(variant: Variant, registers: Registers, memory: &[(u16, u8)], program: &[u8]) -> (CPU6502, Registers) {
+        let mut cpu = setup(variant, registers, memory, program);
+        step(&mut cpu);
+        let registers = cpu.registers();
+        (cpu, registers)
+    }
+}
+
+//Blargg's test ROMs go in res/blargg
+
+const BLARGG_DIR: &str = "res/blargg";
+//Blargg's tests write their status here. $80 means still running, anything under that is the result
+const BLARGG_STATUS: u16 = 0x6000;
+const BLARGG_RUNNING: u8 = 0x80;
+//Followed by a NUL terminated message
+const BLARGG_MESSAGE: u16 = 0x6004;
+//They all finish in well under a minute of emulated time
+const BLARGG_FRAMES: usize = 60 * 60;
+
+//Runs one of blargg's ROMs to the end and returns the result code and message
+pub fn run_blargg(name: &str) -> (u8, String) {
+    let path = format!("{}/{}", BLARGG_DIR, name);
+    let mut nes = Nes::from_rom_file(&path).unwrap_or_else(|e| panic!("couldn't load {}: {}", path, e));
+
+    //The status byte is garbage until the signature at $6001 shows up
+    let started = |nes: &Nes| (1..4).map(|i| nes.cpu().bus().peek(BLARGG_STATUS + i)).eq([0xDE, 0xB0, 0x61].iter().copied());
+    for _ in 0..BLARGG_FRAMES {
+        nes.run_frame().unwrap();
+        if started(&nes) && nes.cpu().bus().peek(BLARGG_STATUS) < BLARGG_RUNNING {
+            break;
+        }
+    }
+    assert!(started(&nes), "{} never reported through ${:04X}", path, BLARGG_STATUS);
+
+    let message = (BLARGG_MESSAGE..)
+        .map(|address| nes.cpu().bus().peek(address))
+        .take_while(|&byte| byte != 0)
+        .map(|byte| byte as char)
+        .collect();
+    (nes.cpu().bus().peek(BLARGG_STATUS), message)
+}
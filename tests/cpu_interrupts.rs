@@ -0,0 +1,242 @@
+//Checks when the CPU notices NMI and IRQ. These run on flat RAM with interrupt lines the test pulls
+//low from a given cycle, so they can land on any cycle of an instruction
+
+use nes_emulator::Bus::{Bus, FlatRAM};
+use nes_emulator::CPU6502::Registers;
+
+mod common;
+
+use common::{registers, ORIGIN, IRQ_HANDLER, IRQ, BRK, UNUSED};
+
+type CPU6502 = nes_emulator::CPU6502::CPU6502<InterruptRAM>;
+
+const NMI_HANDLER: u16 = 0x0900;
+const RESET_HANDLER: u16 = 0x1234;
+//Where the flags end up when an interrupt is taken with the stack pointer at $FD
+const PUSHED_FLAGS: u16 = 0x01FB;
+
+const NOP: u8 = 0xEA;
+
+//Flat RAM with /NMI and /IRQ lines. Each is pulled low from the cycle given, or left high for None
+struct InterruptRAM {
+    ram: FlatRAM,
+    cycle: u64,
+    nmi_from: Option<u64>,
+    irq_from: Option<u64>
+}
+
+impl Bus for InterruptRAM {
+    fn read(&mut self, address: u16) -> u8 {
+        self.ram.read(address)
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        self.ram.write(address, data);
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        self.ram.peek(address)
+    }
+
+    fn tick(&mut self) -> bool {
+        self.cycle += 1;
+        false
+    }
+
+    fn nmi(&self) -> bool {
+        self.nmi_from.is_some_and(|from| self.cycle >= from)
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_from.is_some_and(|from| self.cycle >= from)
+    }
+}
+
+//NOPs everywhere the tests go, with the program poked in over them
+fn setup(registers: Registers, program: &[u8]) -> CPU6502 {
+    let mut ram = FlatRAM::new();
+    for &handler in [ORIGIN, NMI_HANDLER, IRQ_HANDLER].iter() {
+        ram.load(handler, &[NOP; 0x200]);
+    }
+    ram.load(0xFFFA, &[NMI_HANDLER as u8, (NMI_HANDLER >> 8) as u8]);
+    ram.load(0xFFFC, &[RESET_HANDLER as u8, (RESET_HANDLER >> 8) as u8]);
+    ram.load(0xFFFE, &[IRQ_HANDLER as u8, (IRQ_HANDLER >> 8) as u8]);
+
+    let mut cpu = CPU6502::new(InterruptRAM { ram, cycle: 0, nmi_from: None, irq_from: None });
+    //Get through the reset sequence first
+    cpu.step().unwrap();
+    for (i, byte) in program.iter().enumerate() {
+        cpu.bus_mut().write(registers.program_counter.wrapping_add(i as u16), *byte);
+    }
+    cpu.set_registers(registers);
+    cpu
+}
+
+//Pulls the line low from this many cycles into the next step
+fn nmi_in(cpu: &mut CPU6502, cycles: u64) {
+    cpu.bus_mut().nmi_from = Some(cpu.bus().cycle + cycles);
+}
+
+fn irq_in(cpu: &mut CPU6502, cycles: u64) {
+    cpu.bus_mut().irq_from = Some(cpu.bus().cycle + cycles);
+}
+
+//Steps and returns where the PC ended up
+fn step(cpu: &mut CPU6502) -> u16 {
+    cpu.step().unwrap();
+    cpu.registers().program_counter
+}
+
+fn pushed_return_address(cpu: &CPU6502) -> u16 {
+    cpu.bus().peek(PUSHED_FLAGS + 1) as u16 | (cpu.bus().peek(PUSHED_FLAGS + 2) as u16) << 8
+}
+
+#[test]
+fn the_reset_vector_is_read_at_power_on() {
+    let mut ram = FlatRAM::new();
+    ram.load(0xFFFC, &[RESET_HANDLER as u8, (RESET_HANDLER >> 8) as u8]);
+    let mut cpu = CPU6502::new(InterruptRAM { ram, cycle: 0, nmi_from: None, irq_from: None });
+    assert_eq!(step(&mut cpu), RESET_HANDLER);
+    let r = cpu.registers();
+    assert_eq!((r.stack_pointer, r.status & IRQ, cpu.total_cycles()), (0xFD, IRQ, 7));
+}
+
+#[test]
+fn nmi_fires_once_per_falling_edge() {
+    let mut cpu = setup(registers(), &[]);
+    nmi_in(&mut cpu, 0);
+    assert_eq!(step(&mut cpu), ORIGIN + 1);
+    assert_eq!(step(&mut cpu), NMI_HANDLER);
+    assert_eq!(pushed_return_address(&cpu), ORIGIN + 1);
+
+    //Holding the line low doesn't fire it again
+    for i in 1..=3 {
+        assert_eq!(step(&mut cpu), NMI_HANDLER + i);
+    }
+
+    //It has to go high and come back down
+    cpu.bus_mut().nmi_from = None;
+    assert_eq!(step(&mut cpu), NMI_HANDLER + 4);
+    nmi_in(&mut cpu, 0);
+    assert_eq!(step(&mut cpu), NMI_HANDLER + 5);
+    assert_eq!(step(&mut cpu), NMI_HANDLER);
+    assert_eq!(cpu.registers().stack_pointer, 0xFD - 6);
+}
+
+#[test]
+fn nmi_ignores_the_i_flag() {
+    let mut cpu = setup(Registers { status: UNUSED | IRQ, ..registers() }, &[]);
+    nmi_in(&mut cpu, 0);
+    step(&mut cpu);
+    assert_eq!(step(&mut cpu), NMI_HANDLER);
+}
+
+#[test]
+fn irq_fires_for_as_long_as_the_line_is_low_unless_i_is_set() {
+    //Masked
+    let mut cpu = setup(Registers { status: UNUSED | IRQ, ..registers() }, &[]);
+    irq_in(&mut cpu, 0);
+    for i in 1..=3 {
+        assert_eq!(step(&mut cpu), ORIGIN + i);
+    }
+
+    //The handler is an RTI, which clears I again so it comes straight back
+    let mut cpu = setup(Registers { status: UNUSED, ..registers() }, &[]);
+    cpu.bus_mut().write(IRQ_HANDLER, 0x40);
+    irq_in(&mut cpu, 0);
+    assert_eq!(step(&mut cpu), ORIGIN + 1);
+    assert_eq!(step(&mut cpu), IRQ_HANDLER);
+    assert_eq!(cpu.bus().peek(PUSHED_FLAGS) & (IRQ | BRK), 0);
+    assert_eq!(cpu.registers().status & IRQ, IRQ);
+    assert_eq!(step(&mut cpu), ORIGIN + 1);
+    assert_eq!(step(&mut cpu), IRQ_HANDLER);
+
+    //Until it's acknowledged and the line goes high
+    cpu.bus_mut().irq_from = None;
+    assert_eq!(step(&mut cpu), ORIGIN + 1);
+    assert_eq!(step(&mut cpu), ORIGIN + 2);
+}
+
+#[test]
+fn cli_and_plp_let_one_more_instruction_run_before_the_irq() {
+    //CLI, or PLP pulling flags with I clear
+    let programs: [&[u8]; 2] = [&[0x58], &[0x28]];
+    for program in programs.iter() {
+        let mut cpu = setup(Registers { stack_pointer: 0xFC, status: UNUSED | IRQ, ..registers() }, program);
+        cpu.bus_mut().write(0x01FD, UNUSED);
+        irq_in(&mut cpu, 0);
+        assert_eq!(step(&mut cpu), ORIGIN + 1, "{:02X?}", program);
+        assert_eq!(cpu.registers().status & IRQ, 0, "{:02X?}", program);
+        assert_eq!(step(&mut cpu), ORIGIN + 2, "{:02X?}", program);
+        assert_eq!(step(&mut cpu), IRQ_HANDLER, "{:02X?}", program);
+    }
+}
+
+#[test]
+fn sei_still_lets_an_irq_in_right_after_it() {
+    //The IRQ turns up on SEI's first cycle, before I is set
+    let mut cpu = setup(Registers { status: UNUSED, ..registers() }, &[0x78]);
+    irq_in(&mut cpu, 1);
+    assert_eq!(step(&mut cpu), ORIGIN + 1);
+    assert_eq!(step(&mut cpu), IRQ_HANDLER);
+    //With I set in the pushed flags
+    assert_eq!(cpu.bus().peek(PUSHED_FLAGS) & IRQ, IRQ);
+}
+
+#[test]
+fn nmi_can_hijack_brk() {
+    //An NMI seen before the vector is picked sends BRK to the NMI handler, B flag and all
+    let mut cpu = setup(registers(), &[0x00, 0xFF]);
+    nmi_in(&mut cpu, 3);
+    assert_eq!(step(&mut cpu), NMI_HANDLER);
+    assert_eq!(pushed_return_address(&cpu), ORIGIN + 2);
+    assert_eq!(cpu.bus().peek(PUSHED_FLAGS) & BRK, BRK);
+
+    //Any later and BRK gets to its handler, which runs one instruction before the NMI
+    let mut cpu = setup(registers(), &[0x00, 0xFF]);
+    nmi_in(&mut cpu, 5);
+    assert_eq!(step(&mut cpu), IRQ_HANDLER);
+    assert_eq!(step(&mut cpu), IRQ_HANDLER + 1);
+    assert_eq!(step(&mut cpu), NMI_HANDLER);
+}
+
+#[test]
+fn nmi_can_hijack_an_irq() {
+    let mut cpu = setup(Registers { status: UNUSED, ..registers() }, &[]);
+    irq_in(&mut cpu, 0);
+    step(&mut cpu);
+    //Partway through the IRQ sequence
+    nmi_in(&mut cpu, 2);
+    assert_eq!(step(&mut cpu), NMI_HANDLER);
+    assert_eq!(pushed_return_address(&cpu), ORIGIN + 1);
+    assert_eq!(cpu.bus().peek(PUSHED_FLAGS) & BRK, 0);
+}
+
+#[test]
+fn a_taken_branch_that_stays_on_the_page_holds_off_a_late_interrupt() {
+    //(where the BNE is, offset, cycle the interrupt shows up on, where it ends up, if the interrupt comes straight after)
+    let cases = [
+        //3 cycles, but only the first polls for interrupts
+        (ORIGIN, 0x02, 1, ORIGIN + 4, true),
+        (ORIGIN, 0x02, 2, ORIGIN + 4, false),
+        //Onto the next page it's 4 cycles and the third one polls as normal
+        (ORIGIN + 0xFC, 0x10, 2, ORIGIN + 0x10E, true)
+    ];
+    //The NMI is latched rather than held, but it still gets put off rather than lost
+    for &(name, handler) in [("IRQ", IRQ_HANDLER), ("NMI", NMI_HANDLER)].iter() {
+        for &(origin, offset, cycle, target, immediate) in cases.iter() {
+            let mut cpu = setup(Registers { program_counter: origin, status: UNUSED, ..registers() }, &[0xD0, offset]);
+            if handler == NMI_HANDLER {
+                nmi_in(&mut cpu, cycle);
+            } else {
+                irq_in(&mut cpu, cycle);
+            }
+            assert_eq!(step(&mut cpu), target, "BNE {:02X} at {:04X}", offset, origin);
+            if !immediate {
+                assert_eq!(step(&mut cpu), target + 1, "BNE {:02X} at {:04X} {} on {}", offset, origin, name, cycle);
+            }
+            assert_eq!(step(&mut cpu), handler, "BNE {:02X} at {:04X} {} on {}", offset, origin, name, cycle);
+            assert_eq!(pushed_return_address(&cpu), if immediate { target } else { target + 1 });
+        }
+    }
+}
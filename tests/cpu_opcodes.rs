@@ -0,0 +1,785 @@
+//Checks each opcode against the 6502's documented behaviour, or the undocumented behaviour for the
+//unofficial ones. Programs are poked into RAM at ORIGIN and run one instruction at a time
+
+use nes_emulator::NesError;
+use nes_emulator::CPU6502::Registers;
+
+mod common;
+
+use common::{nrom, registers, setup, step, run, peek, ORIGIN, IRQ_HANDLER};
+use common::{CARRY, ZERO, IRQ, DECIMAL, BRK, UNUSED, OVERFLOW, NEGATIVE};
+
+//(opcode, length, cycles) when no page is crossed and no branch is taken.
+//A length of 0 means the instruction sets the PC itself
+const OPCODES: [(u8, u16, u64); 151] = [
+    //ADC
+    (0x61, 2, 6), (0x65, 2, 3), (0x69, 2, 2), (0x6D, 3, 4), (0x71, 2, 5), (0x75, 2, 4), (0x79, 3, 4), (0x7D, 3, 4),
+    //AND
+    (0x21, 2, 6), (0x25, 2, 3), (0x29, 2, 2), (0x2D, 3, 4), (0x31, 2, 5), (0x35, 2, 4), (0x39, 3, 4), (0x3D, 3, 4),
+    //ASL
+    (0x06, 2, 5), (0x0A, 1, 2), (0x0E, 3, 6), (0x16, 2, 6), (0x1E, 3, 7),
+    //BCC
+    (0x90, 2, 2),
+    //BCS
+    (0xB0, 2, 2),
+    //BEQ
+    (0xF0, 2, 2),
+    //BIT
+    (0x24, 2, 3), (0x2C, 3, 4),
+    //BMI
+    (0x30, 2, 2),
+    //BNE
+    (0xD0, 2, 2),
+    //BPL
+    (0x10, 2, 2),
+    //BRK
+    (0x00, 0, 7),
+    //BVC
+    (0x50, 2, 2),
+    //BVS
+    (0x70, 2, 2),
+    //CLC
+    (0x18, 1, 2),
+    //CLD
+    (0xD8, 1, 2),
+    //CLI
+    (0x58, 1, 2),
+    //CLV
+    (0xB8, 1, 2),
+    //CMP
+    (0xC1, 2, 6), (0xC5, 2, 3), (0xC9, 2, 2), (0xCD, 3, 4), (0xD1, 2, 5), (0xD5, 2, 4), (0xD9, 3, 4), (0xDD, 3, 4),
+    //CPX
+    (0xE0, 2, 2), (0xE4, 2, 3), (0xEC, 3, 4),
+    //CPY
+    (0xC0, 2, 2), (0xC4, 2, 3), (0xCC, 3, 4),
+    //DEC
+    (0xC6, 2, 5), (0xCE, 3, 6), (0xD6, 2, 6), (0xDE, 3, 7),
+    //DEX
+    (0xCA, 1, 2),
+    //DEY
+    (0x88, 1, 2),
+    //EOR
+    (0x41, 2, 6), (0x45, 2, 3), (0x49, 2, 2), (0x4D, 3, 4), (0x51, 2, 5), (0x55, 2, 4), (0x59, 3, 4), (0x5D, 3, 4),
+    //INC
+    (0xE6, 2, 5), (0xEE, 3, 6), (0xF6, 2, 6), (0xFE, 3, 7),
+    //INX
+    (0xE8, 1, 2),
+    //INY
+    (0xC8, 1, 2),
+    //JMP
+    (0x4C, 0, 3), (0x6C, 0, 5),
+    //JSR
+    (0x20, 0, 6),
+    //LDA
+    (0xA1, 2, 6), (0xA5, 2, 3), (0xA9, 2, 2), (0xAD, 3, 4), (0xB1, 2, 5), (0xB5, 2, 4), (0xB9, 3, 4), (0xBD, 3, 4),
+    //LDX
+    (0xA2, 2, 2), (0xA6, 2, 3), (0xAE, 3, 4), (0xB6, 2, 4), (0xBE, 3, 4),
+    //LDY
+    (0xA0, 2, 2), (0xA4, 2, 3), (0xAC, 3, 4), (0xB4, 2, 4), (0xBC, 3, 4),
+    //LSR
+    (0x46, 2, 5), (0x4A, 1, 2), (0x4E, 3, 6), (0x56, 2, 6), (0x5E, 3, 7),
+    //NOP
+    (0xEA, 1, 2),
+    //ORA
+    (0x01, 2, 6), (0x05, 2, 3), (0x09, 2, 2), (0x0D, 3, 4), (0x11, 2, 5), (0x15, 2, 4), (0x19, 3, 4), (0x1D, 3, 4),
+    //PHA
+    (0x48, 1, 3),
+    //PHP
+    (0x08, 1, 3),
+    //PLA
+    (0x68, 1, 4),
+    //PLP
+    (0x28, 1, 4),
+    //ROL
+    (0x26, 2, 5), (0x2A, 1, 2), (0x2E, 3, 6), (0x36, 2, 6), (0x3E, 3, 7),
+    //ROR
+    (0x66, 2, 5), (0x6A, 1, 2), (0x6E, 3, 6), (0x76, 2, 6), (0x7E, 3, 7),
+    //RTI
+    (0x40, 0, 6),
+    //RTS
+    (0x60, 0, 6),
+    //SBC
+    (0xE1, 2, 6), (0xE5, 2, 3), (0xE9, 2, 2), (0xED, 3, 4), (0xF1, 2, 5), (0xF5, 2, 4), (0xF9, 3, 4), (0xFD, 3, 4),
+    //SEC
+    (0x38, 1, 2),
+    //SED
+    (0xF8, 1, 2),
+    //SEI
+    (0x78, 1, 2),
+    //STA
+    (0x81, 2, 6), (0x85, 2, 3), (0x8D, 3, 4), (0x91, 2, 6), (0x95, 2, 4), (0x99, 3, 5), (0x9D, 3, 5),
+    //STX
+    (0x86, 2, 3), (0x8E, 3, 4), (0x96, 2, 4),
+    //STY
+    (0x84, 2, 3), (0x8C, 3, 4), (0x94, 2, 4),
+    //TAX
+    (0xAA, 1, 2),
+    //TAY
+    (0xA8, 1, 2),
+    //TSX
+    (0xBA, 1, 2),
+    //TXA
+    (0x8A, 1, 2),
+    //TXS
+    (0x9A, 1, 2),
+    //TYA
+    (0x98, 1, 2)
+];
+
+//The same for the unofficial opcodes, apart from the KILs
+const UNOFFICIAL_OPCODES: [(u8, u16, u64); 93] = [
+    //ALR
+    (0x4B, 2, 2),
+    //ANC
+    (0x0B, 2, 2), (0x2B, 2, 2),
+    //ANE
+    (0x8B, 2, 2),
+    //ARR
+    (0x6B, 2, 2),
+    //AXS
+    (0xCB, 2, 2),
+    //DCP
+    (0xC3, 2, 8), (0xC7, 2, 5), (0xCF, 3, 6), (0xD3, 2, 8), (0xD7, 2, 6), (0xDB, 3, 7), (0xDF, 3, 7),
+    //ISB
+    (0xE3, 2, 8), (0xE7, 2, 5), (0xEF, 3, 6), (0xF3, 2, 8), (0xF7, 2, 6), (0xFB, 3, 7), (0xFF, 3, 7),
+    //LAS
+    (0xBB, 3, 4),
+    //LAX
+    (0xA3, 2, 6), (0xA7, 2, 3), (0xAF, 3, 4), (0xB3, 2, 5), (0xB7, 2, 4), (0xBF, 3, 4),
+    //LXA
+    (0xAB, 2, 2),
+    //NOP
+    (0x04, 2, 3), (0x0C, 3, 4), (0x14, 2, 4), (0x1A, 1, 2), (0x1C, 3, 4), (0x34, 2, 4), (0x3A, 1, 2), (0x3C, 3, 4),
+    (0x44, 2, 3), (0x54, 2, 4), (0x5A, 1, 2), (0x5C, 3, 4), (0x64, 2, 3), (0x74, 2, 4), (0x7A, 1, 2), (0x7C, 3, 4),
+    (0x80, 2, 2), (0x82, 2, 2), (0x89, 2, 2), (0xC2, 2, 2), (0xD4, 2, 4), (0xDA, 1, 2), (0xDC, 3, 4), (0xE2, 2, 2),
+    (0xF4, 2, 4), (0xFA, 1, 2), (0xFC, 3, 4),
+    //RLA
+    (0x23, 2, 8), (0x27, 2, 5), (0x2F, 3, 6), (0x33, 2, 8), (0x37, 2, 6), (0x3B, 3, 7), (0x3F, 3, 7),
+    //RRA
+    (0x63, 2, 8), (0x67, 2, 5), (0x6F, 3, 6), (0x73, 2, 8), (0x77, 2, 6), (0x7B, 3, 7), (0x7F, 3, 7),
+    //SAX
+    (0x83, 2, 6), (0x87, 2, 3), (0x8F, 3, 4), (0x97, 2, 4),
+    //SBC
+    (0xEB, 2, 2),
+    //SHA
+    (0x93, 2, 6), (0x9F, 3, 5),
+    //SHX
+    (0x9E, 3, 5),
+    //SHY
+    (0x9C, 3, 5),
+    //SLO
+    (0x03, 2, 8), (0x07, 2, 5), (0x0F, 3, 6), (0x13, 2, 8), (0x17, 2, 6), (0x1B, 3, 7), (0x1F, 3, 7),
+    //SRE
+    (0x43, 2, 8), (0x47, 2, 5), (0x4F, 3, 6), (0x53, 2, 8), (0x57, 2, 6), (0x5B, 3, 7), (0x5F, 3, 7),
+    //TAS
+    (0x9B, 3, 5)
+];
+
+const JAMS: [u8; 12] = [0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2];
+
+#[test]
+fn every_official_opcode_has_the_right_length_and_cycles() {
+    check_lengths_and_cycles(&OPCODES);
+}
+
+#[test]
+fn every_unofficial_opcode_has_the_right_length_and_cycles() {
+    check_lengths_and_cycles(&UNOFFICIAL_OPCODES);
+}
+
+fn check_lengths_and_cycles(opcodes: &[(u8, u16, u64)]) {
+    for &(opcode, length, cycles) in opcodes.iter() {
+        //Pick flags that leave branches untaken. Opcodes with bit 5 set branch when the flag is set
+        let status = match opcode & 0x1F == 0x10 && opcode & 0x20 == 0 {
+            true => NEGATIVE | OVERFLOW | UNUSED | IRQ | ZERO | CARRY,
+            false => UNUSED | IRQ
+        };
+        let mut nes = setup(&nrom(), Registers { status, ..registers() }, &[], &[opcode, 0x00, 0x00]);
+
+        assert_eq!(step(&mut nes), cycles, "cycles for {:02X}", opcode);
+        if length != 0 {
+            assert_eq!(nes.cpu().registers().program_counter, ORIGIN + length, "length of {:02X}", opcode);
+        }
+    }
+}
+
+#[test]
+fn adc_adds_the_carry_in() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x10, status: UNUSED | CARRY, ..registers() }, &[], &[0x69, 0x20]);
+    assert_eq!(r.accumulator, 0x31);
+    assert_eq!(r.status, UNUSED);
+}
+
+#[test]
+fn adc_sets_and_clears_carry() {
+    let mut nes = setup(&nrom(), Registers { accumulator: 0xFF, status: UNUSED, ..registers() }, &[], &[0x69, 0x01, 0x69, 0x01]);
+    step(&mut nes);
+    assert_eq!(nes.cpu().registers().accumulator, 0x00);
+    assert_eq!(nes.cpu().registers().status, UNUSED | CARRY | ZERO);
+
+    step(&mut nes);
+    assert_eq!(nes.cpu().registers().accumulator, 0x02);
+    assert_eq!(nes.cpu().registers().status, UNUSED);
+}
+
+#[test]
+fn adc_sets_overflow_on_signed_overflow() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x50, status: UNUSED, ..registers() }, &[], &[0x69, 0x50]);
+    assert_eq!(r.accumulator, 0xA0);
+    assert_eq!(r.status, UNUSED | NEGATIVE | OVERFLOW);
+
+    let (_, r) = run(&nrom(), Registers { accumulator: 0xD0, status: UNUSED, ..registers() }, &[], &[0x69, 0x90]);
+    assert_eq!(r.accumulator, 0x60);
+    assert_eq!(r.status, UNUSED | OVERFLOW | CARRY);
+}
+
+#[test]
+fn adc_reads_memory_operands() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x01, reg_x: 0x04, status: UNUSED, ..registers() }, &[(0x0304, 0x02)], &[0x7D, 0x00, 0x03]);
+    assert_eq!(r.accumulator, 0x03);
+}
+
+#[test]
+fn sbc_borrows_when_carry_is_clear() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x05, status: UNUSED, ..registers() }, &[], &[0xE9, 0x03]);
+    assert_eq!(r.accumulator, 0x01);
+    assert_eq!(r.status, UNUSED | CARRY);
+
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x50, status: UNUSED | CARRY, ..registers() }, &[], &[0xE9, 0xF0]);
+    assert_eq!(r.accumulator, 0x60);
+    assert_eq!(r.status, UNUSED);
+}
+
+#[test]
+fn sbc_sets_overflow_on_signed_overflow() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x50, status: UNUSED | CARRY, ..registers() }, &[(0x0010, 0xB0)], &[0xE5, 0x10]);
+    assert_eq!(r.accumulator, 0xA0);
+    assert_eq!(r.status, UNUSED | NEGATIVE | OVERFLOW);
+}
+
+#[test]
+fn and_sets_zero_and_negative_but_not_carry() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0xF0, status: UNUSED | CARRY | ZERO, ..registers() }, &[], &[0x29, 0x8F]);
+    assert_eq!(r.accumulator, 0x80);
+    assert_eq!(r.status, UNUSED | CARRY | NEGATIVE);
+
+    let (_, r) = run(&nrom(), Registers { accumulator: 0xF0, status: UNUSED | NEGATIVE, ..registers() }, &[], &[0x29, 0x0F]);
+    assert_eq!(r.accumulator, 0x00);
+    assert_eq!(r.status, UNUSED | ZERO);
+}
+
+#[test]
+fn ora_ors_into_the_accumulator() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x01, ..registers() }, &[(0x0010, 0x80)], &[0x05, 0x10]);
+    assert_eq!(r.accumulator, 0x81);
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE);
+
+    let (_, r) = run(&nrom(), registers(), &[], &[0x09, 0x00]);
+    assert_eq!(r.status, UNUSED | IRQ | ZERO);
+}
+
+#[test]
+fn eor_xors_into_the_accumulator() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x0F, ..registers() }, &[], &[0x49, 0xF0]);
+    assert_eq!(r.accumulator, 0xFF);
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE);
+
+    let (_, r) = run(&nrom(), Registers { accumulator: 0xFF, ..registers() }, &[(0x0300, 0xFF)], &[0x4D, 0x00, 0x03]);
+    assert_eq!(r.accumulator, 0x00);
+    assert_eq!(r.status, UNUSED | IRQ | ZERO);
+}
+
+#[test]
+fn asl_shifts_the_accumulator_and_memory() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x81, ..registers() }, &[], &[0x0A]);
+    assert_eq!(r.accumulator, 0x02);
+    assert_eq!(r.status, UNUSED | IRQ | CARRY);
+
+    let (nes, r) = run(&nrom(), Registers { accumulator: 0x55, ..registers() }, &[(0x0010, 0x40)], &[0x06, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x80);
+    assert_eq!(r.accumulator, 0x55);
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE);
+
+    let (nes, _) = run(&nrom(), Registers { reg_x: 0x02, ..registers() }, &[(0x0302, 0x01)], &[0x1E, 0x00, 0x03]);
+    assert_eq!(peek(&nes, 0x0302), 0x02);
+}
+
+#[test]
+fn lsr_shifts_the_accumulator_and_memory() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x01, ..registers() }, &[], &[0x4A]);
+    assert_eq!(r.accumulator, 0x00);
+    assert_eq!(r.status, UNUSED | IRQ | CARRY | ZERO);
+
+    let (nes, r) = run(&nrom(), registers(), &[(0x0300, 0x82)], &[0x4E, 0x00, 0x03]);
+    assert_eq!(peek(&nes, 0x0300), 0x41);
+    assert_eq!(r.status, UNUSED | IRQ);
+}
+
+#[test]
+fn rol_rotates_through_carry() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x80, status: UNUSED | CARRY, ..registers() }, &[], &[0x2A]);
+    assert_eq!(r.accumulator, 0x01);
+    assert_eq!(r.status, UNUSED | CARRY);
+
+    let (nes, r) = run(&nrom(), Registers { reg_x: 0x01, status: UNUSED, ..registers() }, &[(0x0011, 0x40)], &[0x36, 0x10]);
+    assert_eq!(peek(&nes, 0x0011), 0x80);
+    assert_eq!(r.status, UNUSED | NEGATIVE);
+}
+
+#[test]
+fn ror_rotates_through_carry() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x01, status: UNUSED | CARRY, ..registers() }, &[], &[0x6A]);
+    assert_eq!(r.accumulator, 0x80);
+    assert_eq!(r.status, UNUSED | CARRY | NEGATIVE);
+
+    let (nes, r) = run(&nrom(), Registers { status: UNUSED, ..registers() }, &[(0x0300, 0x01)], &[0x6E, 0x00, 0x03]);
+    assert_eq!(peek(&nes, 0x0300), 0x00);
+    assert_eq!(r.status, UNUSED | CARRY | ZERO);
+}
+
+#[test]
+fn inc_and_dec_write_memory() {
+    let (nes, r) = run(&nrom(), registers(), &[(0x0010, 0xFF)], &[0xE6, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x00);
+    assert_eq!(r.status, UNUSED | IRQ | ZERO);
+
+    let (nes, r) = run(&nrom(), Registers { reg_x: 0x01, ..registers() }, &[(0x0301, 0x00)], &[0xDE, 0x00, 0x03]);
+    assert_eq!(peek(&nes, 0x0301), 0xFF);
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE);
+}
+
+#[test]
+fn register_increments_and_decrements_wrap() {
+    let (_, r) = run(&nrom(), Registers { reg_x: 0xFF, ..registers() }, &[], &[0xE8]);
+    assert_eq!((r.reg_x, r.status), (0x00, UNUSED | IRQ | ZERO));
+
+    let (_, r) = run(&nrom(), Registers { reg_y: 0x7F, ..registers() }, &[], &[0xC8]);
+    assert_eq!((r.reg_y, r.status), (0x80, UNUSED | IRQ | NEGATIVE));
+
+    let (_, r) = run(&nrom(), registers(), &[], &[0xCA]);
+    assert_eq!((r.reg_x, r.status), (0xFF, UNUSED | IRQ | NEGATIVE));
+
+    let (_, r) = run(&nrom(), Registers { reg_y: 0x01, ..registers() }, &[], &[0x88]);
+    assert_eq!((r.reg_y, r.status), (0x00, UNUSED | IRQ | ZERO));
+}
+
+#[test]
+fn compares_set_carry_zero_and_negative() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x40, ..registers() }, &[], &[0xC9, 0x40]);
+    assert_eq!(r.status, UNUSED | IRQ | CARRY | ZERO);
+
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x40, ..registers() }, &[], &[0xC9, 0x41]);
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE);
+
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x40, ..registers() }, &[], &[0xC9, 0x3F]);
+    assert_eq!(r.status, UNUSED | IRQ | CARRY);
+
+    let (_, r) = run(&nrom(), Registers { reg_x: 0x10, ..registers() }, &[(0x0010, 0x20)], &[0xE4, 0x10]);
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE);
+
+    let (_, r) = run(&nrom(), Registers { reg_y: 0x80, ..registers() }, &[(0x0300, 0x01)], &[0xCC, 0x00, 0x03]);
+    assert_eq!(r.status, UNUSED | IRQ | CARRY);
+}
+
+#[test]
+fn bit_copies_the_top_bits_and_ands_for_zero() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x0F, ..registers() }, &[(0x0010, 0xC0)], &[0x24, 0x10]);
+    assert_eq!(r.accumulator, 0x0F);
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE | OVERFLOW | ZERO);
+
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x01, status: UNUSED | NEGATIVE | OVERFLOW | ZERO, ..registers() },
+        &[(0x0300, 0x01)], &[0x2C, 0x00, 0x03]);
+    assert_eq!(r.status, UNUSED);
+}
+
+#[test]
+fn loads_set_zero_and_negative() {
+    let (_, r) = run(&nrom(), registers(), &[], &[0xA9, 0x80]);
+    assert_eq!((r.accumulator, r.status), (0x80, UNUSED | IRQ | NEGATIVE));
+
+    let (_, r) = run(&nrom(), Registers { reg_y: 0x02, ..registers() }, &[(0x0012, 0x00)], &[0xB6, 0x10]);
+    assert_eq!((r.reg_x, r.status), (0x00, UNUSED | IRQ | ZERO));
+
+    let (_, r) = run(&nrom(), Registers { reg_x: 0x01, ..registers() }, &[(0x0301, 0x42)], &[0xBC, 0x00, 0x03]);
+    assert_eq!((r.reg_y, r.status), (0x42, UNUSED | IRQ));
+}
+
+#[test]
+fn zero_page_indexing_wraps() {
+    let (_, r) = run(&nrom(), Registers { reg_x: 0x01, ..registers() }, &[(0x0000, 0x42), (0x0100, 0x99)], &[0xB5, 0xFF]);
+    assert_eq!(r.accumulator, 0x42);
+}
+
+#[test]
+fn indexed_indirect_pointer_wraps() {
+    let memory = [(0x00FF, 0x34), (0x0000, 0x02), (0x0100, 0x03), (0x0234, 0x99)];
+    let (_, r) = run(&nrom(), Registers { reg_x: 0x01, ..registers() }, &memory, &[0xA1, 0xFE]);
+    assert_eq!(r.accumulator, 0x99);
+}
+
+#[test]
+fn indirect_indexed_adds_y_after_the_lookup() {
+    let memory = [(0x0010, 0xF0), (0x0011, 0x02), (0x0310, 0x77)];
+    let mut nes = setup(&nrom(), Registers { reg_y: 0x20, ..registers() }, &memory, &[0xB1, 0x10]);
+    //Crossing into $03xx costs a cycle
+    assert_eq!(step(&mut nes), 6);
+    assert_eq!(nes.cpu().registers().accumulator, 0x77);
+
+    let memory = [(0x00FF, 0x00), (0x0000, 0x03), (0x0105, 0x11), (0x0305, 0x22)];
+    let (_, r) = run(&nrom(), Registers { reg_y: 0x05, ..registers() }, &memory, &[0xB1, 0xFF]);
+    assert_eq!(r.accumulator, 0x22);
+}
+
+#[test]
+fn absolute_indexing_crosses_pages() {
+    let mut nes = setup(&nrom(), Registers { reg_x: 0x20, ..registers() }, &[(0x0310, 0x55)], &[0xBD, 0xF0, 0x02]);
+    assert_eq!(step(&mut nes), 5);
+    assert_eq!(nes.cpu().registers().accumulator, 0x55);
+
+    let (_, r) = run(&nrom(), Registers { reg_y: 0x01, ..registers() }, &[(0x0300, 0x66)], &[0xB9, 0xFF, 0x02]);
+    assert_eq!(r.accumulator, 0x66);
+}
+
+#[test]
+fn stores_write_to_every_addressing_mode() {
+    let start = Registers { accumulator: 0xA5, reg_x: 0x01, reg_y: 0x02, ..registers() };
+    let pointers = [(0x0020, 0x00), (0x0021, 0x03), (0x0031, 0x10), (0x0032, 0x03)];
+
+    let cases: [(&[u8], u16, u8); 13] = [
+        (&[0x85, 0x10], 0x0010, 0xA5),
+        (&[0x95, 0x10], 0x0011, 0xA5),
+        (&[0x8D, 0x00, 0x03], 0x0300, 0xA5),
+        (&[0x9D, 0x00, 0x03], 0x0301, 0xA5),
+        (&[0x99, 0x00, 0x03], 0x0302, 0xA5),
+        (&[0x81, 0x30], 0x0310, 0xA5),
+        (&[0x91, 0x20], 0x0302, 0xA5),
+        (&[0x86, 0x10], 0x0010, 0x01),
+        (&[0x96, 0x10], 0x0012, 0x01),
+        (&[0x8E, 0x00, 0x03], 0x0300, 0x01),
+        (&[0x84, 0x10], 0x0010, 0x02),
+        (&[0x94, 0x10], 0x0011, 0x02),
+        (&[0x8C, 0x00, 0x03], 0x0300, 0x02)
+    ];
+    for (program, address, data) in cases.iter() {
+        let (nes, r) = run(&nrom(), start, &pointers, program);
+        assert_eq!(peek(&nes, *address), *data, "{:02X?} should write ${:04X}", program, address);
+        //Stores never touch the flags
+        assert_eq!(r.status, start.status);
+    }
+}
+
+#[test]
+fn branches_follow_their_flag() {
+    //(opcode, flag, branches when the flag is set)
+    let branches = [
+        (0x10, NEGATIVE, false), (0x30, NEGATIVE, true),
+        (0x50, OVERFLOW, false), (0x70, OVERFLOW, true),
+        (0x90, CARRY, false), (0xB0, CARRY, true),
+        (0xD0, ZERO, false), (0xF0, ZERO, true)
+    ];
+    for &(opcode, flag, when_set) in branches.iter() {
+        for &set in [false, true].iter() {
+            let status = if set { UNUSED | flag } else { UNUSED };
+            let mut nes = setup(&nrom(), Registers { status, ..registers() }, &[], &[opcode, 0x10]);
+            let cycles = step(&mut nes);
+            let pc = nes.cpu().registers().program_counter;
+            if set == when_set {
+                assert_eq!((pc, cycles), (ORIGIN + 0x12, 3), "{:02X} should branch", opcode);
+            } else {
+                assert_eq!((pc, cycles), (ORIGIN + 0x02, 2), "{:02X} shouldn't branch", opcode);
+            }
+        }
+    }
+}
+
+#[test]
+fn branches_go_backwards_and_pay_for_page_crossings() {
+    //BNE to itself
+    let mut nes = setup(&nrom(), Registers { status: UNUSED, ..registers() }, &[], &[0xD0, 0xFE]);
+    assert_eq!(step(&mut nes), 3);
+    assert_eq!(nes.cpu().registers().program_counter, ORIGIN);
+
+    //$0602 - 128 lands in the page before
+    let mut nes = setup(&nrom(), Registers { status: UNUSED, ..registers() }, &[], &[0xD0, 0x80]);
+    assert_eq!(step(&mut nes), 4);
+    assert_eq!(nes.cpu().registers().program_counter, 0x0582);
+}
+
+#[test]
+fn jmp_absolute_and_indirect() {
+    let (_, r) = run(&nrom(), registers(), &[], &[0x4C, 0x00, 0x07]);
+    assert_eq!(r.program_counter, 0x0700);
+
+    let (_, r) = run(&nrom(), registers(), &[(0x0300, 0x34), (0x0301, 0x12)], &[0x6C, 0x00, 0x03]);
+    assert_eq!(r.program_counter, 0x1234);
+}
+
+#[test]
+fn jmp_indirect_doesnt_cross_pages() {
+    //The high byte comes from $0200 rather than $0300
+    let memory = [(0x02FF, 0x34), (0x0200, 0x12), (0x0300, 0x56)];
+    let (_, r) = run(&nrom(), registers(), &memory, &[0x6C, 0xFF, 0x02]);
+    assert_eq!(r.program_counter, 0x1234);
+}
+
+#[test]
+fn jsr_and_rts_round_trip() {
+    let mut nes = setup(&nrom(), registers(), &[(0x0700, 0x60)], &[0x20, 0x00, 0x07]);
+    step(&mut nes);
+    let r = nes.cpu().registers();
+    assert_eq!((r.program_counter, r.stack_pointer), (0x0700, 0xFB));
+    //The return address pushed is the last byte of the JSR
+    assert_eq!((peek(&nes, 0x01FD), peek(&nes, 0x01FC)), (0x06, 0x02));
+
+    step(&mut nes);
+    let r = nes.cpu().registers();
+    assert_eq!((r.program_counter, r.stack_pointer), (ORIGIN + 3, 0xFD));
+}
+
+#[test]
+fn brk_pushes_state_and_jumps_through_the_irq_vector() {
+    let (nes, r) = run(&nrom(), Registers { status: UNUSED | CARRY, ..registers() }, &[], &[0x00, 0xFF]);
+    assert_eq!(r.program_counter, IRQ_HANDLER);
+    assert_eq!(r.stack_pointer, 0xFA);
+    assert_eq!(r.status, UNUSED | CARRY | IRQ);
+    //BRK skips its padding byte
+    assert_eq!((peek(&nes, 0x01FD), peek(&nes, 0x01FC)), (0x06, 0x02));
+    assert_eq!(peek(&nes, 0x01FB), UNUSED | BRK | CARRY);
+}
+
+#[test]
+fn rti_restores_flags_and_pc() {
+    let memory = [(0x01FB, NEGATIVE | OVERFLOW | BRK | ZERO | CARRY), (0x01FC, 0x34), (0x01FD, 0x12)];
+    let (_, r) = run(&nrom(), Registers { stack_pointer: 0xFA, ..registers() }, &memory, &[0x40]);
+    assert_eq!(r.program_counter, 0x1234);
+    assert_eq!(r.stack_pointer, 0xFD);
+    //B is dropped and U is forced on
+    assert_eq!(r.status, NEGATIVE | OVERFLOW | UNUSED | ZERO | CARRY);
+}
+
+#[test]
+fn pha_and_pla() {
+    let mut nes = setup(&nrom(), Registers { accumulator: 0x80, ..registers() }, &[], &[0x48, 0xA9, 0x00, 0x68]);
+    step(&mut nes);
+    assert_eq!(peek(&nes, 0x01FD), 0x80);
+    assert_eq!(nes.cpu().registers().stack_pointer, 0xFC);
+
+    step(&mut nes);
+    step(&mut nes);
+    let r = nes.cpu().registers();
+    assert_eq!((r.accumulator, r.stack_pointer), (0x80, 0xFD));
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE);
+}
+
+#[test]
+fn php_pushes_b_and_plp_ignores_it() {
+    let (nes, r) = run(&nrom(), Registers { status: UNUSED | CARRY, ..registers() }, &[], &[0x08]);
+    assert_eq!(peek(&nes, 0x01FD), UNUSED | BRK | CARRY);
+    assert_eq!(r.status, UNUSED | CARRY);
+
+    let (_, r) = run(&nrom(), Registers { stack_pointer: 0xFC, ..registers() }, &[(0x01FD, 0xFF)], &[0x28]);
+    assert_eq!(r.status, !BRK);
+    assert_eq!(r.stack_pointer, 0xFD);
+}
+
+#[test]
+fn flag_instructions() {
+    //(opcode, flag, sets it)
+    let cases = [
+        (0x18, CARRY, false), (0x38, CARRY, true),
+        (0x58, IRQ, false), (0x78, IRQ, true),
+        (0xD8, DECIMAL, false), (0xF8, DECIMAL, true),
+        (0xB8, OVERFLOW, false)
+    ];
+    for &(opcode, flag, sets) in cases.iter() {
+        let start = if sets { UNUSED } else { UNUSED | flag };
+        let (_, r) = run(&nrom(), Registers { status: start, ..registers() }, &[], &[opcode]);
+        assert_eq!(r.status, start ^ flag, "{:02X}", opcode);
+    }
+}
+
+#[test]
+fn transfers() {
+    let start = Registers { accumulator: 0x80, reg_x: 0x00, reg_y: 0x01, stack_pointer: 0xF0, ..registers() };
+
+    let (_, r) = run(&nrom(), start, &[], &[0xAA]);
+    assert_eq!((r.reg_x, r.status), (0x80, UNUSED | IRQ | NEGATIVE));
+    let (_, r) = run(&nrom(), start, &[], &[0xA8]);
+    assert_eq!((r.reg_y, r.status), (0x80, UNUSED | IRQ | NEGATIVE));
+    let (_, r) = run(&nrom(), start, &[], &[0x8A]);
+    assert_eq!((r.accumulator, r.status), (0x00, UNUSED | IRQ | ZERO));
+    let (_, r) = run(&nrom(), start, &[], &[0x98]);
+    assert_eq!((r.accumulator, r.status), (0x01, UNUSED | IRQ));
+    let (_, r) = run(&nrom(), start, &[], &[0xBA]);
+    assert_eq!((r.reg_x, r.status), (0xF0, UNUSED | IRQ | NEGATIVE));
+
+    //TXS leaves the flags alone
+    let (_, r) = run(&nrom(), start, &[], &[0x9A]);
+    assert_eq!((r.stack_pointer, r.status), (0x00, UNUSED | IRQ));
+}
+
+#[test]
+fn nop_only_moves_the_pc() {
+    let start = Registers { accumulator: 0x12, reg_x: 0x34, reg_y: 0x56, ..registers() };
+    let (_, r) = run(&nrom(), start, &[], &[0xEA]);
+    assert_eq!(r, Registers { program_counter: ORIGIN + 1, ..start });
+}
+
+#[test]
+fn lax_loads_a_and_x() {
+    let (_, r) = run(&nrom(), registers(), &[(0x0010, 0x80)], &[0xA7, 0x10]);
+    assert_eq!((r.accumulator, r.reg_x), (0x80, 0x80));
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE);
+}
+
+#[test]
+fn sax_stores_a_and_x() {
+    let start = Registers { accumulator: 0xF0, reg_x: 0x3C, ..registers() };
+    let (nes, r) = run(&nrom(), start, &[], &[0x87, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x30);
+    assert_eq!(r.status, start.status);
+}
+
+#[test]
+fn dcp_decrements_then_compares() {
+    let (nes, r) = run(&nrom(), Registers { accumulator: 0x40, ..registers() }, &[(0x0010, 0x41)], &[0xC7, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x40);
+    assert_eq!(r.status, UNUSED | IRQ | ZERO | CARRY);
+}
+
+#[test]
+fn isb_increments_then_subtracts() {
+    let (nes, r) = run(&nrom(), Registers { accumulator: 0x20, status: UNUSED | CARRY, ..registers() }, &[(0x0010, 0x0F)], &[0xE7, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x10);
+    assert_eq!(r.accumulator, 0x10);
+    assert_eq!(r.status, UNUSED | CARRY);
+}
+
+#[test]
+fn slo_shifts_then_ors() {
+    let (nes, r) = run(&nrom(), Registers { accumulator: 0x01, ..registers() }, &[(0x0010, 0x81)], &[0x07, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x02);
+    assert_eq!(r.accumulator, 0x03);
+    assert_eq!(r.status, UNUSED | IRQ | CARRY);
+}
+
+#[test]
+fn rla_rotates_then_ands() {
+    let (nes, r) = run(&nrom(), Registers { accumulator: 0xFF, status: UNUSED | CARRY, ..registers() }, &[(0x0010, 0x80)], &[0x27, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x01);
+    assert_eq!(r.accumulator, 0x01);
+    assert_eq!(r.status, UNUSED | CARRY);
+}
+
+#[test]
+fn sre_shifts_then_xors() {
+    let (nes, r) = run(&nrom(), Registers { accumulator: 0x10, ..registers() }, &[(0x0010, 0x03)], &[0x47, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x01);
+    assert_eq!(r.accumulator, 0x11);
+    assert_eq!(r.status, UNUSED | IRQ | CARRY);
+}
+
+#[test]
+fn rra_rotates_then_adds() {
+    //The bit rotated out is the carry into the add
+    let (nes, r) = run(&nrom(), Registers { accumulator: 0x10, status: UNUSED | CARRY, ..registers() }, &[(0x0010, 0x02)], &[0x67, 0x10]);
+    assert_eq!(peek(&nes, 0x0010), 0x81);
+    assert_eq!(r.accumulator, 0x91);
+    assert_eq!(r.status, UNUSED | NEGATIVE);
+}
+
+#[test]
+fn anc_copies_negative_into_carry() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0xFF, ..registers() }, &[], &[0x0B, 0x80]);
+    assert_eq!(r.accumulator, 0x80);
+    assert_eq!(r.status, UNUSED | IRQ | NEGATIVE | CARRY);
+}
+
+#[test]
+fn alr_ands_then_shifts() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0xFF, ..registers() }, &[], &[0x4B, 0x03]);
+    assert_eq!(r.accumulator, 0x01);
+    assert_eq!(r.status, UNUSED | IRQ | CARRY);
+}
+
+#[test]
+fn arr_takes_carry_and_overflow_from_bits_6_and_5() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0xFF, status: UNUSED | CARRY, ..registers() }, &[], &[0x6B, 0xFF]);
+    assert_eq!(r.accumulator, 0xFF);
+    assert_eq!(r.status, UNUSED | NEGATIVE | CARRY);
+
+    let (_, r) = run(&nrom(), Registers { accumulator: 0x40, status: UNUSED, ..registers() }, &[], &[0x6B, 0xFF]);
+    assert_eq!(r.accumulator, 0x20);
+    assert_eq!(r.status, UNUSED | OVERFLOW);
+}
+
+#[test]
+fn axs_subtracts_from_a_and_x() {
+    let (_, r) = run(&nrom(), Registers { accumulator: 0xF0, reg_x: 0x3F, ..registers() }, &[], &[0xCB, 0x10]);
+    assert_eq!((r.accumulator, r.reg_x), (0xF0, 0x20));
+    assert_eq!(r.status, UNUSED | IRQ | CARRY);
+}
+
+#[test]
+fn unofficial_sbc_matches_the_official_one() {
+    let start = Registers { accumulator: 0x50, status: UNUSED | CARRY, ..registers() };
+    assert_eq!(run(&nrom(), start, &[], &[0xEB, 0xB0]).1, run(&nrom(), start, &[], &[0xE9, 0xB0]).1);
+}
+
+#[test]
+fn lxa_and_ane_use_the_magic_constant() {
+    let (_, r) = run(&nrom(), registers(), &[], &[0xAB, 0xFF]);
+    assert_eq!((r.accumulator, r.reg_x), (0xEE, 0xEE));
+
+    let (_, r) = run(&nrom(), Registers { reg_x: 0xFF, ..registers() }, &[], &[0x8B, 0x0F]);
+    assert_eq!(r.accumulator, 0x0E);
+}
+
+#[test]
+fn las_ands_memory_with_the_stack_pointer() {
+    let (_, r) = run(&nrom(), Registers { stack_pointer: 0xF0, ..registers() }, &[(0x0300, 0x3C)], &[0xBB, 0x00, 0x03]);
+    assert_eq!((r.accumulator, r.reg_x, r.stack_pointer), (0x30, 0x30, 0x30));
+}
+
+#[test]
+fn unstable_stores_and_with_the_high_byte_plus_one() {
+    let start = Registers { accumulator: 0xFF, reg_x: 0xFF, reg_y: 0xFF, ..registers() };
+
+    let (nes, _) = run(&nrom(), Registers { reg_y: 0x01, ..start }, &[], &[0x9E, 0x00, 0x03]);
+    assert_eq!(peek(&nes, 0x0301), 0x04);
+    let (nes, _) = run(&nrom(), Registers { reg_x: 0x02, ..start }, &[], &[0x9C, 0x00, 0x03]);
+    assert_eq!(peek(&nes, 0x0302), 0x04);
+    let (nes, _) = run(&nrom(), Registers { reg_y: 0x00, ..start }, &[], &[0x9F, 0x00, 0x03]);
+    assert_eq!(peek(&nes, 0x0300), 0x04);
+    let (nes, _) = run(&nrom(), Registers { reg_y: 0x00, ..start }, &[(0x0020, 0x00), (0x0021, 0x03)], &[0x93, 0x20]);
+    assert_eq!(peek(&nes, 0x0300), 0x04);
+
+    let (nes, r) = run(&nrom(), Registers { reg_x: 0x0F, reg_y: 0x00, ..start }, &[], &[0x9B, 0x00, 0x03]);
+    assert_eq!(r.stack_pointer, 0x0F);
+    assert_eq!(peek(&nes, 0x0300), 0x04);
+}
+
+#[test]
+fn unstable_stores_corrupt_the_address_on_a_page_cross() {
+    //$02FF + 1 crosses into $03xx, so the value 5 & 3 = 1 becomes the high byte
+    let (nes, _) = run(&nrom(), Registers { reg_x: 0x05, reg_y: 0x01, ..registers() }, &[], &[0x9E, 0xFF, 0x02]);
+    assert_eq!(peek(&nes, 0x0100), 0x01);
+    assert_eq!(peek(&nes, 0x0300), 0x00);
+}
+
+#[test]
+fn kil_opcodes_halt_until_reset() {
+    for &opcode in JAMS.iter() {
+        let mut nes = setup(&nrom(), registers(), &[], &[opcode]);
+        assert!(matches!(nes.step_instruction(), Err(NesError::CpuJam { address: ORIGIN, .. })), "{:02X}", opcode);
+        assert!(nes.is_halted());
+
+        //Stays stuck on the same instruction
+        assert!(matches!(nes.step_instruction(), Err(NesError::CpuJam { address: ORIGIN, .. })));
+        assert_eq!(nes.cpu().registers().program_counter, ORIGIN);
+
+        nes.reset();
+        assert!(!nes.is_halted());
+        nes.step_instruction().unwrap();
+    }
+}
@@ -0,0 +1,207 @@
+//Checks that the CPU's bus accesses land on the right cycles, dummy ones included. Programs are
+//poked into RAM at ORIGIN and run one instruction at a time.
+//Blargg's instr_timing and branch_timing ROMs aren't in this repository, so they're ignored by
+//default. Put them in res/blargg and run cargo test --test cpu_timing -- --ignored
+
+use nes_emulator::Nes;
+use nes_emulator::Bus::Bus;
+use nes_emulator::CPU6502::Registers;
+
+mod common;
+
+use common::{nrom, registers, setup, step, run_blargg, ORIGIN};
+use common::{CARRY, ZERO, IRQ, UNUSED, OVERFLOW, NEGATIVE};
+
+//Cycles for every opcode when no page is crossed and no branch is taken, in rows of 16. 0 is a KIL
+const BASE_CYCLES: [u64; 256] = [
+    7, 6, 0, 8, 3, 3, 5, 5, 3, 2, 2, 2, 4, 4, 6, 6,
+    2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+    6, 6, 0, 8, 3, 3, 5, 5, 4, 2, 2, 2, 4, 4, 6, 6,
+    2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+    6, 6, 0, 8, 3, 3, 5, 5, 3, 2, 2, 2, 3, 4, 6, 6,
+    2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+    6, 6, 0, 8, 3, 3, 5, 5, 4, 2, 2, 2, 5, 4, 6, 6,
+    2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+    2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4,
+    2, 6, 0, 6, 4, 4, 4, 4, 2, 5, 2, 5, 5, 5, 5, 5,
+    2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4,
+    2, 5, 0, 5, 4, 4, 4, 4, 2, 4, 2, 4, 4, 4, 4, 4,
+    2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,
+    2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+    2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,
+    2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7
+];
+
+//An MMC1 image with two 16KiB banks. The first byte of each bank is its number, and $E000 (in the
+//fixed last bank) holds 1
+fn mmc1_rom() -> Vec<u8> {
+    let mut prg = vec![0xEA; 32768];
+    prg[0x0000] = 0;
+    prg[0x4000] = 1;
+    prg[0x6000] = 1;
+    prg[0x7FFC] = 0x00;
+    prg[0x7FFD] = 0xC0;
+
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 2, 1, 0x10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(prg);
+    rom.extend(vec![0; 8192]);
+    rom
+}
+
+#[test]
+fn reset_takes_seven_cycles() {
+    let mut nes = Nes::from_rom_bytes(&mmc1_rom()).unwrap();
+    nes.step_instruction().unwrap();
+    assert_eq!(nes.cpu().total_cycles(), 7);
+    assert_eq!(nes.cpu().registers().program_counter, 0xC000);
+}
+
+#[test]
+fn read_modify_write_writes_the_old_value_first() {
+    //INC $E000 writes 1 then 2 on back to back cycles. MMC1 only takes the first, so that's the
+    //first bit of the PRG bank number. Four more 0 bits select bank 1
+    let mut program = vec![0xEE, 0x00, 0xE0, 0xA9, 0x00];
+    for _ in 0..4 {
+        program.extend(&[0x8D, 0x00, 0xE0]);
+    }
+    let mut nes = setup(&mmc1_rom(), registers(), &[], &program);
+    assert_eq!(nes.cpu().bus().peek(0x8000), 0);
+
+    for _ in 0..6 {
+        nes.step_instruction().unwrap();
+    }
+    assert_eq!(nes.cpu().bus().peek(0x8000), 1);
+}
+
+#[test]
+fn every_opcode_takes_its_base_cycles() {
+    for opcode in 0..=255u8 {
+        let cycles = BASE_CYCLES[opcode as usize];
+        if cycles == 0 {
+            continue;
+        }
+        //The flag that stops each of BPL, BVC, BCC and BNE being taken. The other branches aren't
+        //taken with it clear
+        let flag = match opcode {
+            0x10 => NEGATIVE,
+            0x50 => OVERFLOW,
+            0x90 => CARRY,
+            0xD0 => ZERO,
+            _ => 0
+        };
+        //Absolute operands and the pointer at $00 are both $0300, and X and Y are 0, so nothing crosses a page
+        let mut nes = setup(&nrom(), Registers { status: UNUSED | IRQ | flag, ..registers() }, &[(0x0000, 0x00), (0x0001, 0x03)], &[opcode, 0x00, 0x03]);
+        assert_eq!(step(&mut nes), cycles, "cycles for {:02X}", opcode);
+    }
+}
+
+#[test]
+fn indexed_reads_only_pay_for_page_crossings() {
+    //(program, X and Y, cycles)
+    let cases: [(&[u8], u8, u64); 8] = [
+        //LDA $0300,X
+        (&[0xBD, 0x00, 0x03], 0x01, 4), (&[0xBD, 0x00, 0x03], 0xFF, 4), (&[0xBD, 0x01, 0x03], 0xFF, 5),
+        //LDA $0300,Y
+        (&[0xB9, 0x01, 0x03], 0xFE, 4), (&[0xB9, 0x01, 0x03], 0xFF, 5),
+        //LDA ($10),Y with $10 pointing at $0380
+        (&[0xB1, 0x10], 0x7F, 5), (&[0xB1, 0x10], 0x80, 6),
+        //NOP $0300,X reads too
+        (&[0x1C, 0x01, 0x03], 0xFF, 5)
+    ];
+    for &(program, index, cycles) in cases.iter() {
+        let mut nes = setup(&nrom(), Registers { reg_x: index, reg_y: index, ..registers() }, &[(0x0010, 0x80), (0x0011, 0x03)], program);
+        assert_eq!(step(&mut nes), cycles, "{:02X?} indexed by {:02X}", program, index);
+    }
+}
+
+#[test]
+fn indexed_stores_and_read_modify_writes_always_take_the_extra_cycle() {
+    //(program, cycles)
+    let cases: [(&[u8], u64); 6] = [
+        //STA $0300,X
+        (&[0x9D, 0x00, 0x03], 5),
+        //STA $0300,Y
+        (&[0x99, 0x00, 0x03], 5),
+        //STA ($10),Y
+        (&[0x91, 0x10], 6),
+        //INC $0300,X
+        (&[0xFE, 0x00, 0x03], 7),
+        //DCP $0300,Y
+        (&[0xDB, 0x00, 0x03], 7),
+        //SLO ($10),Y
+        (&[0x13, 0x10], 8)
+    ];
+    for &(program, cycles) in cases.iter() {
+        for &index in [0x00, 0xFF].iter() {
+            let mut nes = setup(&nrom(), Registers { reg_x: index, reg_y: index, ..registers() }, &[(0x0010, 0x80), (0x0011, 0x03)], program);
+            assert_eq!(step(&mut nes), cycles, "{:02X?} indexed by {:02X}", program, index);
+        }
+    }
+}
+
+#[test]
+fn branches_pay_for_being_taken_and_crossing_pages() {
+    //(where the BNE is, offset, Z, cycles, where it ends up)
+    let cases = [
+        (0x0600, 0x10, ZERO, 2, 0x0602),
+        (0x0600, 0x10, 0, 3, 0x0612),
+        (0x0600, 0xFE, 0, 3, 0x0600),
+        //Forwards off the end of the page
+        (0x06F0, 0x10, 0, 4, 0x0702),
+        //The next instruction is on the next page but the branch isn't taken
+        (0x06FE, 0x10, ZERO, 2, 0x0700),
+        //Taken from the next page back to this one
+        (0x06FE, 0xFC, 0, 4, 0x06FC)
+    ];
+    for &(origin, offset, zero, cycles, target) in cases.iter() {
+        let mut nes = setup(&nrom(), Registers { program_counter: origin, status: UNUSED | zero, ..registers() }, &[], &[0xD0, offset]);
+        assert_eq!((step(&mut nes), nes.cpu().registers().program_counter), (cycles, target),
+            "BNE {:02X} at {:04X}", offset, origin);
+    }
+}
+
+#[test]
+fn sprite_dma_halts_the_cpu_for_513_or_514_cycles() {
+    //LDA #$02, STA $4014 then a NOP that gets held up. LDA $10 first puts the DMA on the other
+    //parity, where it needs a cycle to line up
+    let programs: [&[u8]; 2] = [
+        &[0xA9, 0x02, 0x8D, 0x14, 0x40, 0xEA],
+        &[0xA5, 0x10, 0xA9, 0x02, 0x8D, 0x14, 0x40, 0xEA]
+    ];
+    let mut cycles = Vec::new();
+    for program in programs.iter() {
+        let mut nes = setup(&nrom(), registers(), &[], program);
+        for i in 0..256 {
+            nes.cpu_mut().bus_mut().write(0x0200 + i, i as u8);
+        }
+        while nes.cpu().registers().program_counter != ORIGIN + program.len() as u16 - 1 {
+            nes.step_instruction().unwrap();
+        }
+        cycles.push(step(&mut nes));
+
+        //The attribute bytes lose bits 2-4 on the way in
+        let bus = nes.cpu_mut().bus_mut();
+        for i in 0..=255u8 {
+            bus.write(0x2003, i);
+            assert_eq!(bus.read(0x2004), if i & 0x03 == 0x02 {i & 0b11100011} else {i});
+        }
+    }
+    cycles.sort();
+    assert_eq!(cycles, [2 + 513, 2 + 514]);
+}
+
+#[test]
+#[ignore = "needs blargg's instr_timing.nes in res/blargg"]
+fn blargg_instr_timing() {
+    let (status, message) = run_blargg("instr_timing.nes");
+    assert_eq!(status, 0, "{}", message);
+}
+
+#[test]
+#[ignore = "needs blargg's ROMs in res/blargg/branch_timing"]
+fn blargg_branch_timing() {
+    for name in ["1.Branch_Basics.nes", "2.Backward_Branch.nes", "3.Forward_Branch.nes"].iter() {
+        let (status, message) = run_blargg(&format!("branch_timing/{}", name));
+        assert_eq!(status, 0, "{}: {}", name, message);
+    }
+}
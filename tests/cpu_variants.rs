@@ -0,0 +1,227 @@
+//Checks the differences between the 2A03, the NMOS 6502 and the 65C02. These run on flat RAM
+//rather than a NES. Programs are poked in at ORIGIN and run one instruction at a time
+
+use nes_emulator::Bus::Bus;
+use nes_emulator::CPU6502::{Registers, Variant};
+
+mod common;
+
+use common::{registers, ORIGIN, IRQ_HANDLER};
+use common::{CARRY, ZERO, IRQ, DECIMAL, UNUSED, OVERFLOW, NEGATIVE};
+use common::flat::{setup, step, run};
+
+#[test]
+fn the_2a03_ignores_decimal_mode() {
+    let (_, r) = run(Variant::Ricoh2A03, Registers { accumulator: 0x09, status: UNUSED | DECIMAL, ..registers() }, &[], &[0x69, 0x01]);
+    assert_eq!(r.accumulator, 0x0A);
+
+    let (_, r) = run(Variant::Ricoh2A03, Registers { accumulator: 0x10, status: UNUSED | DECIMAL | CARRY, ..registers() }, &[], &[0xE9, 0x01]);
+    assert_eq!(r.accumulator, 0x0F);
+}
+
+#[test]
+fn nmos_adc_and_sbc_work_in_bcd() {
+    //(A, operand, carry in, A out, carry out)
+    let adds = [(0x09, 0x01, 0, 0x10, 0), (0x15, 0x27, 1, 0x43, 0), (0x58, 0x46, 1, 0x05, CARRY), (0x99, 0x01, 0, 0x00, CARRY)];
+    for &(a, data, carry, result, carry_out) in adds.iter() {
+        let (_, r) = run(Variant::Nmos6502, Registers { accumulator: a, status: UNUSED | DECIMAL | carry, ..registers() }, &[], &[0x69, data]);
+        assert_eq!((r.accumulator, r.status & CARRY), (result, carry_out), "{:02X} + {:02X} + {}", a, data, carry);
+    }
+
+    //(A, operand, carry in, A out, carry out). Carry clear means borrow
+    let subtracts = [(0x10, 0x01, CARRY, 0x09, CARRY), (0x46, 0x12, CARRY, 0x34, CARRY), (0x40, 0x13, 0, 0x26, CARRY), (0x12, 0x21, CARRY, 0x91, 0)];
+    for &(a, data, carry, result, carry_out) in subtracts.iter() {
+        let (_, r) = run(Variant::Nmos6502, Registers { accumulator: a, status: UNUSED | DECIMAL | carry, ..registers() }, &[], &[0xE9, data]);
+        assert_eq!((r.accumulator, r.status & CARRY), (result, carry_out), "{:02X} - {:02X} - {}", a, data, carry);
+    }
+}
+
+#[test]
+fn nmos_decimal_flags_come_from_the_wrong_places() {
+    //Z comes from the binary sum $9A, and N from the high digit before it's adjusted
+    let (_, r) = run(Variant::Nmos6502, Registers { accumulator: 0x99, status: UNUSED | DECIMAL, ..registers() }, &[], &[0x69, 0x01]);
+    assert_eq!(r.accumulator, 0x00);
+    assert_eq!(r.status, UNUSED | DECIMAL | CARRY | NEGATIVE);
+
+    //The 65C02 sets them from the result
+    let (_, r) = run(Variant::Wdc65C02, Registers { accumulator: 0x99, status: UNUSED | DECIMAL, ..registers() }, &[], &[0x69, 0x01]);
+    assert_eq!(r.accumulator, 0x00);
+    assert_eq!(r.status, UNUSED | DECIMAL | CARRY | ZERO);
+}
+
+#[test]
+fn nmos_decimal_overflow() {
+    //$79 + $10 = $89, which overflows as signed
+    let (_, r) = run(Variant::Nmos6502, Registers { accumulator: 0x79, status: UNUSED | DECIMAL, ..registers() }, &[], &[0x69, 0x10]);
+    assert_eq!(r.accumulator, 0x89);
+    assert_eq!(r.status, UNUSED | DECIMAL | OVERFLOW | NEGATIVE);
+}
+
+#[test]
+fn unofficial_opcodes_use_decimal_mode_on_the_nmos_6502() {
+    //ISB $10 with $10 holding $00 subtracts 1
+    let (cpu, r) = run(Variant::Nmos6502, Registers { accumulator: 0x10, status: UNUSED | DECIMAL | CARRY, ..registers() }, &[(0x0010, 0x00)], &[0xE7, 0x10]);
+    assert_eq!(cpu.bus().peek(0x0010), 0x01);
+    assert_eq!(r.accumulator, 0x09);
+}
+
+#[test]
+fn nmos_arr_fixes_up_each_digit_in_decimal_mode() {
+    //(A, operand, carry in, A out, flags out). N, Z and V come from the rotate before the fixup
+    let cases = [
+        (0xFF, 0xFF, CARRY, 0x55, CARRY | NEGATIVE),
+        (0x0F, 0x0F, 0, 0x0D, 0),
+        (0x50, 0xFF, 0, 0x88, CARRY | OVERFLOW),
+        (0x01, 0x03, 0, 0x00, ZERO)
+    ];
+    for &(a, data, carry, result, flags) in cases.iter() {
+        let (_, r) = run(Variant::Nmos6502, Registers { accumulator: a, status: UNUSED | DECIMAL | carry, ..registers() }, &[], &[0x6B, data]);
+        assert_eq!((r.accumulator, r.status), (result, UNUSED | DECIMAL | flags), "ARR {:02X} with A {:02X}", data, a);
+    }
+
+    //The 2A03 rotates and that's all
+    let (_, r) = run(Variant::Ricoh2A03, Registers { accumulator: 0x50, status: UNUSED | DECIMAL, ..registers() }, &[], &[0x6B, 0xFF]);
+    assert_eq!((r.accumulator, r.status), (0x28, UNUSED | DECIMAL | OVERFLOW));
+}
+
+#[test]
+fn c02_decimal_arithmetic_takes_an_extra_cycle() {
+    let mut cpu = setup(Variant::Wdc65C02, Registers { accumulator: 0x09, status: UNUSED | DECIMAL, ..registers() }, &[], &[0x69, 0x01, 0xD8, 0x69, 0x01]);
+    assert_eq!(step(&mut cpu), 3);
+    assert_eq!(cpu.registers().accumulator, 0x10);
+    step(&mut cpu);
+    assert_eq!(step(&mut cpu), 2);
+    assert_eq!(cpu.registers().accumulator, 0x11);
+}
+
+#[test]
+fn c02_jmp_indirect_crosses_pages() {
+    let memory = [(0x02FF, 0x34), (0x0200, 0x12), (0x0300, 0x56)];
+    let mut cpu = setup(Variant::Nmos6502, registers(), &memory, &[0x6C, 0xFF, 0x02]);
+    assert_eq!(step(&mut cpu), 5);
+    assert_eq!(cpu.registers().program_counter, 0x1234);
+
+    let mut cpu = setup(Variant::Wdc65C02, registers(), &memory, &[0x6C, 0xFF, 0x02]);
+    assert_eq!(step(&mut cpu), 6);
+    assert_eq!(cpu.registers().program_counter, 0x5634);
+}
+
+#[test]
+fn c02_jmp_indexed_indirect() {
+    let memory = [(0x0304, 0x34), (0x0305, 0x12)];
+    let mut cpu = setup(Variant::Wdc65C02, Registers { reg_x: 0x04, ..registers() }, &memory, &[0x7C, 0x00, 0x03]);
+    assert_eq!(step(&mut cpu), 6);
+    assert_eq!(cpu.registers().program_counter, 0x1234);
+}
+
+#[test]
+fn c02_zero_page_indirect() {
+    let memory = [(0x0010, 0x00), (0x0011, 0x03), (0x0300, 0x42)];
+    let (_, r) = run(Variant::Wdc65C02, registers(), &memory, &[0xB2, 0x10]);
+    assert_eq!(r.accumulator, 0x42);
+
+    let (cpu, _) = run(Variant::Wdc65C02, Registers { accumulator: 0x24, ..registers() }, &memory, &[0x92, 0x10]);
+    assert_eq!(cpu.bus().peek(0x0300), 0x24);
+}
+
+#[test]
+fn c02_stz_and_the_new_stack_instructions() {
+    let (cpu, _) = run(Variant::Wdc65C02, Registers { accumulator: 0xFF, ..registers() }, &[(0x0010, 0x42)], &[0x64, 0x10]);
+    assert_eq!(cpu.bus().peek(0x0010), 0x00);
+
+    //PHX then PLY
+    let mut cpu = setup(Variant::Wdc65C02, Registers { reg_x: 0x80, ..registers() }, &[], &[0xDA, 0x7A]);
+    assert_eq!(step(&mut cpu), 3);
+    assert_eq!(step(&mut cpu), 4);
+    let r = cpu.registers();
+    assert_eq!((r.reg_y, r.stack_pointer, r.status), (0x80, 0xFD, UNUSED | IRQ | NEGATIVE));
+}
+
+#[test]
+fn c02_tsb_and_trb() {
+    let (cpu, r) = run(Variant::Wdc65C02, Registers { accumulator: 0x0F, ..registers() }, &[(0x0010, 0xF0)], &[0x04, 0x10]);
+    assert_eq!(cpu.bus().peek(0x0010), 0xFF);
+    assert_eq!(r.status & ZERO, ZERO);
+
+    let (cpu, r) = run(Variant::Wdc65C02, Registers { accumulator: 0x0F, ..registers() }, &[(0x0010, 0xFF)], &[0x14, 0x10]);
+    assert_eq!(cpu.bus().peek(0x0010), 0xF0);
+    assert_eq!(r.status & ZERO, 0);
+}
+
+#[test]
+fn c02_bit_instructions() {
+    //SMB5 then RMB0
+    let mut cpu = setup(Variant::Wdc65C02, registers(), &[(0x0010, 0x01)], &[0xD7, 0x10, 0x07, 0x10]);
+    step(&mut cpu);
+    assert_eq!(cpu.bus().peek(0x0010), 0x21);
+    step(&mut cpu);
+    assert_eq!(cpu.bus().peek(0x0010), 0x20);
+
+    //BBS5 is taken, BBR5 isn't
+    let mut cpu = setup(Variant::Wdc65C02, registers(), &[(0x0010, 0x20)], &[0xDF, 0x10, 0x10]);
+    assert_eq!(step(&mut cpu), 6);
+    assert_eq!(cpu.registers().program_counter, ORIGIN + 0x13);
+
+    let mut cpu = setup(Variant::Wdc65C02, registers(), &[(0x0010, 0x20)], &[0x5F, 0x10, 0x10]);
+    assert_eq!(step(&mut cpu), 5);
+    assert_eq!(cpu.registers().program_counter, ORIGIN + 3);
+}
+
+#[test]
+fn c02_bra_inc_a_and_bit_immediate() {
+    let mut cpu = setup(Variant::Wdc65C02, registers(), &[], &[0x80, 0x10]);
+    assert_eq!(step(&mut cpu), 3);
+    assert_eq!(cpu.registers().program_counter, ORIGIN + 0x12);
+
+    let (_, r) = run(Variant::Wdc65C02, Registers { accumulator: 0x7F, ..registers() }, &[], &[0x1A]);
+    assert_eq!((r.accumulator, r.status), (0x80, UNUSED | IRQ | NEGATIVE));
+
+    //Only Z changes
+    let (_, r) = run(Variant::Wdc65C02, Registers { accumulator: 0x0F, ..registers() }, &[], &[0x89, 0xF0]);
+    assert_eq!(r.status, UNUSED | IRQ | ZERO);
+}
+
+#[test]
+fn c02_brk_clears_decimal_mode() {
+    let (_, r) = run(Variant::Wdc65C02, Registers { status: UNUSED | DECIMAL, ..registers() }, &[], &[0x00, 0x00]);
+    assert_eq!(r.program_counter, IRQ_HANDLER);
+    assert_eq!(r.status, UNUSED | IRQ);
+
+    let (_, r) = run(Variant::Nmos6502, Registers { status: UNUSED | DECIMAL, ..registers() }, &[], &[0x00, 0x00]);
+    assert_eq!(r.status, UNUSED | IRQ | DECIMAL);
+}
+
+#[test]
+fn c02_undocumented_opcodes_are_nops() {
+    //LAX $10 on the NMOS chips
+    let (_, r) = run(Variant::Wdc65C02, registers(), &[(0x0010, 0x42)], &[0xA3, 0x10]);
+    assert_eq!((r.accumulator, r.reg_x, r.program_counter), (0, 0, ORIGIN + 1));
+
+    //A KIL on the NMOS chips
+    let (_, r) = run(Variant::Wdc65C02, registers(), &[], &[0x02, 0x10]);
+    assert_eq!(r.program_counter, ORIGIN + 2);
+}
+
+#[test]
+fn c02_stp_halts_until_reset() {
+    let mut cpu = setup(Variant::Wdc65C02, registers(), &[], &[0xDB]);
+    assert!(cpu.step().is_err());
+    assert!(cpu.is_halted());
+    cpu.reset();
+    cpu.step().unwrap();
+    assert!(!cpu.is_halted());
+}
+
+#[test]
+fn every_c02_opcode_runs() {
+    //Each instruction checks its own cycle count against the table in debug builds
+    for opcode in 0..=0xFF {
+        if opcode == 0xDB {
+            continue;
+        }
+        for &status in [UNUSED | IRQ, UNUSED | IRQ | DECIMAL | CARRY | ZERO | NEGATIVE | OVERFLOW].iter() {
+            let mut cpu = setup(Variant::Wdc65C02, Registers { reg_x: 0xFF, reg_y: 0xFF, status, ..registers() }, &[], &[opcode, 0xFF, 0x02]);
+            cpu.step().unwrap_or_else(|e| panic!("{:02X}: {}", opcode, e));
+        }
+    }
+}
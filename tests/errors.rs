@@ -0,0 +1,92 @@
+//Each way loading a ROM or running the CPU can fail
+
+use nes_emulator::{Nes, NesError};
+
+mod common;
+
+use common::{nrom, registers, setup, ORIGIN};
+
+//An iNES image with the sizes the header claims, cut down to length bytes if that's given
+fn image(mapper: u8, prg_banks: u8, chr_banks: u8, length: Option<usize>) -> Vec<u8> {
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, prg_banks, chr_banks, mapper << 4, mapper & 0xF0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(vec![0xEA; prg_banks as usize * 16384 + chr_banks as usize * 8192]);
+    if let Some(length) = length {
+        rom.truncate(length);
+    }
+    rom
+}
+
+fn load(rom: &[u8]) -> NesError {
+    match Nes::from_rom_bytes(rom) {
+        Ok(_) => panic!("loaded a bad image"),
+        Err(e) => e
+    }
+}
+
+#[test]
+fn bad_magic() {
+    let mut rom = image(0, 1, 1, None);
+    rom[3] = 0x00;
+    assert!(matches!(load(&rom), NesError::BadMagic));
+    //Too short to even have the magic number
+    assert!(matches!(load(b"NES"), NesError::BadMagic));
+    assert_eq!(NesError::BadMagic.to_string(), "not an iNES image");
+}
+
+#[test]
+fn truncated_header() {
+    assert!(matches!(load(&image(0, 1, 1, Some(10))), NesError::TruncatedFile { expected: 16, actual: 10 }));
+}
+
+#[test]
+fn truncated_prg_rom() {
+    let full = 16 + 2 * 16384 + 8192;
+    let e = load(&image(0, 2, 1, Some(16 + 16384)));
+    assert!(matches!(e, NesError::TruncatedFile { expected, actual: 16400 } if expected == full), "{:?}", e);
+    assert_eq!(e.to_string(), format!("ROM image is truncated: expected {} bytes but got 16400", full));
+}
+
+#[test]
+fn truncated_chr_rom() {
+    let full = 16 + 16384 + 8192;
+    let e = load(&image(0, 1, 1, Some(full - 1)));
+    assert!(matches!(e, NesError::TruncatedFile { expected, actual } if expected == full && actual == full - 1), "{:?}", e);
+}
+
+#[test]
+fn unsupported_mapper() {
+    let e = load(&image(5, 1, 1, None));
+    assert!(matches!(e, NesError::UnsupportedMapper(5)), "{:?}", e);
+    assert_eq!(e.to_string(), "mapper 5 isn't supported");
+}
+
+#[test]
+fn oversized_image() {
+    //NROM can only see 32KiB of PRG
+    let e = load(&image(0, 4, 1, None));
+    assert!(matches!(e, NesError::OversizedImage { size } if size == 4 * 16384 + 8192), "{:?}", e);
+    //That gets reported before checking the file is all there
+    let e = load(&image(0, 4, 1, Some(16)));
+    assert!(matches!(e, NesError::OversizedImage { .. }), "{:?}", e);
+    //And it's per mapper, since UxROM can bank switch its way through it
+    assert!(Nes::from_rom_bytes(&image(2, 4, 1, None)).is_ok());
+}
+
+#[test]
+fn missing_file() {
+    let e = match Nes::from_rom_file("res/no_such_rom.nes") {
+        Ok(_) => panic!("loaded a file that isn't there"),
+        Err(e) => e
+    };
+    assert!(matches!(e, NesError::Io(_)), "{:?}", e);
+}
+
+#[test]
+fn cpu_jam() {
+    //KIL
+    let mut nes = setup(&nrom(), registers(), &[], &[0x02]);
+    let e = nes.step_instruction().unwrap_err();
+    assert!(matches!(e, NesError::CpuJam { opcode: 0x02, address: ORIGIN }), "{:?}", e);
+    assert_eq!(e.to_string(), "CPU jammed on opcode 02 at 0600");
+    assert!(nes.is_halted());
+}
@@ -0,0 +1,86 @@
+//Klaus Dormann's 6502 test suite (github.com/Klaus2m5/6502_65C02_functional_tests) on flat RAM.
+//Each test spins on a JMP * or branch to itself when it's done, at a known address for success and
+//anywhere else for the check that failed. Look the trap address up in the test's listing.
+//The binaries aren't in this repository, so these are ignored by default. Put them in res/klaus and
+//run cargo test --release --test klaus_dormann -- --ignored
+
+use std::fs;
+
+use nes_emulator::Bus::{Bus, FlatRAM};
+use nes_emulator::CPU6502::{CPU6502, Variant};
+
+const DIRECTORY: &str = "res/klaus";
+//Where the prebuilt images start running
+const START: u16 = 0x0400;
+//Where the prebuilt 6502_functional_test.bin ends up when everything passes
+const FUNCTIONAL_SUCCESS: u16 = 0x3469;
+//The same for 65C02_extended_opcodes_test.bin, built with the Rockwell and WDC opcodes enabled
+const EXTENDED_SUCCESS: u16 = 0x24F1;
+//The decimal test has to be assembled by hand. It runs from $0200 and leaves 0 in ERROR if it passed
+const DECIMAL_START: u16 = 0x0200;
+const DECIMAL_ERROR: u16 = 0x000B;
+//The functional test takes about 30 million instructions
+const MAX_INSTRUCTIONS: u64 = 100_000_000;
+
+//Loads a binary into RAM at origin
+fn load(name: &str, origin: u16, variant: Variant) -> CPU6502<FlatRAM> {
+    let path = format!("{}/{}", DIRECTORY, name);
+    let image = fs::read(&path).unwrap_or_else(|e| panic!("no test at {}: {}", path, e));
+    assert!(origin as usize + image.len() <= 0x10000, "{} doesn't fit at ${:04X}", path, origin);
+
+    let mut ram = FlatRAM::new();
+    ram.load(origin, &image);
+    let mut cpu = CPU6502::with_variant(ram, variant);
+    //Get through the reset sequence first
+    cpu.step().unwrap();
+    cpu
+}
+
+//Runs from start until the PC stops moving and returns where it stopped
+fn run_until_trapped(cpu: &mut CPU6502<FlatRAM>, start: u16) -> u16 {
+    cpu.set_program_counter(start);
+    for _ in 0..MAX_INSTRUCTIONS {
+        let pc = cpu.registers().program_counter;
+        //Some builds finish on a STP instead
+        if let Err(e) = cpu.step() {
+            assert!(cpu.is_halted(), "{}", e);
+            return pc;
+        }
+        if cpu.registers().program_counter == pc {
+            return pc;
+        }
+    }
+    panic!("still running after {} instructions, at ${:04X}", MAX_INSTRUCTIONS, cpu.registers().program_counter);
+}
+
+fn report(cpu: &CPU6502<FlatRAM>, trap: u16) -> String {
+    let r = cpu.registers();
+    format!("trapped at ${:04X} after {} cycles with A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+        trap, cpu.total_cycles(), r.accumulator, r.reg_x, r.reg_y, r.status, r.stack_pointer)
+}
+
+#[test]
+#[ignore = "needs the binaries in res/klaus"]
+fn functional_test() {
+    let mut cpu = load("6502_functional_test.bin", 0x0000, Variant::Nmos6502);
+    let trap = run_until_trapped(&mut cpu, START);
+    assert_eq!(trap, FUNCTIONAL_SUCCESS, "{}", report(&cpu, trap));
+}
+
+#[test]
+#[ignore = "needs the binaries in res/klaus"]
+fn extended_opcodes_test() {
+    let mut cpu = load("65C02_extended_opcodes_test.bin", 0x0000, Variant::Wdc65C02);
+    let trap = run_until_trapped(&mut cpu, START);
+    assert_eq!(trap, EXTENDED_SUCCESS, "{}", report(&cpu, trap));
+}
+
+#[test]
+#[ignore = "needs the binaries in res/klaus"]
+fn decimal_test() {
+    for &(variant, name) in [(Variant::Nmos6502, "6502_decimal_test.bin"), (Variant::Wdc65C02, "65C02_decimal_test.bin")].iter() {
+        let mut cpu = load(name, DECIMAL_START, variant);
+        let trap = run_until_trapped(&mut cpu, DECIMAL_START);
+        assert_eq!(cpu.bus().peek(DECIMAL_ERROR), 0, "{}: {}", name, report(&cpu, trap));
+    }
+}
@@ -0,0 +1,472 @@
+//Bank switching and the other board features, driven straight through the cartridge
+
+use nes_emulator::Cartridge::Cartridge;
+use nes_emulator::CartridgeHeader::{CartridgeHeader, Mirroring};
+use nes_emulator::Mapper::{Mapper, Mapper0, MappedAddress};
+
+//A NES 2.0 image. Every PRG ROM byte holds which 4KiB of the ROM it's in and every CHR ROM byte which 1KiB,
+//so reading an address shows what's mapped there. RAM sizes are NES 2.0 shifts, 0 for none
+fn image(mapper: u8, submapper: u8, prg_banks: u8, chr_banks: u8, prg_ram_shift: u8, chr_ram_shift: u8) -> Vec<u8> {
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, prg_banks, chr_banks, mapper << 4, (mapper & 0xF0) | 0x08,
+        submapper << 4, 0, prg_ram_shift, chr_ram_shift, 0, 0, 0, 0];
+    rom.extend((0..prg_banks as usize * 16384).map(|i| (i / 0x1000) as u8));
+    rom.extend((0..chr_banks as usize * 8192).map(|i| (i / 0x400) as u8));
+    rom
+}
+
+fn cartridge(rom: &[u8]) -> Cartridge {
+    Cartridge::from_bytes(rom).unwrap()
+}
+
+//Leaves a couple of cycles since the last write so MMC1 doesn't take it for the second half of a read-modify-write
+fn write(cart: &mut Cartridge, address: u16, data: u8) {
+    cart.clock();
+    cart.clock();
+    cart.write(address, data);
+}
+
+//Which bank of this size the CPU sees at address
+fn prg_bank(cart: &mut Cartridge, address: u16, size: usize) -> usize {
+    cart.read(address).unwrap() as usize * 0x1000 / size
+}
+
+//Which bank of this size the PPU sees at address
+fn chr_bank(cart: &mut Cartridge, address: u16, size: usize) -> usize {
+    cart.ppu_read(address) as usize * 0x400 / size
+}
+
+//MMC1 registers are loaded a bit at a time, low bit first
+fn mmc1_write(cart: &mut Cartridge, address: u16, value: u8) {
+    for i in 0..5 {
+        write(cart, address, (value >> i) & 1);
+    }
+}
+
+//Only what every mapper has to provide, to see what the rest defaults to
+struct Bare;
+
+impl Mapper for Bare {
+    fn map_cpu_address(&self, _address: u16) -> MappedAddress {
+        MappedAddress::None
+    }
+
+    fn map_ppu_address(&self, address: u16) -> usize {
+        address as usize
+    }
+
+    fn cpu_write(&mut self, _address: u16, _data: u8) -> MappedAddress {
+        MappedAddress::None
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::Vertical
+    }
+}
+
+#[test]
+fn mapper_defaults_are_a_board_with_no_irq_ram_or_bus_conflicts() {
+    let mut mapper = Bare;
+    mapper.ppu_write(0x0000, 0xFF);
+    mapper.ppu_a12_rise();
+    mapper.ppu_a12_fall();
+    mapper.cpu_clock();
+    assert!(!mapper.irq());
+    assert!(!mapper.has_bus_conflicts());
+    assert_eq!((mapper.prg_ram_size(), mapper.has_battery()), (0, false));
+}
+
+#[test]
+fn mapper0_has_no_registers_so_only_ram_writes_land() {
+    let header = CartridgeHeader::parse(&image(0, 0, 1, 1, 7, 0)).unwrap();
+    let mut mapper = Mapper0::new(&header);
+    assert_eq!((mapper.prg_ram_size(), mapper.has_battery()), (8192, false));
+    //8KiB of RAM mirrored through $6000-$7FFF
+    assert_eq!(mapper.cpu_write(0x6000, 0x12), MappedAddress::PrgRam(0));
+    assert_eq!(mapper.cpu_write(0x7FFF, 0x12), MappedAddress::PrgRam(0x1FFF));
+    //ROM, and nothing below $6000
+    for &address in [0x4020, 0x5FFF, 0x8000, 0xFFFF].iter() {
+        assert_eq!(mapper.cpu_write(address, 0x12), MappedAddress::None, "{:04X}", address);
+    }
+    //Which changed nothing, and one 16KiB bank shows up twice
+    assert_eq!(mapper.map_cpu_address(0x8000), MappedAddress::PrgRom(0));
+    assert_eq!(mapper.map_cpu_address(0xC000), MappedAddress::PrgRom(0));
+    assert_eq!(mapper.map_cpu_address(0xFFFF), MappedAddress::PrgRom(0x3FFF));
+    assert_eq!(mapper.map_cpu_address(0x5000), MappedAddress::None);
+
+    //Battery backed, with 32KiB of ROM that isn't mirrored
+    let header = CartridgeHeader::parse(&image(0, 0, 2, 1, 7 << 4, 0)).unwrap();
+    let mapper = Mapper0::new(&header);
+    assert_eq!((mapper.prg_ram_size(), mapper.has_battery()), (8192, true));
+    assert_eq!(mapper.map_cpu_address(0xC000), MappedAddress::PrgRom(0x4000));
+}
+
+#[test]
+fn mapper0_mirroring_is_soldered_to_what_the_header_says() {
+    //(flags 6, mirroring)
+    let cases = [(0b0000, Mirroring::Horizontal), (0b0001, Mirroring::Vertical), (0b1000, Mirroring::FourScreen)];
+    for &(flags6, mirroring) in cases.iter() {
+        let mut rom = image(0, 0, 1, 1, 0, 0);
+        rom[6] |= flags6;
+        let mut mapper = Mapper0::new(&CartridgeHeader::parse(&rom).unwrap());
+        assert_eq!(mapper.mirroring(), mirroring);
+        //Writes can't change it
+        mapper.cpu_write(0x8000, 0xFF);
+        assert_eq!(mapper.mirroring(), mirroring);
+    }
+}
+
+#[test]
+fn mmc1_registers_are_loaded_by_5_serial_writes() {
+    let mut cart = cartridge(&image(1, 0, 8, 1, 0, 0));
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (0, 7));
+
+    //Nothing happens until the 5th write
+    for bit in [1, 1, 0, 0].iter() {
+        write(&mut cart, 0xE000, *bit);
+        assert_eq!(prg_bank(&mut cart, 0x8000, 16384), 0);
+    }
+    write(&mut cart, 0xE000, 0);
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (3, 7));
+}
+
+#[test]
+fn mmc1_writing_bit_7_resets_the_shift_register_and_fixes_the_last_bank() {
+    let mut cart = cartridge(&image(1, 0, 8, 1, 0, 0));
+    mmc1_write(&mut cart, 0xE000, 3);
+    //First bank fixed at $8000
+    mmc1_write(&mut cart, 0x8000, 0x08);
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (0, 3));
+
+    //Half a load gets thrown away
+    write(&mut cart, 0xE000, 1);
+    write(&mut cart, 0xE000, 1);
+    write(&mut cart, 0x8000, 0x80);
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (3, 7));
+    mmc1_write(&mut cart, 0xE000, 5);
+    assert_eq!(prg_bank(&mut cart, 0x8000, 16384), 5);
+}
+
+#[test]
+fn mmc1_ignores_a_write_on_the_cycle_after_another() {
+    let mut cart = cartridge(&image(1, 0, 8, 1, 0, 0));
+    //Like INC $E000, which writes the old value back and then the new one
+    write(&mut cart, 0xE000, 1);
+    cart.clock();
+    cart.write(0xE000, 0x80);
+    //Or two writes in the same cycle
+    cart.write(0xE000, 0x80);
+    for _ in 0..4 {
+        write(&mut cart, 0xE000, 0);
+    }
+    assert_eq!(prg_bank(&mut cart, 0x8000, 16384), 1);
+}
+
+#[test]
+fn mmc1_prg_modes() {
+    //(control, bank at $8000, bank at $C000) with bank 5 selected
+    let cases = [
+        //32KiB, which ignores the low bit
+        (0x00, 4, 5),
+        (0x04, 4, 5),
+        //First bank fixed
+        (0x08, 0, 5),
+        //Last bank fixed
+        (0x0C, 5, 7)
+    ];
+    for &(control, low, high) in cases.iter() {
+        let mut cart = cartridge(&image(1, 0, 8, 1, 0, 0));
+        mmc1_write(&mut cart, 0xE000, 5);
+        mmc1_write(&mut cart, 0x8000, control);
+        assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (low, high), "control {:02X}", control);
+    }
+}
+
+#[test]
+fn mmc1_chr_is_one_8k_bank_or_two_4k_banks() {
+    let mut cart = cartridge(&image(1, 0, 2, 16, 0, 0));
+    mmc1_write(&mut cart, 0xA000, 5);
+    mmc1_write(&mut cart, 0xC000, 10);
+
+    //8KiB mode ignores the low bit and the second register
+    assert_eq!((chr_bank(&mut cart, 0x0000, 4096), chr_bank(&mut cart, 0x1000, 4096)), (4, 5));
+    mmc1_write(&mut cart, 0x8000, 0x1C);
+    assert_eq!((chr_bank(&mut cart, 0x0000, 4096), chr_bank(&mut cart, 0x1000, 4096)), (5, 10));
+}
+
+#[test]
+fn snrom_disables_prg_ram_with_chr_bit_4() {
+    //256KiB PRG, 8KiB of PRG RAM and CHR RAM
+    let mut cart = cartridge(&image(1, 0, 16, 0, 7, 7));
+    write(&mut cart, 0x6000, 0x42);
+    assert_eq!(cart.read(0x6000), Some(0x42));
+
+    mmc1_write(&mut cart, 0xA000, 0x10);
+    assert_eq!(cart.read(0x6000), None);
+    write(&mut cart, 0x6000, 0x24);
+
+    mmc1_write(&mut cart, 0xA000, 0x00);
+    assert_eq!(cart.read(0x6000), Some(0x42));
+}
+
+#[test]
+fn sorom_and_sxrom_bank_prg_ram_with_the_chr_register() {
+    //(RAM shift, number of 8KiB banks, how far up the CHR register the bank number goes)
+    let cases = [(8, 2, 3), (9, 4, 2)];
+    for &(shift, banks, bit) in cases.iter() {
+        let mut cart = cartridge(&image(1, 0, 16, 0, shift, 7));
+        for bank in 0..banks {
+            mmc1_write(&mut cart, 0xA000, bank << bit);
+            write(&mut cart, 0x6000, bank + 1);
+        }
+        for bank in 0..banks {
+            mmc1_write(&mut cart, 0xA000, bank << bit);
+            assert_eq!(cart.read(0x6000), Some(bank + 1), "{} banks", banks);
+            assert_eq!(cart.prg_ram()[bank as usize * 8192], bank + 1, "{} banks", banks);
+        }
+    }
+}
+
+#[test]
+fn surom_picks_the_256k_half_with_chr_bit_4() {
+    let mut cart = cartridge(&image(1, 0, 32, 0, 7, 7));
+    mmc1_write(&mut cart, 0xE000, 3);
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (3, 15));
+
+    mmc1_write(&mut cart, 0xA000, 0x10);
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (19, 31));
+    //Which leaves PRG RAM alone, unlike on SNROM
+    write(&mut cart, 0x6000, 0x42);
+    assert_eq!(cart.read(0x6000), Some(0x42));
+}
+
+//UxROM, CNROM or AxROM with enough banks to switch between
+fn discrete(mapper: u8, submapper: u8) -> Cartridge {
+    match mapper {
+        3 => cartridge(&image(mapper, submapper, 2, 4, 0, 0)),
+        _ => cartridge(&image(mapper, submapper, 8, 1, 0, 0))
+    }
+}
+
+//The bank the discrete mapper has switched in
+fn selected_bank(mapper: u8, cart: &mut Cartridge) -> usize {
+    match mapper {
+        2 => prg_bank(cart, 0x8000, 16384),
+        3 => chr_bank(cart, 0x0000, 8192),
+        _ => prg_bank(cart, 0x8000, 32768)
+    }
+}
+
+#[test]
+fn uxrom_switches_16k_at_8000_and_fixes_the_last_bank() {
+    let mut cart = discrete(2, 1);
+    write(&mut cart, 0x8000, 3);
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (3, 7));
+    write(&mut cart, 0xFFFF, 5);
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (5, 7));
+}
+
+#[test]
+fn cnrom_switches_8k_of_chr() {
+    let mut cart = discrete(3, 1);
+    write(&mut cart, 0x8000, 2);
+    assert_eq!(chr_bank(&mut cart, 0x0000, 8192), 2);
+    assert_eq!(chr_bank(&mut cart, 0x1FFF, 8192), 2);
+    //PRG doesn't move
+    assert_eq!((prg_bank(&mut cart, 0x8000, 16384), prg_bank(&mut cart, 0xC000, 16384)), (0, 1));
+}
+
+#[test]
+fn axrom_switches_32k_and_picks_a_single_screen_with_bit_4() {
+    let mut cart = discrete(7, 0);
+    assert_eq!(cart.mirroring(), Mirroring::SingleScreenLower);
+    write(&mut cart, 0x8000, 0x12);
+    assert_eq!((prg_bank(&mut cart, 0x8000, 32768), prg_bank(&mut cart, 0xFFFF, 32768)), (2, 2));
+    assert_eq!(cart.mirroring(), Mirroring::SingleScreenUpper);
+    write(&mut cart, 0x8000, 0x03);
+    assert_eq!(prg_bank(&mut cart, 0x8000, 32768), 3);
+    assert_eq!(cart.mirroring(), Mirroring::SingleScreenLower);
+}
+
+#[test]
+fn bus_conflicts_and_the_written_value_with_the_rom() {
+    //(mapper, submapper, bank after writing 3 over a ROM byte of 2)
+    let cases = [
+        (2, 0, 2),
+        (2, 1, 3),
+        (2, 2, 2),
+        (3, 0, 2),
+        (3, 1, 3),
+        (3, 2, 2),
+        //ANROM doesn't have them, AMROM and AOROM do
+        (7, 0, 3),
+        (7, 1, 3),
+        (7, 2, 2)
+    ];
+    for &(mapper, submapper, bank) in cases.iter() {
+        let mut cart = discrete(mapper, submapper);
+        assert_eq!(cart.read(0xA000), Some(2));
+        write(&mut cart, 0xA000, 3);
+        assert_eq!(selected_bank(mapper, &mut cart), bank, "mapper {} submapper {}", mapper, submapper);
+    }
+}
+
+//Selects an MMC3 bank register, along with the PRG and CHR mode bits, and writes to it
+fn mmc3_bank(cart: &mut Cartridge, modes: u8, register: u8, value: u8) {
+    write(cart, 0x8000, modes | register);
+    write(cart, 0x8001, value);
+}
+
+//A12 sits low long enough to get past the filter and then rises, like it does once per scanline
+fn scanline(cart: &mut Cartridge) {
+    cart.ppu_address(0x0000);
+    for _ in 0..3 {
+        cart.clock();
+    }
+    cart.ppu_address(0x1000);
+}
+
+//An MMC3 with its IRQ counter set up to reload from latch and fire
+fn mmc3_irq(submapper: u8, latch: u8) -> Cartridge {
+    let mut cart = cartridge(&image(4, submapper, 8, 4, 0, 0));
+    write(&mut cart, 0xC000, latch);
+    write(&mut cart, 0xC001, 0);
+    write(&mut cart, 0xE001, 0);
+    cart
+}
+
+#[test]
+fn mmc3_counts_scanlines_down_from_the_latch_and_fires_at_0() {
+    let mut cart = mmc3_irq(0, 2);
+    //The first clock loads the counter
+    for &fires in [false, false, true].iter() {
+        assert!(!cart.irq());
+        scanline(&mut cart);
+        assert_eq!(cart.irq(), fires);
+    }
+    //Then it stays high until it's acknowledged
+    scanline(&mut cart);
+    assert!(cart.irq());
+}
+
+#[test]
+fn mmc3_c001_reloads_the_counter_on_the_next_clock() {
+    let mut cart = mmc3_irq(0, 3);
+    scanline(&mut cart);
+    //A new latch doesn't do anything until the counter is reloaded
+    write(&mut cart, 0xC000, 1);
+    scanline(&mut cart);
+    write(&mut cart, 0xC001, 0);
+    scanline(&mut cart);
+    assert!(!cart.irq());
+    scanline(&mut cart);
+    assert!(cart.irq());
+}
+
+#[test]
+fn mmc3_doesnt_fire_with_the_irq_disabled() {
+    let mut cart = mmc3_irq(0, 1);
+    write(&mut cart, 0xE000, 0);
+    for _ in 0..4 {
+        scanline(&mut cart);
+        assert!(!cart.irq());
+    }
+}
+
+#[test]
+fn mmc3_e000_acknowledges_and_disables_the_irq() {
+    let mut cart = mmc3_irq(0, 1);
+    scanline(&mut cart);
+    scanline(&mut cart);
+    assert!(cart.irq());
+
+    write(&mut cart, 0xE000, 0);
+    assert!(!cart.irq());
+    //Counting carries on while it's disabled
+    scanline(&mut cart);
+    scanline(&mut cart);
+    assert!(!cart.irq());
+
+    write(&mut cart, 0xE001, 0);
+    scanline(&mut cart);
+    scanline(&mut cart);
+    assert!(cart.irq());
+}
+
+#[test]
+fn mmc3_sharp_fires_every_clock_with_a_latch_of_0_but_nec_only_after_the_reload() {
+    //(submapper, fires on each scanline)
+    let cases = [(0, [true, true, true]), (4, [true, false, false])];
+    for &(submapper, fires) in cases.iter() {
+        let mut cart = mmc3_irq(submapper, 0);
+        for (line, &fire) in fires.iter().enumerate() {
+            scanline(&mut cart);
+            assert_eq!(cart.irq(), fire, "submapper {} scanline {}", submapper, line);
+            write(&mut cart, 0xE000, 0);
+            write(&mut cart, 0xE001, 0);
+        }
+    }
+}
+
+#[test]
+fn mmc3_ignores_a12_rising_again_within_3_cycles() {
+    let mut cart = mmc3_irq(0, 1);
+    scanline(&mut cart);
+
+    //Like the toggling during sprite fetches
+    for _ in 0..4 {
+        cart.ppu_address(0x0000);
+        cart.clock();
+        cart.clock();
+        cart.ppu_address(0x1000);
+    }
+    assert!(!cart.irq());
+
+    scanline(&mut cart);
+    assert!(cart.irq());
+}
+
+#[test]
+fn mmc3_prg_mode_swaps_the_switchable_and_second_last_banks() {
+    //(bank select bits, 8KiB banks at $8000, $A000, $C000 and $E000) with R6 = 3 and R7 = 4
+    let cases = [(0x00, [3, 4, 14, 15]), (0x40, [14, 4, 3, 15])];
+    for &(mode, banks) in cases.iter() {
+        let mut cart = cartridge(&image(4, 0, 8, 4, 0, 0));
+        mmc3_bank(&mut cart, mode, 6, 3);
+        mmc3_bank(&mut cart, mode, 7, 4);
+        let mapped: Vec<usize> = [0x8000, 0xA000, 0xC000, 0xE000].iter().map(|&address| prg_bank(&mut cart, address, 8192)).collect();
+        assert_eq!(mapped, banks, "mode {:02X}", mode);
+    }
+}
+
+#[test]
+fn mmc3_chr_inversion_swaps_the_2k_and_1k_halves() {
+    //(bank select bits, 1KiB banks at $0000-$1C00) with R0 = 3, R1 = 4 and R2-R5 = 10-13
+    let cases = [(0x00, [2, 3, 4, 5, 10, 11, 12, 13]), (0x80, [10, 11, 12, 13, 2, 3, 4, 5])];
+    for &(mode, banks) in cases.iter() {
+        let mut cart = cartridge(&image(4, 0, 8, 4, 0, 0));
+        //The 2KiB banks ignore the low bit
+        mmc3_bank(&mut cart, mode, 0, 3);
+        mmc3_bank(&mut cart, mode, 1, 4);
+        for register in 2..6 {
+            mmc3_bank(&mut cart, mode, register, register + 8);
+        }
+        let mapped: Vec<usize> = (0..8).map(|slot| chr_bank(&mut cart, slot * 0x400, 1024)).collect();
+        assert_eq!(mapped, banks, "mode {:02X}", mode);
+    }
+}
+
+#[test]
+fn mmc3_a001_enables_and_write_protects_prg_ram() {
+    let mut cart = cartridge(&image(4, 0, 8, 4, 7, 0));
+    //Powers on enabled and writable
+    write(&mut cart, 0x6000, 0x11);
+    assert_eq!(cart.read(0x6000), Some(0x11));
+
+    //(bit 7 enable and bit 6 protect, what's read back after writing $22 or None if nothing is)
+    let cases = [(0x00, None), (0xC0, Some(0x11)), (0x80, Some(0x22))];
+    for &(a001, read) in cases.iter() {
+        write(&mut cart, 0xA001, a001);
+        write(&mut cart, 0x6000, 0x22);
+        assert_eq!(cart.read(0x6000), read, "$A001 {:02X}", a001);
+    }
+}
@@ -0,0 +1,86 @@
+//Drives the Nes facade the way the SDL binary does
+
+use nes_emulator::{Nes, NesError, SCREEN_WIDTH, SCREEN_HEIGHT};
+use nes_emulator::Bus::Bus;
+
+//NROM with a reset vector at start and a program there that stores value to $10 then spins
+fn image(start: u16, value: u8) -> Vec<u8> {
+    let mut prg = vec![0xEA; 16384];
+    let program = [0xA9, value, 0x85, 0x10, 0x4C, (start + 4) as u8, ((start + 4) >> 8) as u8];
+    let offset = (start & 0x3FFF) as usize;
+    prg[offset..offset + program.len()].copy_from_slice(&program);
+    prg[0x3FFC] = start as u8;
+    prg[0x3FFD] = (start >> 8) as u8;
+
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(prg);
+    rom.extend(vec![0; 8192]);
+    rom
+}
+
+fn ram(nes: &Nes, address: u16) -> u8 {
+    nes.cpu().bus().peek(address)
+}
+
+#[test]
+fn from_rom_bytes_runs_from_the_reset_vector() {
+    let mut nes = Nes::from_rom_bytes(&image(0x8000, 0x42)).unwrap();
+    //The first step is the reset sequence
+    assert!(!nes.step_instruction().unwrap());
+    assert_eq!(nes.cpu().registers().program_counter, 0x8000);
+
+    nes.run_frame().unwrap();
+    assert_eq!(ram(&nes, 0x10), 0x42);
+    assert!(!nes.is_halted());
+    assert_eq!(nes.frame_buffer().len(), SCREEN_WIDTH * SCREEN_HEIGHT * 3);
+
+    //Another frame takes the same time give or take a cycle
+    let before = nes.cpu().total_cycles();
+    nes.run_frame().unwrap();
+    let cycles = nes.cpu().total_cycles() - before;
+    assert!((29780..=29782).contains(&cycles), "{}", cycles);
+}
+
+#[test]
+fn load_rom_swaps_the_cartridge_and_powers_back_on() {
+    let path = std::env::temp_dir().join(format!("nes_emulator_load_rom_{}.nes", std::process::id()));
+    std::fs::write(&path, image(0xC000, 0x99)).unwrap();
+
+    let mut nes = Nes::from_rom_bytes(&image(0x8000, 0x42)).unwrap();
+    nes.run_frame().unwrap();
+    let loaded = nes.load_rom(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    loaded.unwrap();
+
+    assert_eq!(nes.cpu().total_cycles(), 0);
+    nes.step_instruction().unwrap();
+    assert_eq!(nes.cpu().registers().program_counter, 0xC000);
+    nes.run_frame().unwrap();
+    assert_eq!(ram(&nes, 0x10), 0x99);
+
+    //And from memory
+    nes.load_rom_bytes(&image(0x8000, 0x42)).unwrap();
+    nes.step_instruction().unwrap();
+    assert_eq!(nes.cpu().registers().program_counter, 0x8000);
+}
+
+#[test]
+fn a_failed_load_keeps_the_old_cartridge() {
+    let mut nes = Nes::from_rom_bytes(&image(0x8000, 0x42)).unwrap();
+    assert!(matches!(nes.load_rom("res/no_such_rom.nes"), Err(NesError::Io(_))));
+    assert!(matches!(nes.load_rom_bytes(b"NES"), Err(NesError::BadMagic)));
+    nes.step_instruction().unwrap();
+    assert_eq!(nes.cpu().registers().program_counter, 0x8000);
+}
+
+#[test]
+fn reset_goes_back_through_the_reset_vector() {
+    let mut nes = Nes::from_rom_bytes(&image(0x8000, 0x42)).unwrap();
+    for _ in 0..4 {
+        nes.step_instruction().unwrap();
+    }
+    assert_eq!(nes.cpu().registers().program_counter, 0x8004);
+    nes.reset();
+    nes.step_instruction().unwrap();
+    assert_eq!(nes.cpu().registers().program_counter, 0x8000);
+}
@@ -0,0 +1,112 @@
+//Runs nestest in its automation mode (starting at $C000, no PPU needed) and checks it against
+//Nintendulator's golden log. The log isn't distributed with the ROM, so that check is ignored by
+//default. Drop the log at res/nestest.log or point NESTEST_LOG at it, then run
+//cargo test --test nestest -- --ignored
+
+use std::fs;
+
+use nes_emulator::{Nes, Debugger};
+use nes_emulator::Bus::Bus;
+
+const ROM: &str = "res/nestest.nes";
+const AUTOMATION_START: u16 = 0xC000;
+//The final RTS. Returning from it would take us back into the reset code
+const AUTOMATION_END: u16 = 0xC66E;
+//Everything before the first *NOP only uses official opcodes
+const UNOFFICIAL_START: u16 = 0xC6BD;
+//Lines either side of a divergence to print
+const CONTEXT: usize = 5;
+
+fn boot() -> Nes {
+    let mut nes = Nes::from_rom_file(ROM).expect("couldn't load nestest");
+    //Let the reset sequence finish before moving the PC
+    nes.step_instruction().unwrap();
+    nes.cpu_mut().set_program_counter(AUTOMATION_START);
+    nes
+}
+
+//Runs until the PC reaches end or max_lines, whichever comes first. Stops early if the CPU faults
+fn run_trace(nes: &mut Nes, end: u16, max_lines: usize) -> Vec<String> {
+    let mut log = Vec::new();
+    while log.len() < max_lines {
+        log.push(Debugger::trace_line(nes.cpu()));
+        if nes.cpu().registers().program_counter == end {
+            break;
+        }
+        if let Err(e) = nes.step_instruction() {
+            log.push(format!("CPU fault: {}", e));
+            break;
+        }
+    }
+    log
+}
+
+//None if the logs agree, otherwise a report around the first line that doesn't
+fn first_divergence(ours: &[String], reference: &[&str]) -> Option<String> {
+    let mismatch = (0..ours.len().max(reference.len())).find(|i| {
+        ours.get(*i).map(|s| s.as_str()) != reference.get(*i).copied()
+    })?;
+
+    let mut report = format!("logs diverge at line {}\n", mismatch + 1);
+    let end = mismatch.min(reference.len());
+    for line in &reference[end.saturating_sub(CONTEXT)..end] {
+        report += &format!("      {}\n", line);
+    }
+    report += &format!("want: {}\n", reference.get(mismatch).unwrap_or(&"<end of log>"));
+    report += &format!("got:  {}\n", ours.get(mismatch).map(|s| s.as_str()).unwrap_or("<end of log>"));
+    for line in ours.iter().skip(mismatch + 1).take(CONTEXT) {
+        report += &format!("      {}\n", line);
+    }
+    Some(report)
+}
+
+#[test]
+#[ignore = "needs Nintendulator's nestest.log, see the top of this file"]
+fn nestest_matches_golden_log() {
+    let path = std::env::var("NESTEST_LOG").unwrap_or_else(|_| "res/nestest.log".to_string());
+    let reference = fs::read_to_string(&path).unwrap_or_else(|e| panic!("no reference log at {}: {}", path, e));
+    let reference: Vec<&str> = reference.lines().map(|line| line.trim_end()).collect();
+
+    let mut nes = boot();
+    let ours = run_trace(&mut nes, AUTOMATION_END, reference.len());
+
+    if let Some(report) = first_divergence(&ours, &reference) {
+        panic!("{}", report);
+    }
+}
+
+//nestest leaves an error code in $02 for the official opcodes and $03 for the unofficial ones
+#[test]
+fn nestest_official_opcodes_pass() {
+    let mut nes = boot();
+    let log = run_trace(&mut nes, UNOFFICIAL_START, 20_000);
+
+    let last = log.last().unwrap();
+    assert!(last.starts_with(&format!("{:04X}", UNOFFICIAL_START)), "nestest didn't finish, stopped at:\n{}", last);
+    let official = nes.cpu().bus().peek(0x0002);
+    assert_eq!(official, 0, "nestest error code $02={:02X}", official);
+}
+
+#[test]
+fn nestest_reports_no_errors() {
+    let mut nes = boot();
+    let log = run_trace(&mut nes, AUTOMATION_END, 20_000);
+
+    let last = log.last().unwrap();
+    assert!(last.starts_with(&format!("{:04X}", AUTOMATION_END)), "nestest didn't finish, stopped at:\n{}", last);
+
+    let official = nes.cpu().bus().peek(0x0002);
+    let unofficial = nes.cpu().bus().peek(0x0003);
+    assert_eq!((official, unofficial), (0, 0), "nestest error codes $02={:02X} $03={:02X}", official, unofficial);
+}
+
+#[test]
+fn first_divergence_points_at_the_mismatch() {
+    let ours: Vec<String> = ["a", "b", "x", "d"].iter().map(|s| s.to_string()).collect();
+    let report = first_divergence(&ours, &["a", "b", "c", "d"]).unwrap();
+    assert!(report.starts_with("logs diverge at line 3"));
+    assert!(report.contains("want: c\ngot:  x\n"));
+
+    assert!(first_divergence(&ours[..2], &["a", "b"]).is_none());
+    assert!(first_divergence(&ours[..2], &["a", "b", "c"]).unwrap().contains("got:  <end of log>"));
+}
@@ -0,0 +1,845 @@
+//Drives the PPU directly through its registers, with an NROM cartridge for the pattern tables or an
+//MMC3 one counting the lines it renders.
+//Blargg's ppu_vbl_nmi ROMs, the newer version of vbl_nmi_timing, aren't in this repository so they
+//are ignored by default. Put them in res/blargg/ppu_vbl_nmi and run
+//cargo test --test ppu -- --ignored
+
+use nes_emulator::Nes;
+use nes_emulator::Bus::Bus;
+use nes_emulator::Cartridge::Cartridge;
+use nes_emulator::PPU::{rgb, PPU};
+
+mod common;
+
+use common::run_blargg;
+
+const BACKDROP: u8 = 0x0F;
+//Colour 1 of background palettes 0 and 1
+const COLOUR_1: u8 = 0x16;
+const PALETTE_1_COLOUR_1: u8 = 0x12;
+//Colour 1 of sprite palettes 0 and 1
+const SPRITE_COLOUR_1: u8 = 0x2A;
+const SPRITE_PALETTE_1_COLOUR_1: u8 = 0x24;
+//PPUMASK bits
+const SHOW_LEFT_BACKGROUND: u8 = 0b00000010;
+const SHOW_BACKGROUND: u8 = 0b00001010;
+const SHOW_LEFT_SPRITES: u8 = 0b00000100;
+const SHOW_SPRITES: u8 = 0b00010100;
+//PPUCTRL bits
+const SPRITE_TABLE_1000: u8 = 0b00001000;
+const TALL_SPRITES: u8 = 0b00100000;
+const NMI_ENABLE: u8 = 0b10000000;
+//PPUSTATUS bits
+const SPRITE_OVERFLOW: u8 = 0b00100000;
+const SPRITE_ZERO_HIT: u8 = 0b01000000;
+const VBLANK: u8 = 0b10000000;
+//Sprite attributes
+const BEHIND_BACKGROUND: u8 = 0b00100000;
+const FLIP_HORIZONTALLY: u8 = 0b01000000;
+const FLIP_VERTICALLY: u8 = 0b10000000;
+
+//Tile 1 is solid colour 1, tile 2 is colour 1 on the left half only and tile 3 is colour 1 on the
+//top row only. The rest are empty
+fn cartridge(mirroring: u8) -> Cartridge {
+    let mut chr = vec![0; 8192];
+    for row in 0..8 {
+        chr[0x10 + row] = 0xFF;
+        chr[0x20 + row] = 0xF0;
+    }
+    chr[0x30] = 0xFF;
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, mirroring, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(vec![0xEA; 16384]);
+    rom.extend(chr);
+    Cartridge::from_bytes(&rom).unwrap()
+}
+
+//A whole NROM image with program at $8000 and an NMI handler at $8010 that just returns
+fn nrom(program: &[u8]) -> Vec<u8> {
+    let mut prg = vec![0xEA; 16384];
+    prg[..program.len()].copy_from_slice(program);
+    prg[0x10] = 0x40;
+    prg[0x3FFA..].copy_from_slice(&[0x10, 0x80, 0x00, 0x80, 0x00, 0x80]);
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(prg);
+    rom.extend(vec![0; 8192]);
+    rom
+}
+
+fn set_address(ppu: &mut PPU, cart: &mut Cartridge, address: u16) {
+    ppu.cpu_write(0x2006, (address >> 8) as u8, cart);
+    ppu.cpu_write(0x2006, address as u8, cart);
+}
+
+fn write_vram(ppu: &mut PPU, cart: &mut Cartridge, address: u16, data: &[u8]) {
+    set_address(ppu, cart, address);
+    for &byte in data {
+        ppu.cpu_write(0x2007, byte, cart);
+    }
+}
+
+//Everything but the palette needs a read to fill the buffer first
+fn read_vram(ppu: &mut PPU, cart: &mut Cartridge, address: u16) -> u8 {
+    set_address(ppu, cart, address);
+    if address < 0x3F00 {
+        ppu.cpu_read(0x2007, cart);
+    }
+    ppu.cpu_read(0x2007, cart)
+}
+
+//Background palettes set up and nothing drawn yet
+fn setup(mirroring: u8) -> (PPU, Cartridge) {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(mirroring);
+    write_vram(&mut ppu, &mut cart, 0x3F00, &[BACKDROP, COLOUR_1, 0x2A, 0x30, BACKDROP, PALETTE_1_COLOUR_1]);
+    write_vram(&mut ppu, &mut cart, 0x3F11, &[SPRITE_COLOUR_1, 0x2B, 0x2C, BACKDROP, SPRITE_PALETTE_1_COLOUR_1]);
+    (ppu, cart)
+}
+
+//Games do this once they're done writing to VRAM, since $2006 writes move the scroll too
+fn scroll(ppu: &mut PPU, cart: &mut Cartridge, nametable: u8, x: u8, y: u8) {
+    ppu.cpu_write(0x2000, nametable, cart);
+    ppu.cpu_write(0x2005, x, cart);
+    ppu.cpu_write(0x2005, y, cart);
+}
+
+//Turns on the background with mask and runs until there's a whole frame of it. The first frame is
+//missing the tiles fetched on the line before, so this runs two
+fn render(ppu: &mut PPU, cart: &mut Cartridge, mask: u8) {
+    ppu.cpu_write(0x2001, mask, cart);
+    for _ in 0..2 {
+        while !ppu.clock(cart) {}
+    }
+}
+
+//Fills OAM with sprites given as (Y, tile, attributes, X). The rest are put below the screen
+fn sprites(ppu: &mut PPU, cart: &mut Cartridge, sprites: &[(u8, u8, u8, u8)]) {
+    let mut oam = [0xFF; 256];
+    for (i, &(y, tile, attributes, x)) in sprites.iter().enumerate() {
+        oam[i * 4..i * 4 + 4].copy_from_slice(&[y, tile, attributes, x]);
+    }
+    ppu.cpu_write(0x2003, 0, cart);
+    for &byte in oam.iter() {
+        ppu.cpu_write(0x2004, byte, cart);
+    }
+}
+
+//Runs until the end of the visible part of the frame and returns PPUSTATUS, before the flags get
+//cleared for the next one
+fn status_after_drawing(ppu: &mut PPU, cart: &mut Cartridge) -> u8 {
+    run_to(ppu, cart, (240, 0));
+    ppu.peek_register(0x2002)
+}
+
+fn run_to(ppu: &mut PPU, cart: &mut Cartridge, position: (u16, u16)) {
+    while ppu.position() != position {
+        ppu.clock(cart);
+    }
+}
+
+//How many dots until the end of the frame
+fn dots_in_frame(ppu: &mut PPU, cart: &mut Cartridge) -> u32 {
+    let mut dots = 1;
+    while !ppu.clock(cart) {
+        dots += 1;
+    }
+    dots
+}
+
+fn pixel(ppu: &PPU, x: usize, y: usize) -> [u8; 3] {
+    let index = (y * 256 + x) * 3;
+    [ppu.frame_buffer[index], ppu.frame_buffer[index + 1], ppu.frame_buffer[index + 2]]
+}
+
+#[test]
+fn vram_reads_are_a_step_behind_but_palette_reads_are_not() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    write_vram(&mut ppu, &mut cart, 0x2000, &[0x11, 0x22]);
+    write_vram(&mut ppu, &mut cart, 0x3F01, &[0x33]);
+
+    set_address(&mut ppu, &mut cart, 0x2000);
+    let reads: Vec<u8> = (0..3).map(|_| ppu.cpu_read(0x2007, &mut cart)).collect();
+    assert_eq!(reads[1..], [0x11, 0x22]);
+
+    set_address(&mut ppu, &mut cart, 0x3F01);
+    assert_eq!(ppu.cpu_read(0x2007, &mut cart), 0x33);
+}
+
+#[test]
+fn pattern_tables_come_from_the_cartridge() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    assert_eq!(read_vram(&mut ppu, &mut cart, 0x0010), 0xFF);
+    assert_eq!(read_vram(&mut ppu, &mut cart, 0x0020), 0xF0);
+    //CHR ROM ignores writes
+    write_vram(&mut ppu, &mut cart, 0x0010, &[0x00]);
+    assert_eq!(read_vram(&mut ppu, &mut cart, 0x0010), 0xFF);
+}
+
+#[test]
+fn nametables_are_mirrored_the_way_the_cartridge_says() {
+    //(iNES flags 6, nametable that should mirror $2000, one that shouldn't)
+    for &(flags, same, different) in [(0, 0x2400, 0x2800), (1, 0x2800, 0x2400)].iter() {
+        let mut ppu = PPU::new();
+        let mut cart = cartridge(flags);
+        write_vram(&mut ppu, &mut cart, 0x2000, &[0x5A]);
+        assert_eq!(read_vram(&mut ppu, &mut cart, same), 0x5A, "flags {}", flags);
+        assert_eq!(read_vram(&mut ppu, &mut cart, different), 0x00, "flags {}", flags);
+        //$3000-$3EFF mirrors $2000-$2EFF
+        assert_eq!(read_vram(&mut ppu, &mut cart, 0x3000), 0x5A, "flags {}", flags);
+    }
+}
+
+#[test]
+fn sprite_backdrop_entries_share_the_background_ones() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    write_vram(&mut ppu, &mut cart, 0x3F10, &[0x21]);
+    write_vram(&mut ppu, &mut cart, 0x3F14, &[0x22]);
+    assert_eq!(read_vram(&mut ppu, &mut cart, 0x3F00), 0x21);
+    assert_eq!(read_vram(&mut ppu, &mut cart, 0x3F04), 0x22);
+    //The palette repeats every 32 bytes. The top 2 bits come from the I/O latch, which has the low
+    //byte of the address in it
+    assert_eq!(read_vram(&mut ppu, &mut cart, 0x3FE0), 0xC0 | 0x21);
+}
+
+#[test]
+fn background_tiles_are_drawn_from_the_nametable() {
+    let (mut ppu, mut cart) = setup(0);
+    //Tile 1 at the top left and tile 2 next to it
+    write_vram(&mut ppu, &mut cart, 0x2000, &[1, 2]);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND);
+
+    for y in 0..8 {
+        for x in 0..8 {
+            assert_eq!(pixel(&ppu, x, y), rgb(COLOUR_1), "({}, {})", x, y);
+        }
+        for x in 8..12 {
+            assert_eq!(pixel(&ppu, x, y), rgb(COLOUR_1), "({}, {})", x, y);
+        }
+        for x in 12..24 {
+            assert_eq!(pixel(&ppu, x, y), rgb(BACKDROP), "({}, {})", x, y);
+        }
+    }
+    assert_eq!(pixel(&ppu, 0, 8), rgb(BACKDROP));
+}
+
+#[test]
+fn fine_x_scroll_moves_the_background_left() {
+    let (mut ppu, mut cart) = setup(0);
+    write_vram(&mut ppu, &mut cart, 0x2000, &[1]);
+    scroll(&mut ppu, &mut cart, 0, 3, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND);
+
+    assert_eq!(pixel(&ppu, 4, 0), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 5, 0), rgb(BACKDROP));
+}
+
+#[test]
+fn the_left_column_can_be_hidden() {
+    let (mut ppu, mut cart) = setup(0);
+    write_vram(&mut ppu, &mut cart, 0x2000, &[1, 1]);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND & !SHOW_LEFT_BACKGROUND);
+
+    assert_eq!(pixel(&ppu, 7, 0), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 8, 0), rgb(COLOUR_1));
+}
+
+#[test]
+fn attributes_pick_the_palette_for_each_16_pixel_square() {
+    let (mut ppu, mut cart) = setup(0);
+    //Tile 1 everywhere in the top 4 rows, with palette 1 for the top right 16x16 of the first
+    //attribute byte
+    write_vram(&mut ppu, &mut cart, 0x2000, &[1; 128]);
+    write_vram(&mut ppu, &mut cart, 0x23C0, &[0b00000100]);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND);
+
+    assert_eq!(pixel(&ppu, 15, 15), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 16, 15), rgb(PALETTE_1_COLOUR_1));
+    assert_eq!(pixel(&ppu, 31, 0), rgb(PALETTE_1_COLOUR_1));
+    assert_eq!(pixel(&ppu, 16, 16), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 32, 0), rgb(COLOUR_1));
+}
+
+#[test]
+fn vertical_scroll_moves_the_background_up() {
+    let (mut ppu, mut cart) = setup(0);
+    //Tile 1 on the second row
+    write_vram(&mut ppu, &mut cart, 0x2020, &[1]);
+    scroll(&mut ppu, &mut cart, 0, 0, 4);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND);
+
+    assert_eq!(pixel(&ppu, 0, 3), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 0, 4), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 0, 11), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 0, 12), rgb(BACKDROP));
+}
+
+#[test]
+fn scrolling_carries_into_the_next_nametable() {
+    //Vertical mirroring, so $2400 is the nametable to the right. Tile 1 in its top left
+    let (mut ppu, mut cart) = setup(1);
+    write_vram(&mut ppu, &mut cart, 0x2400, &[1]);
+
+    //Selected with $2000
+    scroll(&mut ppu, &mut cart, 1, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND);
+    assert_eq!(pixel(&ppu, 0, 0), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 8, 0), rgb(BACKDROP));
+
+    //Or scrolled into from nametable 0
+    scroll(&mut ppu, &mut cart, 0, 250, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND);
+    assert_eq!(pixel(&ppu, 5, 0), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 6, 0), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 13, 0), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 14, 0), rgb(BACKDROP));
+}
+
+#[test]
+fn scroll_changes_mid_frame_start_on_the_next_line() {
+    //Tile 1 down the left edge of the screen
+    let (mut ppu, mut cart) = setup(0);
+    for row in 0..30 {
+        write_vram(&mut ppu, &mut cart, 0x2000 + row * 32, &[1]);
+    }
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND);
+
+    //Like a status bar split. Only X can be changed this way, Y waits for the next frame
+    while ppu.position() != (100, 0) {
+        ppu.clock(&mut cart);
+    }
+    scroll(&mut ppu, &mut cart, 0, 8, 100);
+    while !ppu.clock(&mut cart) {}
+
+    assert_eq!(pixel(&ppu, 0, 100), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 0, 101), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 0, 239), rgb(BACKDROP));
+}
+
+#[test]
+fn sprites_are_drawn_a_line_below_their_y() {
+    let (mut ppu, mut cart) = setup(0);
+    sprites(&mut ppu, &mut cart, &[(10, 1, 0, 20), (0, 1, 1, 100)]);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_SPRITES);
+
+    assert_eq!(pixel(&ppu, 20, 10), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 19, 11), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 20, 11), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 27, 18), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 28, 18), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 27, 19), rgb(BACKDROP));
+    //With the palette from the attributes. Nothing can be drawn on the first line
+    assert_eq!(pixel(&ppu, 100, 0), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 100, 1), rgb(SPRITE_PALETTE_1_COLOUR_1));
+}
+
+#[test]
+fn sprites_can_be_flipped() {
+    let (mut ppu, mut cart) = setup(0);
+    //Tile 2 is the left half and tile 3 the top row
+    sprites(&mut ppu, &mut cart, &[(10, 2, FLIP_HORIZONTALLY, 20), (30, 3, FLIP_VERTICALLY, 20)]);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_SPRITES);
+
+    assert_eq!(pixel(&ppu, 23, 11), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 24, 11), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 20, 31), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 20, 38), rgb(SPRITE_COLOUR_1));
+}
+
+#[test]
+fn tall_sprites_are_two_tiles_from_the_table_in_the_tile_number() {
+    let (mut ppu, mut cart) = setup(0);
+    //Tiles 0 and 1 of the first table, and then the same tiles from the empty second table
+    sprites(&mut ppu, &mut cart, &[(10, 0x00, 0, 20), (10, 0x01, 0, 40), (30, 0x00, FLIP_VERTICALLY, 20)]);
+    scroll(&mut ppu, &mut cart, TALL_SPRITES, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_SPRITES);
+
+    assert_eq!(pixel(&ppu, 20, 18), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 20, 19), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 20, 26), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 20, 27), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 40, 19), rgb(BACKDROP));
+    //Flipping swaps the tiles too
+    assert_eq!(pixel(&ppu, 20, 31), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 20, 39), rgb(BACKDROP));
+}
+
+#[test]
+fn sprite_priority_against_the_background_and_each_other() {
+    let (mut ppu, mut cart) = setup(0);
+    //Tile 1 in the second nametable column, covering x 8-15
+    write_vram(&mut ppu, &mut cart, 0x2001, &[1]);
+    //In front of the background, behind it, and two overlapping on a blank bit where the first
+    //one in OAM wins even though it's behind the background
+    sprites(&mut ppu, &mut cart, &[(10, 1, 0, 8), (20, 1, BEHIND_BACKGROUND, 8), (30, 1, BEHIND_BACKGROUND, 40), (30, 1, 1, 44)]);
+    write_vram(&mut ppu, &mut cart, 0x2000 + 4 * 32 + 1, &[1]);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND | SHOW_SPRITES);
+
+    assert_eq!(pixel(&ppu, 8, 7), rgb(COLOUR_1));
+    //Row 1 of tiles isn't drawn on so the sprite shows
+    assert_eq!(pixel(&ppu, 8, 11), rgb(SPRITE_COLOUR_1));
+    //Row 2 isn't either, but the sprite's behind a blank bit so it still shows
+    assert_eq!(pixel(&ppu, 8, 21), rgb(SPRITE_COLOUR_1));
+    //Row 4 is, and the sprite is behind it
+    assert_eq!(pixel(&ppu, 8, 33), rgb(COLOUR_1));
+    assert_eq!(pixel(&ppu, 40, 31), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 44, 31), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 48, 31), rgb(SPRITE_PALETTE_1_COLOUR_1));
+}
+
+#[test]
+fn the_left_column_can_hide_sprites() {
+    let (mut ppu, mut cart) = setup(0);
+    sprites(&mut ppu, &mut cart, &[(10, 1, 0, 4)]);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_SPRITES & !SHOW_LEFT_SPRITES);
+
+    assert_eq!(pixel(&ppu, 7, 11), rgb(BACKDROP));
+    assert_eq!(pixel(&ppu, 8, 11), rgb(SPRITE_COLOUR_1));
+}
+
+#[test]
+fn only_eight_sprites_are_drawn_on_a_line() {
+    let (mut ppu, mut cart) = setup(0);
+    let line: Vec<_> = (0..9).map(|i| (10, 1, 0, i * 16)).collect();
+    sprites(&mut ppu, &mut cart, &line);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_SPRITES);
+
+    assert_eq!(pixel(&ppu, 7 * 16, 11), rgb(SPRITE_COLOUR_1));
+    assert_eq!(pixel(&ppu, 8 * 16, 11), rgb(BACKDROP));
+    assert_ne!(status_after_drawing(&mut ppu, &mut cart) & SPRITE_OVERFLOW, 0);
+}
+
+#[test]
+fn sprite_overflow_checks_the_wrong_bytes_after_eight() {
+    //(sprites after the 8 on line 11, overflow). Once the check is going wrong the 10th sprite's
+    //tile number is read as its Y, and the 11th sprite's attributes
+    let cases = [
+        //Nothing else on the line
+        (vec![(200, 0, 0, 0)], false),
+        //A 9th sprite, but it isn't the first one checked after the 8
+        (vec![(200, 0, 0, 0), (10, 0, 0, 0)], false),
+        //Nothing on the line but a tile number that looks like it
+        (vec![(200, 0, 0, 0), (200, 10, 0, 0)], true),
+        //Attributes lose bits 2-4, so it has to be a small line to look like one
+        (vec![(200, 0, 0, 0), (200, 0, 0, 0), (200, 0, 3, 0)], true)
+    ];
+    for (extra, overflow) in cases.iter() {
+        let (mut ppu, mut cart) = setup(0);
+        let mut line: Vec<_> = (0..8).map(|i| (10, 0, 0, i * 16)).collect();
+        line.extend(extra);
+        sprites(&mut ppu, &mut cart, &line);
+        scroll(&mut ppu, &mut cart, 0, 0, 0);
+        render(&mut ppu, &mut cart, SHOW_SPRITES);
+        assert_eq!(status_after_drawing(&mut ppu, &mut cart) & SPRITE_OVERFLOW != 0, *overflow, "{:?}", extra);
+    }
+}
+
+#[test]
+fn sprite_attribute_bytes_are_missing_bits_2_to_4() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    sprites(&mut ppu, &mut cart, &[(0xFF, 0xFF, 0xFF, 0xFF)]);
+    for (address, byte) in [0xFF, 0xFF, 0b11100011, 0xFF].iter().enumerate() {
+        ppu.cpu_write(0x2003, address as u8, &mut cart);
+        assert_eq!(ppu.cpu_read(0x2004, &mut cart), *byte);
+    }
+}
+
+#[test]
+fn sprite_zero_hits_where_it_overlaps_the_background() {
+    //(sprite 0's X, attributes, mask, hit)
+    let cases = [
+        (16, 0, SHOW_BACKGROUND | SHOW_SPRITES, true),
+        //Behind the background still counts
+        (16, BEHIND_BACKGROUND, SHOW_BACKGROUND | SHOW_SPRITES, true),
+        //Over a blank bit of background
+        (40, 0, SHOW_BACKGROUND | SHOW_SPRITES, false),
+        //Needs both turned on
+        (16, 0, SHOW_SPRITES, false),
+        (16, 0, SHOW_BACKGROUND, false),
+        //Not in a hidden left column
+        (0, 0, SHOW_BACKGROUND | SHOW_SPRITES, true),
+        (0, 0, SHOW_BACKGROUND | SHOW_SPRITES & !SHOW_LEFT_SPRITES, false),
+        (0, 0, SHOW_BACKGROUND & !SHOW_LEFT_BACKGROUND | SHOW_SPRITES, false),
+        //Not on the last column of the screen
+        (255, 0, SHOW_BACKGROUND | SHOW_SPRITES, false),
+        (254, 0, SHOW_BACKGROUND | SHOW_SPRITES, true)
+    ];
+    for &(x, attributes, mask, hit) in cases.iter() {
+        let (mut ppu, mut cart) = setup(0);
+        //Tile 1 across the first 3 columns of the second row and the last one
+        write_vram(&mut ppu, &mut cart, 0x2020, &[1, 1, 1]);
+        write_vram(&mut ppu, &mut cart, 0x203F, &[1]);
+        sprites(&mut ppu, &mut cart, &[(8, 1, attributes, x)]);
+        scroll(&mut ppu, &mut cart, 0, 0, 0);
+        render(&mut ppu, &mut cart, mask);
+        assert_eq!(status_after_drawing(&mut ppu, &mut cart) & SPRITE_ZERO_HIT != 0, hit,
+            "X {} attributes {:02X} mask {:02X}", x, attributes, mask);
+    }
+}
+
+#[test]
+fn only_sprite_zero_hits() {
+    let (mut ppu, mut cart) = setup(0);
+    write_vram(&mut ppu, &mut cart, 0x2020, &[1]);
+    sprites(&mut ppu, &mut cart, &[(100, 1, 0, 100), (8, 1, 0, 0)]);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND | SHOW_SPRITES);
+    assert_eq!(status_after_drawing(&mut ppu, &mut cart) & SPRITE_ZERO_HIT, 0);
+}
+
+#[test]
+fn sprite_flags_are_cleared_for_the_next_frame() {
+    let (mut ppu, mut cart) = setup(0);
+    write_vram(&mut ppu, &mut cart, 0x2020, &[1]);
+    let line: Vec<_> = (0..9).map(|i| (8, 1, 0, i * 16)).collect();
+    sprites(&mut ppu, &mut cart, &line);
+    scroll(&mut ppu, &mut cart, 0, 0, 0);
+    render(&mut ppu, &mut cart, SHOW_BACKGROUND | SHOW_SPRITES);
+    assert_eq!(status_after_drawing(&mut ppu, &mut cart) & (SPRITE_ZERO_HIT | SPRITE_OVERFLOW), SPRITE_ZERO_HIT | SPRITE_OVERFLOW);
+
+    //Still set through VBlank, then gone on the pre-render line
+    run_to(&mut ppu, &mut cart, (261, 1));
+    assert_eq!(ppu.peek_register(0x2002) & (SPRITE_ZERO_HIT | SPRITE_OVERFLOW), SPRITE_ZERO_HIT | SPRITE_OVERFLOW);
+    ppu.clock(&mut cart);
+    assert_eq!(ppu.peek_register(0x2002) & (SPRITE_ZERO_HIT | SPRITE_OVERFLOW), 0);
+}
+
+#[test]
+fn vblank_lasts_from_line_241_to_the_pre_render_line() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    run_to(&mut ppu, &mut cart, (241, 1));
+    assert_eq!(ppu.peek_register(0x2002) & VBLANK, 0);
+    ppu.clock(&mut cart);
+    assert_eq!(ppu.peek_register(0x2002) & VBLANK, VBLANK);
+
+    run_to(&mut ppu, &mut cart, (261, 1));
+    assert_eq!(ppu.peek_register(0x2002) & VBLANK, VBLANK);
+    ppu.clock(&mut cart);
+    assert_eq!(ppu.peek_register(0x2002) & VBLANK, 0);
+}
+
+#[test]
+fn reading_status_ends_vblank_and_resets_the_write_latch() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    write_vram(&mut ppu, &mut cart, 0x2345, &[0x67]);
+    run_to(&mut ppu, &mut cart, (250, 0));
+
+    //Half an address, then start again
+    ppu.cpu_write(0x2006, 0x3F, &mut cart);
+    assert_eq!(ppu.cpu_read(0x2002, &mut cart) & VBLANK, VBLANK);
+    assert_eq!(ppu.cpu_read(0x2002, &mut cart) & VBLANK, 0);
+    assert_eq!(read_vram(&mut ppu, &mut cart, 0x2345), 0x67);
+}
+
+#[test]
+fn nmi_is_vblank_with_nmis_turned_on() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    run_to(&mut ppu, &mut cart, (250, 0));
+    assert!(!ppu.nmi_line());
+
+    //Turning NMIs on in the middle of VBlank gives one straight away
+    ppu.cpu_write(0x2000, NMI_ENABLE, &mut cart);
+    assert!(ppu.nmi_line());
+    ppu.cpu_read(0x2002, &mut cart);
+    assert!(!ppu.nmi_line());
+
+    run_to(&mut ppu, &mut cart, (241, 2));
+    assert!(ppu.nmi_line());
+    run_to(&mut ppu, &mut cart, (261, 2));
+    assert!(!ppu.nmi_line());
+}
+
+#[test]
+fn reading_status_just_before_vblank_cancels_it() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    ppu.cpu_write(0x2000, NMI_ENABLE, &mut cart);
+    run_to(&mut ppu, &mut cart, (241, 1));
+    assert_eq!(ppu.cpu_read(0x2002, &mut cart) & VBLANK, 0);
+
+    //Not for the whole frame
+    while ppu.position() != (261, 1) {
+        ppu.clock(&mut cart);
+        assert!(!ppu.nmi_line());
+        assert_eq!(ppu.peek_register(0x2002) & VBLANK, 0);
+    }
+
+    //But the next one is fine
+    run_to(&mut ppu, &mut cart, (241, 2));
+    assert!(ppu.nmi_line());
+}
+
+#[test]
+fn odd_frames_are_a_dot_short_with_rendering_on() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    assert_eq!(dots_in_frame(&mut ppu, &mut cart), 341 * 262);
+    assert_eq!(dots_in_frame(&mut ppu, &mut cart), 341 * 262);
+
+    ppu.cpu_write(0x2001, SHOW_BACKGROUND, &mut cart);
+    assert_eq!(dots_in_frame(&mut ppu, &mut cart), 341 * 262);
+    assert_eq!(dots_in_frame(&mut ppu, &mut cart), 341 * 262 - 1);
+    assert_eq!(dots_in_frame(&mut ppu, &mut cart), 341 * 262);
+}
+
+//An MMC3 cartridge with the IRQ counter set to reload from latch and IRQs on. Submapper 4 is the
+//NEC MMC3A, anything else the Sharp MMC3B/C
+fn mmc3(submapper: u8, latch: u8) -> Cartridge {
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 2, 1, 0x40, 0x08, submapper << 4, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(vec![0xEA; 2 * 16384 + 8192]);
+    let mut cart = Cartridge::from_bytes(&rom).unwrap();
+    cart.write(0xC000, latch);
+    cart.write(0xC001, 0);
+    cart.write(0xE001, 0);
+    cart
+}
+
+//Renders a frame, from the pre-render line to the end of the visible lines, with the background at $0000 and sprites at $1000, so A12
+//rises once a line when the sprite patterns are fetched. The mapper is clocked every 3 dots like the
+//CPU would. Returns the (scanline, dot) of every IRQ, each of which gets acknowledged straight away
+fn mmc3_irqs(cart: &mut Cartridge) -> Vec<(u16, u16)> {
+    let mut ppu = PPU::new();
+    run_to(&mut ppu, cart, (261, 0));
+    ppu.cpu_write(0x2000, SPRITE_TABLE_1000, cart);
+    ppu.cpu_write(0x2001, SHOW_BACKGROUND | SHOW_SPRITES, cart);
+
+    let mut irqs = Vec::new();
+    let mut dots = 0;
+    while ppu.position() != (240, 0) {
+        ppu.clock(cart);
+        dots += 1;
+        if dots % 3 == 0 {
+            cart.clock();
+        }
+        if cart.irq() {
+            irqs.push(ppu.position());
+            cart.write(0xE000, 0);
+            cart.write(0xE001, 0);
+        }
+    }
+    irqs
+}
+
+#[test]
+fn mmc3_counts_the_lines_the_ppu_renders() {
+    //Reloaded to 20 on the pre-render line, it hits 0 on line 19 and then every 21 lines after
+    let irqs = mmc3_irqs(&mut mmc3(0, 20));
+    let lines: Vec<u16> = irqs.iter().map(|&(scanline, _)| scanline).collect();
+    assert_eq!(lines, [19, 40, 61, 82, 103, 124, 145, 166, 187, 208, 229]);
+    //At the first sprite pattern fetch. The nametable fetches around the other seven don't count again
+    for &(scanline, dot) in irqs.iter() {
+        assert!((257..=264).contains(&dot), "IRQ on line {} dot {}", scanline, dot);
+    }
+}
+
+#[test]
+fn mmc3_with_a_latch_of_0_depends_on_the_revision() {
+    //Sharp fires on every line that clocks it, which is the pre-render line and the 240 visible ones
+    let irqs = mmc3_irqs(&mut mmc3(0, 0));
+    let lines: Vec<u16> = irqs.iter().map(|&(scanline, _)| scanline).collect();
+    let mut expected: Vec<u16> = (0..240).collect();
+    expected.insert(0, 261);
+    assert_eq!(lines, expected);
+
+    //NEC only fires for the reload
+    assert_eq!(mmc3_irqs(&mut mmc3(4, 0)).iter().map(|&(scanline, _)| scanline).collect::<Vec<_>>(), [261]);
+}
+
+#[test]
+fn mmc3_is_not_clocked_with_rendering_off() {
+    let mut cart = mmc3(0, 0);
+    let mut ppu = PPU::new();
+    for _ in 0..2 * 341 * 262 {
+        ppu.clock(&mut cart);
+        cart.clock();
+    }
+    assert!(!cart.irq());
+}
+
+#[test]
+fn the_cpu_takes_the_nmi_at_the_start_of_vblank() {
+    //Turns on NMIs and waits. The handler is at $8010
+    let mut nes = Nes::from_rom_bytes(&nrom(&[0xA9, NMI_ENABLE, 0x8D, 0x00, 0x20, 0x4C, 0x05, 0x80])).unwrap();
+
+    for _ in 0..3 {
+        while nes.cpu().registers().program_counter != 0x8010 {
+            nes.step_instruction().unwrap();
+        }
+        //The NMI sequence is 7 CPU cycles, and it might have had to wait for a JMP to finish
+        let (scanline, dot) = nes.cpu().bus().ppu().position();
+        assert_eq!(scanline, 241);
+        assert!(dot > 7 * 3 && dot <= 10 * 3 + 1, "NMI handler started on dot {}", dot);
+        nes.step_instruction().unwrap();
+    }
+}
+
+#[test]
+fn the_cpu_can_wait_for_vblank_by_polling_ppustatus() {
+    //BIT $2002 and BPL back to it until VBlank shows up, then INC $00 and go round again
+    let mut nes = Nes::from_rom_bytes(&nrom(&[0x2C, 0x02, 0x20, 0x10, 0xFB, 0xE6, 0x00, 0x4C, 0x00, 0x80])).unwrap();
+
+    for frame in 1..=3 {
+        while nes.cpu().registers().program_counter != 0x8005 {
+            nes.step_instruction().unwrap();
+        }
+        //The loop is 7 CPU cycles, and the BPL after the read is 2 more
+        let (scanline, dot) = nes.cpu().bus().ppu().position();
+        assert_eq!(scanline, 241);
+        assert!(dot > 1 && dot <= 1 + 9 * 3, "VBlank seen on dot {}", dot);
+        //Reading it ended VBlank
+        assert_eq!(nes.cpu().bus().ppu().peek_register(0x2002) & VBLANK, 0);
+
+        nes.step_instruction().unwrap();
+        assert_eq!(nes.cpu().bus().peek(0x0000), frame);
+    }
+}
+
+#[test]
+fn registers_repeat_every_8_bytes_up_to_4000() {
+    let mut nes = Nes::from_rom_bytes(&nrom(&[])).unwrap();
+    let bus = nes.cpu_mut().bus_mut();
+    //$2006 twice and $2007
+    bus.write(0x3FFE, 0x23);
+    bus.write(0x200E, 0x45);
+    bus.write(0x2F57, 0x67);
+
+    bus.write(0x2006, 0x23);
+    bus.write(0x2006, 0x45);
+    bus.read(0x3FFF);
+    assert_eq!(bus.read(0x2007), 0x67);
+}
+
+#[test]
+fn unconnected_addresses_read_back_the_last_value_on_the_data_bus() {
+    let mut nes = Nes::from_rom_bytes(&nrom(&[])).unwrap();
+    let bus = nes.cpu_mut().bus_mut();
+    bus.write(0x0010, 0x5A);
+    assert_eq!(bus.peek(0x4018), 0x5A);
+    assert_eq!(bus.read(0x4018), 0x5A);
+    //The joypad ports only drive the bottom bits
+    assert_eq!(bus.peek(0x4016), 0x40);
+    assert_eq!(bus.read(0x4016), 0x40);
+    bus.read(0x0011);
+    assert_eq!(bus.peek(0x401F), 0x00);
+    assert_eq!(bus.read(0x401F), 0x00);
+    //Or anywhere the cartridge doesn't answer
+    bus.write(0x0010, 0xA5);
+    for &address in [0x4020, 0x5000, 0x5FFF].iter() {
+        assert_eq!(bus.peek(address), 0xA5, "{:04X}", address);
+        assert_eq!(bus.read(address), 0xA5, "{:04X}", address);
+    }
+}
+
+#[test]
+fn write_only_registers_read_back_the_io_latch() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    ppu.cpu_write(0x2003, 0x5A, &mut cart);
+    for &register in [0x2000, 0x2001, 0x2003, 0x2005, 0x2006].iter() {
+        assert_eq!(ppu.cpu_read(register, &mut cart), 0x5A, "${:04X}", register);
+    }
+    //Even after writing to PPUSTATUS, which does nothing else
+    ppu.cpu_write(0x2002, 0xA5, &mut cart);
+    assert_eq!(ppu.cpu_read(0x2000, &mut cart), 0xA5);
+}
+
+#[test]
+fn reads_only_drive_the_bits_they_have() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    run_to(&mut ppu, &mut cart, (250, 0));
+    //PPUSTATUS only has the top 3 bits, and puts them in the latch
+    ppu.cpu_write(0x2000, 0x1F, &mut cart);
+    assert_eq!(ppu.cpu_read(0x2002, &mut cart), VBLANK | 0x1F);
+    assert_eq!(ppu.cpu_read(0x2000, &mut cart), VBLANK | 0x1F);
+
+    //Palette entries only have 6 bits
+    write_vram(&mut ppu, &mut cart, 0x3F00, &[0x2D]);
+    set_address(&mut ppu, &mut cart, 0x3F00);
+    ppu.cpu_write(0x2000, 0xFF, &mut cart);
+    assert_eq!(ppu.cpu_read(0x2007, &mut cart), 0xC0 | 0x2D);
+}
+
+#[test]
+fn peeking_a_register_sees_what_reading_it_would() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    run_to(&mut ppu, &mut cart, (250, 0));
+    write_vram(&mut ppu, &mut cart, 0x2000, &[0x11, 0x22]);
+    write_vram(&mut ppu, &mut cart, 0x3F00, &[0x2D]);
+    sprites(&mut ppu, &mut cart, &[(0x33, 0, 0, 0)]);
+    ppu.cpu_write(0x2003, 0, &mut cart);
+
+    //Through the read buffer, then straight from the palette
+    set_address(&mut ppu, &mut cart, 0x2000);
+    ppu.cpu_read(0x2007, &mut cart);
+    ppu.cpu_write(0x2000, 0xFF, &mut cart);
+    for &address in [0x2007, 0x2007].iter() {
+        let peeked = ppu.peek_register(address);
+        assert_eq!(ppu.peek_register(address), peeked);
+        assert_eq!(ppu.cpu_read(address, &mut cart), peeked);
+    }
+    set_address(&mut ppu, &mut cart, 0x3F00);
+    ppu.cpu_write(0x2000, 0xFF, &mut cart);
+    let peeked = ppu.peek_register(0x2007);
+    assert_eq!(ppu.cpu_read(0x2007, &mut cart), peeked);
+
+    //PPUSTATUS last, since reading it ends VBlank
+    ppu.cpu_write(0x2000, 0x5A, &mut cart);
+    for &register in [0x2000, 0x2001, 0x2003, 0x2004, 0x2005, 0x2006, 0x2002].iter() {
+        let peeked = ppu.peek_register(register);
+        assert_eq!(ppu.peek_register(register), peeked, "${:04X}", register);
+        assert_eq!(ppu.cpu_read(register, &mut cart), peeked, "${:04X}", register);
+    }
+    //VBlank is over and the bottom bits are still from reading OAM
+    assert_eq!(ppu.peek_register(0x2002), 0x33 & 0x1F);
+}
+
+#[test]
+fn the_io_latch_fades_if_it_is_not_refreshed() {
+    let mut ppu = PPU::new();
+    let mut cart = cartridge(0);
+    ppu.cpu_write(0x2000, 0xFF, &mut cart);
+    for _ in 0..10 {
+        dots_in_frame(&mut ppu, &mut cart);
+    }
+    assert_eq!(ppu.cpu_read(0x2000, &mut cart), 0xFF);
+
+    //Reading PPUSTATUS keeps the top 3 bits going
+    for _ in 0..50 {
+        dots_in_frame(&mut ppu, &mut cart);
+        ppu.cpu_read(0x2002, &mut cart);
+    }
+    assert_eq!(ppu.cpu_read(0x2000, &mut cart) & 0x1F, 0);
+
+    for _ in 0..60 {
+        dots_in_frame(&mut ppu, &mut cart);
+    }
+    assert_eq!(ppu.cpu_read(0x2000, &mut cart), 0);
+}
+
+#[test]
+#[ignore = "needs blargg's ROMs in res/blargg/ppu_vbl_nmi"]
+fn blargg_ppu_vbl_nmi() {
+    let roms = ["01-vbl_basics.nes", "02-vbl_set_time.nes", "03-vbl_clear_time.nes", "04-nmi_control.nes",
+        "05-nmi_timing.nes", "06-suppression.nes", "07-nmi_on_timing.nes", "08-nmi_off_timing.nes",
+        "09-even_odd_frames.nes", "10-even_odd_timing.nes"];
+    for name in roms.iter() {
+        let (status, message) = run_blargg(&format!("ppu_vbl_nmi/{}", name));
+        assert_eq!(status, 0, "{}: {}", name, message);
+    }
+}
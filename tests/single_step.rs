@@ -0,0 +1,214 @@
+//Runs the per-opcode SingleStepTests (github.com/SingleStepTests/65x02) against the CPU on flat RAM.
+//Each opcode has a JSON file of tests giving the state before and after one instruction and every
+//bus access in between. The tests aren't in this repository, so this is ignored by default. Put a
+//checkout at res/ProcessorTests or point SINGLE_STEP_TESTS at one, then run
+//cargo test --release --test single_step -- --ignored
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde_json::Value;
+
+use nes_emulator::Bus::{Bus, FlatRAM};
+use nes_emulator::CPU6502::{CPU6502, Registers, StatusFlags, Variant};
+
+const DEFAULT_ROOT: &str = "res/ProcessorTests";
+//(directory under the root, variant)
+const SUITES: [(&str, Variant); 3] = [
+    ("nes6502/v1", Variant::Ricoh2A03),
+    ("6502/v1", Variant::Nmos6502),
+    ("wdc65c02/v1", Variant::Wdc65C02)
+];
+//How many failing tests to describe in full per suite
+const REPORTED_FAILURES: usize = 10;
+//B and U aren't real bits in the status register, so they're left out of comparisons
+const STATUS_MASK: u8 = !(StatusFlags::BRK.bits() | StatusFlags::UNUSED.bits());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write
+}
+
+//Flat RAM that remembers every access
+struct RecordingRAM {
+    ram: FlatRAM,
+    cycles: Vec<(u16, u8, Access)>
+}
+
+impl Bus for RecordingRAM {
+    fn read(&mut self, address: u16) -> u8 {
+        let data = self.ram.read(address);
+        self.cycles.push((address, data, Access::Read));
+        data
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        self.ram.write(address, data);
+        self.cycles.push((address, data, Access::Write));
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        self.ram.peek(address)
+    }
+}
+
+//The registers and the bits of RAM a test cares about
+struct State {
+    registers: Registers,
+    ram: Vec<(u16, u8)>
+}
+
+fn number(value: &Value) -> u64 {
+    value.as_u64().unwrap_or_else(|| panic!("expected a number, got {}", value))
+}
+
+fn parse_state(value: &Value) -> State {
+    let registers = Registers {
+        program_counter: number(&value["pc"]) as u16,
+        stack_pointer: number(&value["s"]) as u8,
+        accumulator: number(&value["a"]) as u8,
+        reg_x: number(&value["x"]) as u8,
+        reg_y: number(&value["y"]) as u8,
+        status: number(&value["p"]) as u8
+    };
+    let ram = value["ram"].as_array().expect("ram should be an array").iter()
+        .map(|entry| (number(&entry[0]) as u16, number(&entry[1]) as u8))
+        .collect();
+    State { registers, ram }
+}
+
+fn parse_cycles(value: &Value) -> Vec<(u16, u8, Access)> {
+    value.as_array().expect("cycles should be an array").iter()
+        .map(|cycle| {
+            let access = match cycle[2].as_str() {
+                Some("read") => Access::Read,
+                Some("write") => Access::Write,
+                other => panic!("unknown bus access {:?}", other)
+            };
+            (number(&cycle[0]) as u16, number(&cycle[1]) as u8, access)
+        })
+        .collect()
+}
+
+//Runs one test, returning a description of everything that came out wrong
+fn run_test(cpu: &mut CPU6502<RecordingRAM>, test: &Value) -> Vec<String> {
+    let initial = parse_state(&test["initial"]);
+    let expected = parse_state(&test["final"]);
+    let expected_cycles = parse_cycles(&test["cycles"]);
+
+    //A reset gets the CPU out of anything the last test left it in, like WAI
+    cpu.reset();
+    cpu.step().unwrap();
+    for &(address, data) in initial.ram.iter() {
+        cpu.bus_mut().ram.write(address, data);
+    }
+    cpu.set_registers(initial.registers);
+    cpu.bus_mut().cycles.clear();
+
+    let mut mismatches = Vec::new();
+    if let Err(e) = cpu.step() {
+        mismatches.push(e.to_string());
+    }
+
+    let got = cpu.registers();
+    let want = expected.registers;
+    let registers = [
+        ("PC", want.program_counter, got.program_counter),
+        ("S", want.stack_pointer as u16, got.stack_pointer as u16),
+        ("A", want.accumulator as u16, got.accumulator as u16),
+        ("X", want.reg_x as u16, got.reg_x as u16),
+        ("Y", want.reg_y as u16, got.reg_y as u16),
+        ("P", (want.status & STATUS_MASK) as u16, (got.status & STATUS_MASK) as u16)
+    ];
+    for &(name, want, got) in registers.iter() {
+        if want != got {
+            mismatches.push(format!("{}: want {:02X} got {:02X}", name, want, got));
+        }
+    }
+
+    for &(address, want) in expected.ram.iter() {
+        let got = cpu.bus().peek(address);
+        if want != got {
+            mismatches.push(format!("${:04X}: want {:02X} got {:02X}", address, want, got));
+        }
+    }
+
+    let got_cycles = &cpu.bus().cycles;
+    for i in 0..expected_cycles.len().max(got_cycles.len()) {
+        let want = expected_cycles.get(i);
+        let got = got_cycles.get(i);
+        if want != got {
+            let show = |cycle: Option<&(u16, u8, Access)>| match cycle {
+                Some((address, data, access)) => format!("{:?} {:02X} at ${:04X}", access, data, address),
+                None => "nothing".to_string()
+            };
+            mismatches.push(format!("cycle {}: want {} got {}", i + 1, show(want), show(got)));
+        }
+    }
+
+    //Leave RAM clean for the next test
+    for &(address, _) in initial.ram.iter().chain(expected.ram.iter()) {
+        cpu.bus_mut().ram.write(address, 0);
+    }
+    mismatches
+}
+
+//Runs every opcode in a suite, printing a summary. Returns how many tests failed
+fn run_suite(directory: &str, variant: Variant) -> usize {
+    if let Err(e) = fs::metadata(directory) {
+        panic!("no tests at {}: {}", directory, e);
+    }
+
+    let ram = RecordingRAM { ram: FlatRAM::new(), cycles: Vec::new() };
+    let mut cpu = CPU6502::with_variant(ram, variant);
+    //(passed, failed) for each opcode
+    let mut results: BTreeMap<u8, (usize, usize)> = BTreeMap::new();
+    let mut reports = Vec::new();
+
+    for opcode in 0..=0xFF {
+        let path = format!("{}/{:02x}.json", directory, opcode);
+        let tests: Value = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", path, e)),
+            Err(_) => continue
+        };
+
+        let counts = results.entry(opcode).or_insert((0, 0));
+        for test in tests.as_array().expect("a test file should be an array") {
+            let mismatches = run_test(&mut cpu, test);
+            if mismatches.is_empty() {
+                counts.0 += 1;
+                continue;
+            }
+            counts.1 += 1;
+            if reports.len() < REPORTED_FAILURES {
+                reports.push(format!("{}:\n    {}", test["name"].as_str().unwrap_or("?"), mismatches.join("\n    ")));
+            }
+        }
+    }
+
+    let failing: Vec<String> = results.iter()
+        .filter(|(_, &(_, failed))| failed != 0)
+        .map(|(opcode, &(passed, failed))| format!("{:02X}: {}/{}", opcode, passed, passed + failed))
+        .collect();
+    let failed: usize = results.values().map(|&(_, failed)| failed).sum();
+    let total: usize = results.values().map(|&(passed, failed)| passed + failed).sum();
+
+    println!("{}: {}/{} opcodes pass, {} of {} tests fail", directory, results.len() - failing.len(), results.len(), failed, total);
+    if !failing.is_empty() {
+        println!("failing opcodes (passed/run):\n  {}", failing.join("\n  "));
+        println!("first failures:\n  {}", reports.join("\n  "));
+    }
+    failed
+}
+
+#[test]
+#[ignore = "needs a SingleStepTests checkout, see the top of this file"]
+fn single_step_tests() {
+    let root = std::env::var("SINGLE_STEP_TESTS").unwrap_or_else(|_| DEFAULT_ROOT.to_string());
+    let mut failed = 0;
+    for &(suite, variant) in SUITES.iter() {
+        failed += run_suite(&format!("{}/{}", root, suite), variant);
+    }
+    assert_eq!(failed, 0, "some SingleStepTests failed, run with --nocapture for details");
+}